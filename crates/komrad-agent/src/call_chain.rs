@@ -0,0 +1,63 @@
+use komrad_ast::prelude::{Channel, RuntimeError};
+use std::future::Future;
+
+tokio::task_local! {
+    /// The agents waiting, in order, on the handler that's running.
+    static CALL_CHAIN: Vec<Channel>;
+}
+
+/// Runs a handler for a message, with the handling agent added to the
+/// agents already waiting on the message's reply.
+pub async fn handling<F: Future>(message_chain: &[Channel], me: Channel, handler: F) -> F::Output {
+    let mut chain = message_chain.to_vec();
+    chain.push(me);
+    CALL_CHAIN.scope(chain, handler).await
+}
+
+/// The chain for a call made by the running handler; empty outside one.
+pub fn current() -> Vec<Channel> {
+    CALL_CHAIN.try_with(Clone::clone).unwrap_or_default()
+}
+
+/// Fails if `target` is already waiting on `chain`: it couldn't handle the
+/// call until the call had been answered.
+pub fn check(chain: &[Channel], target: &Channel) -> Result<(), RuntimeError> {
+    match chain.iter().position(|waiting| waiting == target) {
+        Some(start) => {
+            let cycle: Vec<String> = chain[start..].iter().chain([target]).map(name).collect();
+            Err(RuntimeError::Deadlock(cycle.join(" -> ")))
+        }
+        None => Ok(()),
+    }
+}
+
+fn name(channel: &Channel) -> String {
+    match channel.agent_type() {
+        Some(agent_type) => agent_type.to_string(),
+        None => channel.uuid().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_check_names_the_cycle() {
+        let (a, _a) = Channel::new(1);
+        let (b, _b) = Channel::new(1);
+        let (a, b) = (a.with_agent_type("A"), b.with_agent_type("B"));
+
+        assert!(check(&current(), &a).is_ok());
+        let chain = handling(&[a.clone()], b.clone(), async { current() }).await;
+        assert_eq!(chain, vec![a.clone(), b.clone()]);
+        assert_eq!(
+            check(&chain, &a),
+            Err(RuntimeError::Deadlock("A -> B -> A".to_string()))
+        );
+        assert_eq!(
+            check(&chain, &b),
+            Err(RuntimeError::Deadlock("B -> B".to_string()))
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use async_trait::async_trait;
-use komrad_ast::prelude::{BinaryExpr, Block, CallExpr, Expr, Statement, Value};
+use komrad_ast::prelude::{BinaryExpr, Block, CallExpr, Expr, Statement, UnaryExpr, Value};
 use komrad_ast::scope::Scope;
+use std::collections::HashSet;
 
 #[async_trait]
 pub trait Closure {
@@ -25,7 +26,7 @@ impl Closure for Block {
             let _ = statement.closure(context).await;
         }
 
-        Value::Block(Block::new(new_statements).into())
+        Value::Block(self.with_statements(new_statements).into())
     }
 }
 
@@ -56,6 +57,38 @@ impl Closure for Statement {
                 }
             }
             Statement::Expander(expr) => Statement::Expander(expr.closure(context).await),
+            Statement::Try(body, name, handler) => {
+                let mut new_body = Vec::new();
+                for stmt in body.statements() {
+                    new_body.push(stmt.closure(context).await);
+                }
+                let mut new_handler = Vec::new();
+                for stmt in handler.statements() {
+                    new_handler.push(stmt.closure(context).await);
+                }
+                Statement::Try(
+                    body.with_statements(new_body),
+                    name.clone(),
+                    handler.with_statements(new_handler),
+                )
+            }
+            Statement::While(condition, body) => {
+                // Names the loop assigns must be looked up on each
+                // iteration, not fixed to their value before the loop
+                let mut context = context.without(&assigned_names(body));
+                let mut new_condition = Vec::new();
+                for stmt in condition.statements() {
+                    new_condition.push(stmt.closure(&mut context).await);
+                }
+                let mut new_body = Vec::new();
+                for stmt in body.statements() {
+                    new_body.push(stmt.closure(&mut context).await);
+                }
+                Statement::While(
+                    condition.with_statements(new_condition),
+                    body.with_statements(new_body),
+                )
+            }
         }
     }
 }
@@ -75,6 +108,13 @@ impl Closure for Expr {
                 }
                 Expr::List(new_list)
             }
+            Expr::Map(entries) => {
+                let mut new_entries = Vec::new();
+                for (key, value) in entries {
+                    new_entries.push((key.closure(context).await, value.closure(context).await));
+                }
+                Expr::Map(new_entries)
+            }
             Expr::Variable(name) => {
                 if let Some(val) = context.get(name) {
                     Expr::Value(val)
@@ -87,10 +127,11 @@ impl Closure for Expr {
                 for stmt in block.statements() {
                     new_stmts.push(stmt.closure(context).await);
                 }
-                Expr::Block(Box::new(Block::new(new_stmts)))
+                Expr::Block(Box::new(block.with_statements(new_stmts)))
             }
             Expr::Call(call) => Expr::Call(call.closure(context).await),
             Expr::Binary(bexpr) => Expr::Binary(bexpr.closure(context).await),
+            Expr::Unary(uexpr) => Expr::Unary(uexpr.closure(context).await),
             Expr::Value(_) => self.clone(),
         }
     }
@@ -109,6 +150,17 @@ impl Closure for BinaryExpr {
     }
 }
 
+#[async_trait]
+impl Closure for UnaryExpr {
+    type Input = UnaryExpr;
+    type Output = UnaryExpr;
+    type Context = Scope;
+
+    async fn closure(&self, context: &mut Self::Context) -> Self::Output {
+        UnaryExpr::new(self.op.clone(), self.expr().closure(context).await)
+    }
+}
+
 #[async_trait]
 impl Closure for CallExpr {
     type Input = CallExpr;
@@ -121,6 +173,26 @@ impl Closure for CallExpr {
         for arg in self.args() {
             new_args.push(Box::new(arg.closure(context).await));
         }
-        CallExpr::new(target, new_args)
+        CallExpr::new(target, new_args).with_reply_timeout(self.reply_timeout())
+    }
+}
+
+/// The names a block assigns, including in the blocks of nested `try`s
+/// and `while`s.
+fn assigned_names(block: &Block) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for statement in block.statements() {
+        match statement {
+            Statement::Assignment(name, _) | Statement::Field(name, _, _) => {
+                names.insert(name.clone());
+            }
+            Statement::Try(body, _, handler) => {
+                names.extend(assigned_names(body));
+                names.extend(assigned_names(handler));
+            }
+            Statement::While(_, body) => names.extend(assigned_names(body)),
+            _ => {}
+        }
     }
+    names
 }
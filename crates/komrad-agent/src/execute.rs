@@ -1,12 +1,14 @@
+use crate::call_chain;
 use crate::closure::Closure;
-use crate::stdlib_agent::ListAgent;
-use crate::AgentBehavior;
+use crate::list_ops;
+use crate::value_call::call_value;
 use async_trait::async_trait;
 use komrad_ast::prelude::{
-    BinaryExpr, BinaryOp, Block, CallExpr, Channel, Expr, Message, RuntimeError, Statement,
-    ToSexpr, Typed, Value,
+    BinaryExpr, BinaryOp, Block, CallExpr, Channel, Expr, Map, Message, MessageBuilder,
+    RuntimeError, Statement, ToSexpr, UnaryExpr, UnaryOp, Value,
 };
 use komrad_ast::scope::Scope;
+use miette::Report;
 use tracing::{debug, error, info};
 // TODO
 // pub enum ExecutionResult<T, E> {
@@ -41,23 +43,28 @@ impl Execute for Block {
     async fn execute(&self, scope: &mut Self::Context) -> Self::Output {
         let mut last_value = Value::Empty;
 
-        for statement in self.statements() {
+        for (index, statement) in self.statements().iter().enumerate() {
             match statement {
                 Statement::NoOp | Statement::Comment(_) => {
                     // Skip no-op and comment statements
                     continue;
                 }
                 _ => {
+                    // Let go of the last value first, so that it doesn't
+                    // share a list the statement changes
+                    drop(std::mem::replace(&mut last_value, Value::Empty));
                     last_value = statement.execute(scope).await;
                 }
             }
-            if let Value::Boolean(b) = last_value {
-                error!("Boolean value: {:}", b);
-            }
-            if let Value::Error(_) = last_value {
-                error!("{:} -> {:}", statement.to_sexpr().format(0), last_value);
-                // TODO: debateable whether to break. We don't have error
-                //       handling constructs yet, so break for now.
+            if let Value::Error(err) = &last_value {
+                if !is_caught() {
+                    match self.location(index) {
+                        Some(location) => error!("{:?}", Report::new(err.clone().at(location))),
+                        None => error!("{:} -> {:}", statement.to_sexpr().format(0), last_value),
+                    }
+                }
+                // Stop at the first error; `try { ... } catch _err { ... }`
+                // is how a block recovers from one.
                 break;
             }
         }
@@ -66,6 +73,23 @@ impl Execute for Block {
     }
 }
 
+tokio::task_local! {
+    /// Set while running code whose errors are handled elsewhere.
+    static CAUGHT: ();
+}
+
+/// Runs `work` without reporting the errors it stops at, because they are
+/// handled elsewhere: by the `catch` of a `try`, or by a caller waiting for
+/// the reply of a handler. Errors nobody handles are reported where they
+/// stop a block.
+pub async fn catching<F: Future>(work: F) -> F::Output {
+    CAUGHT.scope((), work).await
+}
+
+fn is_caught() -> bool {
+    CAUGHT.try_with(|_| ()).is_ok()
+}
+
 #[async_trait]
 impl Execute for Statement {
     type Output = Value;
@@ -75,13 +99,16 @@ impl Execute for Statement {
         match self {
             Statement::Assignment(name, expr) => match expr {
                 Expr::Call(call) => {
-                    let value = call.execute_with_reply(scope).await;
-                    scope.set(name.clone(), value.clone()).await;
+                    let value = match update_list(call, name, scope).await {
+                        Some(value) => value,
+                        None => call.execute_with_reply(scope).await,
+                    };
+                    scope.assign(name.clone(), value.clone()).await;
                     return value;
                 }
                 _ => {
                     let value = expr.execute(scope).await;
-                    scope.set(name.clone(), value.clone()).await;
+                    scope.assign(name.clone(), value.clone()).await;
                     value
                 }
             },
@@ -89,25 +116,52 @@ impl Execute for Statement {
             Statement::NoOp => Value::Empty,
             Statement::Comment(_comment_text) => Value::Empty,
             Statement::Handler(_handler) => Value::Empty,
-            Statement::Field(name, typ, expr) => {
-                if let Some(expr) = expr {
-                    // If a non-default value was provided for the field, use that.
-                    let value = match scope.get(name) {
-                        Some(value) => value.clone(),
-                        None => expr.execute(scope).await,
-                    };
-                    let value_type = value.get_type_expr();
-                    if !value_type.is_subtype_of(typ) {
-                        return Value::Error(RuntimeError::TypeMismatch(format!(
-                            "Expected type {:?}, found {:?}",
-                            typ, value_type
+            Statement::Try(body, name, handler) => match catching(body.execute(scope)).await {
+                error @ Value::Error(_) => {
+                    debug!("caught {:} as {}", error, name);
+                    scope.set(name.clone(), error).await;
+                    handler.execute(scope).await
+                }
+                value => value,
+            },
+            Statement::While(condition, body) => loop {
+                match condition.execute(scope).await {
+                    Value::Boolean(true) => {}
+                    Value::Boolean(false) => break Value::Empty,
+                    Value::Error(err) => break Value::Error(err),
+                    other => {
+                        break Value::Error(RuntimeError::TypeMismatch(format!(
+                            "Expected a Boolean from the while condition, found {}",
+                            other.get_type()
                         )));
                     }
-                    scope.set(name.clone(), value.clone()).await;
-                    value
-                } else {
-                    Value::Empty
                 }
+                if let error @ Value::Error(_) = body.execute(scope).await {
+                    break error;
+                }
+                // Let other agents run between iterations of a long loop
+                tokio::task::yield_now().await;
+            },
+            Statement::Field(name, typ, expr) => {
+                // If a non-default value was provided for the field, use that.
+                let value = match (scope.get(name), expr) {
+                    (Some(value), _) => value.clone(),
+                    (None, Some(expr)) => expr.execute(scope).await,
+                    (None, None) => {
+                        // Bound anyway, so that assigning it later sets the field
+                        scope.set(name.clone(), Value::Empty).await;
+                        return Value::Empty;
+                    }
+                };
+                if !typ.accepts(&value) {
+                    return Value::Error(RuntimeError::TypeMismatch(format!(
+                        "Expected type {:?}, found {:?}",
+                        typ,
+                        value.get_type_expr()
+                    )));
+                }
+                scope.set(name.clone(), value.clone()).await;
+                value
             }
             Statement::Expander(expr) => {
                 let name = expr.execute(scope).await;
@@ -128,81 +182,16 @@ impl Execute for Statement {
                         // If an actual block is provided, execute it directly
                         block.execute(scope).await
                     }
-                    Value::List(list) => {
-                        // If a list is provided, treat it as a call
-                        if let Some(Value::Channel(target)) = list.get(0) {
-                            let mut args = Vec::new();
-                            for arg in list.iter().skip(1) {
-                                args.push(Expr::Value(arg.clone()).into());
-                            }
-                            let target = Expr::Value(Value::Channel(target.clone())).into();
-                            let call = CallExpr::new(target, args);
-                            call.execute_with_reply(scope).await
-                        } else {
-                            Value::Error(RuntimeError::TypeMismatch(format!(
-                                "Expected a channel or word, found {:?}",
-                                list.get(0)
-                            )))
-                        }
-                    }
+                    Value::List(list) => expand_list(&list, scope).await,
                     Value::Channel(channel) => {
-                        // assume it has the List protocol, call `items` to get the value list
-                        let (reply_chan, reply_chan_listener) = Channel::new(1);
-                        let message = Message::new(
-                            vec![Value::Word("items".to_string())],
-                            Some(reply_chan.clone()),
-                        );
-
-                        let items = match channel.send(message).await {
-                            Ok(_) => {
-                                // Wait for the reply
-                                let message = reply_chan_listener.recv().await;
-                                match message {
-                                    Ok(msg) => {
-                                        assert_eq!(
-                                            msg.terms().len(),
-                                            1,
-                                            "Expected a single term in reply"
-                                        );
-                                        msg.terms().get(0).unwrap().clone()
-                                    }
-                                    Err(_) => {
-                                        // Handle receive error
-                                        error!("Failed to receive message");
-                                        Value::Error(RuntimeError::ReceiveError)
-                                    }
-                                }
-                            }
-                            Err(_) => {
-                                // Handle send error
-                                error!("Failed to send message");
-                                Value::Error(RuntimeError::SendError)
-                            }
-                        };
-
-                        // Convert the items to a call
-                        if let Value::List(list) = items {
-                            // If the first item is a channel, treat it as a call
-                            if let Some(Value::Channel(target)) = list.get(0) {
-                                let mut args = Vec::new();
-                                for arg in list.iter().skip(1) {
-                                    args.push(Expr::Value(arg.clone()).into());
-                                }
-                                let target = Expr::Value(Value::Channel(target.clone())).into();
-                                let call = CallExpr::new(target, args);
-                                return call.execute_with_reply(scope).await;
-                            } else {
-                                // If the first item is not a channel, return an error
-                                Value::Error(RuntimeError::TypeMismatch(format!(
-                                    "Expected a channel, found {:?}",
-                                    list.get(0)
-                                )))
-                            }
-                        } else {
-                            Value::Error(RuntimeError::TypeMismatch(format!(
+                        // A shared list: ask it for its items
+                        match list_ops::items_of(&Value::Channel(channel)).await {
+                            Value::List(list) => expand_list(&list, scope).await,
+                            Value::Error(err) => Value::Error(err),
+                            items => Value::Error(RuntimeError::TypeMismatch(format!(
                                 "Expected a list, found {:?}",
                                 items
-                            )))
+                            ))),
                         }
                     }
                     _ => Value::Error(RuntimeError::TypeMismatch(format!(
@@ -215,6 +204,24 @@ impl Execute for Statement {
     }
 }
 
+/// Expands `[target arg ...]` into a call on `target`.
+async fn expand_list(list: &[Value], scope: &mut Scope) -> Value {
+    if let Some(Value::Channel(target)) = list.first() {
+        let mut args = Vec::new();
+        for arg in list.iter().skip(1) {
+            args.push(Expr::Value(arg.clone()).into());
+        }
+        let target = Expr::Value(Value::Channel(target.clone()));
+        let call = CallExpr::new(target, args);
+        call.execute_with_reply(scope).await
+    } else {
+        Value::Error(RuntimeError::TypeMismatch(format!(
+            "Expected a channel, found {:?}",
+            list.first()
+        )))
+    }
+}
+
 #[async_trait]
 impl Execute for Expr {
     type Output = Value;
@@ -227,12 +234,17 @@ impl Execute for Expr {
                 for item in list {
                     new_list.push(item.execute(scope).await);
                 }
-
-                // spawn a ListAgent with the list, return a channel
-                let list_agent = ListAgent::new(new_list);
-                let list_channel = list_agent.spawn();
-                // Return the channel as a value
-                Value::Channel(list_channel)
+                // A plain value; `List new` makes a list agents can share
+                Value::from(new_list)
+            }
+            Expr::Map(entries) => {
+                let mut map = Map::new();
+                for (key, value) in entries {
+                    let key = key.execute(scope).await;
+                    let value = value.execute(scope).await;
+                    map.insert(key, value);
+                }
+                Value::Map(map)
             }
             Expr::Value(val) => val.clone(),
             Expr::Variable(name) => {
@@ -245,6 +257,7 @@ impl Execute for Expr {
             }
 
             Expr::Binary(b) => b.execute(scope).await,
+            Expr::Unary(u) => u.execute(scope).await,
             Expr::Call(call) => call.execute(scope).await,
 
             Expr::Block(_block) => {
@@ -263,6 +276,24 @@ impl Execute for Expr {
     }
 }
 
+/// Runs `xs = xs add x` and the like without copying the list. The list
+/// is taken out of the scope while it changes, so that nothing else shares
+/// it; meanwhile `xs` is empty. Returns `None` unless `call` is sent to a
+/// list bound to `name`.
+async fn update_list(call: &CallExpr, name: &str, scope: &mut Scope) -> Option<Value> {
+    if call.target() != &Expr::Variable(name.to_string())
+        || !matches!(scope.get(name), Some(Value::List(_)))
+    {
+        return None;
+    }
+    let mut args = Vec::new();
+    for arg in call.args() {
+        args.push(arg.execute(scope).await);
+    }
+    let list = scope.take(name)?;
+    Some(call_value(list, args, scope).await)
+}
+
 #[async_trait]
 impl Execute for CallExpr {
     type Output = Value;
@@ -287,13 +318,13 @@ impl Execute for CallExpr {
                     return Value::Empty;
                 }
                 Err(_) => {
-                    // Handle send error
-                    error!("Failed to send message");
+                    // Reported by the block, unless it's caught
+                    debug!("Failed to send message");
                     return Value::Error(RuntimeError::SendError);
                 }
             }
         } else {
-            Value::Error(RuntimeError::SendError)
+            call_value(target, args, scope).await
         }
     }
 }
@@ -315,32 +346,39 @@ impl ExecuteWithReply for CallExpr {
         );
 
         if let Value::Channel(channel) = target {
+            // Waiting on an agent that's waiting on us would never end
+            let chain = call_chain::current();
+            if let Err(err) = call_chain::check(&chain, &channel) {
+                return Value::Error(err);
+            }
             let (reply_chan, reply_chan_rx) = Channel::new(1);
-            let message_with_reply_to = Message::new(args, Some(reply_chan.clone()));
+            let message_with_reply_to =
+                Message::new(args, Some(reply_chan.clone())).with_call_chain(chain);
             match channel.send(message_with_reply_to).await {
                 Ok(_) => {
-                    // Wait for the reply
-                    let message = reply_chan_rx.recv().await;
+                    // Wait for the reply, unless it takes longer than the timeout
+                    let timeout = self.reply_timeout().or_else(Channel::default_reply_timeout);
+                    let message = reply_chan_rx.recv_timeout(timeout).await;
                     match message {
                         Ok(msg) => {
                             assert_eq!(msg.terms().len(), 1, "Expected a single term in reply");
                             msg.terms().get(0).unwrap().clone()
                         }
-                        Err(_) => {
-                            // Handle receive error
-                            error!("Failed to receive message");
-                            Value::Error(RuntimeError::ReceiveError)
+                        Err(err) => {
+                            // Reported by the block, unless it's caught
+                            debug!("Failed to receive message: {}", err);
+                            Value::Error(err)
                         }
                     }
                 }
                 Err(_) => {
-                    // Handle send error
-                    error!("Failed to send message");
+                    // Reported by the block, unless it's caught
+                    debug!("Failed to send message");
                     Value::Error(RuntimeError::SendError)
                 }
             }
         } else {
-            Value::Error(RuntimeError::SendError)
+            call_value(target, args, scope).await
         }
     }
 }
@@ -357,7 +395,7 @@ impl Execute for BinaryExpr {
         match self.operator() {
             // Math
             BinaryOp::Add => match (left.clone(), right.clone()) {
-                (Value::Number(l), Value::Number(r)) => Value::Number(l + r),
+                (Value::Number(l), Value::Number(r)) => (l + r).into(),
                 (Value::String(l), Value::String(r)) => Value::String(format!("{}{}", l, r)),
                 (Value::String(l), Value::Channel(ch)) => {
                     Value::String(format!("{}{}", l, ch.uuid().to_string()))
@@ -388,38 +426,26 @@ impl Execute for BinaryExpr {
             },
             BinaryOp::Sub => {
                 if let (Value::Number(l), Value::Number(r)) = (left, right) {
-                    Value::Number(l - r)
+                    (l - r).into()
                 } else {
                     Value::Empty
                 }
             }
             BinaryOp::Mul => {
                 if let (Value::Number(l), Value::Number(r)) = (left, right) {
-                    Value::Number(l * r)
+                    (l * r).into()
                 } else {
                     Value::Empty
                 }
             }
             BinaryOp::Div => match (left, right) {
-                (Value::Number(l), Value::Number(r)) => {
-                    if !r.is_zero() {
-                        Value::Number(l / r)
-                    } else {
-                        Value::Error(RuntimeError::DivisionByZero)
-                    }
-                }
+                (Value::Number(l), Value::Number(r)) => (l / r).into(),
                 _ => Value::Empty,
             },
 
             // Mod
             BinaryOp::Mod => match (left, right) {
-                (Value::Number(l), Value::Number(r)) => {
-                    if !r.is_zero() {
-                        Value::Number(l % r)
-                    } else {
-                        Value::Error(RuntimeError::DivisionByZero)
-                    }
-                }
+                (Value::Number(l), Value::Number(r)) => (l % r).into(),
                 _ => Value::Empty,
             },
 
@@ -453,6 +479,35 @@ impl Execute for BinaryExpr {
                     Value::Boolean(false)
                 }
             }
+
+            // Relational
+            BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge => {
+                match left.partial_cmp(&right) {
+                    Some(ordering) => Value::Boolean(match self.operator() {
+                        BinaryOp::Lt => ordering.is_lt(),
+                        BinaryOp::Le => ordering.is_le(),
+                        BinaryOp::Gt => ordering.is_gt(),
+                        _ => ordering.is_ge(),
+                    }),
+                    None => Value::Error(RuntimeError::TypeMismatch(format!(
+                        "Cannot compare {:} with {:}",
+                        left, right
+                    ))),
+                }
+            }
+            BinaryOp::Divisible => match (left, right) {
+                (Value::Number(l), Value::Number(r)) => {
+                    if !r.is_zero() {
+                        Value::Boolean(l.is_divisible_by(&r))
+                    } else {
+                        Value::Error(RuntimeError::DivisionByZero)
+                    }
+                }
+                (left, right) => Value::Error(RuntimeError::TypeMismatch(format!(
+                    "Cannot test {:} for divisibility by {:}",
+                    left, right
+                ))),
+            },
             BinaryOp::Access => {
                 // Access operator e.g. `a.b` or `foo.bar`
                 match (left.clone(), right.clone()) {
@@ -463,6 +518,16 @@ impl Execute for BinaryExpr {
                             Err(_) => Value::Error(RuntimeError::NameNotFound(word)),
                         }
                     }
+                    (Value::Map(map), Value::Word(member)) => match map.get_field(&member) {
+                        Some(value) => value.clone(),
+                        None => Value::Error(RuntimeError::NameNotFound(member)),
+                    },
+                    (Value::Error(err), Value::Word(member)) => match member.as_str() {
+                        // Caught errors expose `err.kind` and `err.message`
+                        "kind" => Value::Word(err.kind().to_string()),
+                        "message" => Value::String(err.to_string()),
+                        _ => Value::Error(RuntimeError::NameNotFound(member)),
+                    },
                     (Value::Word(word), Value::Word(member)) => {
                         // left is a word, right is a word
                         if let Some(value) = scope.get(word.as_str()) {
@@ -470,6 +535,9 @@ impl Execute for BinaryExpr {
                                 Value::Channel(channel) => {
                                     channel.get(member.as_str()).await.unwrap_or(Value::Empty)
                                 }
+                                Value::Map(map) => {
+                                    map.get_field(&member).cloned().unwrap_or(Value::Empty)
+                                }
                                 _ => Value::Error(RuntimeError::TypeMismatch(format!(
                                     "Expected a channel, found {:?}",
                                     value
@@ -480,7 +548,7 @@ impl Execute for BinaryExpr {
                         }
                     }
                     (_, _) => Value::Error(RuntimeError::TypeMismatch(format!(
-                        "Expected a channel or word, found {:?} {:?}",
+                        "Expected a channel, map or word, found {:?} {:?}",
                         left, right
                     ))),
                 }
@@ -489,6 +557,37 @@ impl Execute for BinaryExpr {
     }
 }
 
+#[async_trait]
+impl Execute for UnaryExpr {
+    type Output = Value;
+    type Context = Scope;
+
+    /// `++x` and `--x` rebind a variable operand and evaluate to its new value.
+    async fn execute(&self, scope: &mut Self::Context) -> Self::Output {
+        let value = self.expr().execute(scope).await;
+
+        let result = match (self.operator(), value) {
+            (_, Value::Error(err)) => Value::Error(err),
+            (UnaryOp::Neg, Value::Number(n)) => (-n).into(),
+            (UnaryOp::Inc, Value::Number(n)) => n.add1().into(),
+            (UnaryOp::Dec, Value::Number(n)) => n.sub1().into(),
+            (UnaryOp::Not, Value::Boolean(b)) => Value::Boolean(!b),
+            (op, value) => Value::Error(RuntimeError::TypeMismatch(format!(
+                "Unsupported unary operation: {} {}",
+                op.to_sexpr().format(0),
+                value
+            ))),
+        };
+
+        if let (UnaryOp::Inc | UnaryOp::Dec, Expr::Variable(name)) = (self.operator(), self.expr())
+            && !result.is_error()
+        {
+            scope.assign(name.clone(), result.clone()).await;
+        }
+        result
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -603,6 +702,137 @@ mod tests {
         assert_eq!(or_result, Value::Boolean(true));
     }
 
+    #[tokio::test]
+    async fn test_binary_relational() {
+        let mut scope = Scope::default();
+
+        let compare = |left: Number, op: BinaryOp, right: Number| {
+            Expr::Binary(BinaryExpr::new(
+                Expr::Value(Value::Number(left)),
+                op,
+                Expr::Value(Value::Number(right)),
+            ))
+        };
+
+        // Same types
+        let lt_expr = compare(Number::Int(2), BinaryOp::Lt, Number::Int(3));
+        assert_eq!(lt_expr.execute(&mut scope).await, Value::Boolean(true));
+        let ge_expr = compare(Number::UInt(2), BinaryOp::Ge, Number::UInt(3));
+        assert_eq!(ge_expr.execute(&mut scope).await, Value::Boolean(false));
+
+        // Across number types: -1 < 0u, 2 <= 2.0, 3.5 > 3
+        let lt_expr = compare(Number::Int(-1), BinaryOp::Lt, Number::UInt(0));
+        assert_eq!(lt_expr.execute(&mut scope).await, Value::Boolean(true));
+        let le_expr = compare(Number::UInt(2), BinaryOp::Le, Number::Float(2.0));
+        assert_eq!(le_expr.execute(&mut scope).await, Value::Boolean(true));
+        let gt_expr = compare(Number::Float(3.5), BinaryOp::Gt, Number::Int(3));
+        assert_eq!(gt_expr.execute(&mut scope).await, Value::Boolean(true));
+
+        // Divisibility: 12 %% 4, 12 %% 5, 12 %% 0
+        let div_expr = compare(Number::UInt(12), BinaryOp::Divisible, Number::Int(4));
+        assert_eq!(div_expr.execute(&mut scope).await, Value::Boolean(true));
+        let div_expr = compare(Number::Int(12), BinaryOp::Divisible, Number::Int(5));
+        assert_eq!(div_expr.execute(&mut scope).await, Value::Boolean(false));
+        let div_expr = compare(Number::Int(12), BinaryOp::Divisible, Number::Int(0));
+        assert_eq!(
+            div_expr.execute(&mut scope).await,
+            Value::Error(RuntimeError::DivisionByZero)
+        );
+
+        // Type mismatch: "a" < 1
+        let bad_expr = Expr::Binary(BinaryExpr::new(
+            Expr::Value(Value::String("a".into())),
+            BinaryOp::Lt,
+            Expr::Value(Value::Number(Number::Int(1))),
+        ));
+        assert!(matches!(
+            bad_expr.execute(&mut scope).await,
+            Value::Error(RuntimeError::TypeMismatch(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_binary_mixed_and_overflow() {
+        let mut scope = Scope::default();
+
+        // Mixed types promote: 3u - 5 = -2, 1u + 0.5 = 1.5
+        let sub_expr = Expr::Binary(BinaryExpr::new(
+            Expr::Value(Value::Number(Number::UInt(3))),
+            BinaryOp::Sub,
+            Expr::Value(Value::Number(Number::Int(5))),
+        ));
+        assert_eq!(
+            sub_expr.execute(&mut scope).await,
+            Value::Number(Number::Int(-2))
+        );
+        let add_expr = Expr::Binary(BinaryExpr::new(
+            Expr::Value(Value::Number(Number::UInt(1))),
+            BinaryOp::Add,
+            Expr::Value(Value::Number(Number::Float(0.5))),
+        ));
+        assert_eq!(
+            add_expr.execute(&mut scope).await,
+            Value::Number(Number::Float(1.5))
+        );
+
+        // Overflow is an error value rather than a panic
+        let mul_expr = Expr::Binary(BinaryExpr::new(
+            Expr::Value(Value::Number(Number::Float(f64::MAX))),
+            BinaryOp::Mul,
+            Expr::Value(Value::Number(Number::UInt(2))),
+        ));
+        assert_eq!(
+            mul_expr.execute(&mut scope).await,
+            Value::Error(RuntimeError::Overflow)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unary() {
+        let mut scope = Scope::default();
+        scope
+            .set("x".to_string(), Value::Number(Number::UInt(5)))
+            .await;
+
+        // Test negation: -x = -5
+        let neg_expr = Expr::Unary(UnaryExpr::new(UnaryOp::Neg, Expr::Variable("x".into())));
+        assert_eq!(
+            neg_expr.execute(&mut scope).await,
+            Value::Number(Number::Int(-5))
+        );
+
+        // Test increment and decrement: ++x = 6 rebinds x, then --x = 5
+        let inc_expr = Expr::Unary(UnaryExpr::new(UnaryOp::Inc, Expr::Variable("x".into())));
+        assert_eq!(
+            inc_expr.execute(&mut scope).await,
+            Value::Number(Number::UInt(6))
+        );
+        assert_eq!(scope.get("x"), Some(Value::Number(Number::UInt(6))));
+        let dec_expr = Expr::Unary(UnaryExpr::new(UnaryOp::Dec, Expr::Variable("x".into())));
+        assert_eq!(
+            dec_expr.execute(&mut scope).await,
+            Value::Number(Number::UInt(5))
+        );
+        assert_eq!(scope.get("x"), Some(Value::Number(Number::UInt(5))));
+
+        // Test logical NOT: !true = false
+        let not_expr = Expr::Unary(UnaryExpr::new(
+            UnaryOp::Not,
+            Expr::Value(Value::Boolean(true)),
+        ));
+        assert_eq!(not_expr.execute(&mut scope).await, Value::Boolean(false));
+
+        // Test type mismatch: !5
+        let bad_expr = Expr::Unary(UnaryExpr::new(
+            UnaryOp::Not,
+            Expr::Value(Value::Number(Number::Int(5))),
+        ));
+        assert!(matches!(
+            bad_expr.execute(&mut scope).await,
+            Value::Error(RuntimeError::TypeMismatch(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_call_expression() {
         let mut scope = Scope::default();
@@ -631,6 +861,152 @@ mod tests {
         assert_eq!(message.terms(), &vec![Value::Number(Number::Int(100))]);
     }
 
+    #[tokio::test]
+    async fn test_call_with_reply_timeout() {
+        let mut scope = Scope::default();
+
+        // Nobody ever answers on this channel.
+        let (channel, _listener) = Channel::new(1);
+        let timeout = std::time::Duration::from_millis(10);
+        let call_expr = CallExpr::new(
+            Expr::Value(Value::Channel(channel)),
+            vec![Expr::Value(Value::Word("ping".to_string())).into()],
+        )
+        .with_reply_timeout(Some(timeout));
+
+        let result = call_expr.execute_with_reply(&mut scope).await;
+        assert_eq!(result, Value::Error(RuntimeError::Timeout(timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_default_reply_timeouts_dont_mix() {
+        // Two systems waiting on silent agents at the same time
+        let call = |millis: u64| async move {
+            let timeout = std::time::Duration::from_millis(millis);
+            let (channel, _listener) = Channel::new(1);
+            let call_expr = CallExpr::new(
+                Expr::Value(Value::Channel(channel)),
+                vec![Expr::Value(Value::Word("ping".to_string())).into()],
+            );
+            let result = Channel::with_reply_timeout(
+                Some(timeout),
+                call_expr.execute_with_reply(&mut Scope::default()),
+            )
+            .await;
+            assert_eq!(result, Value::Error(RuntimeError::Timeout(timeout)));
+        };
+        tokio::join!(call(10), call(30));
+        assert_eq!(Channel::default_reply_timeout(), None);
+    }
+
+    #[tokio::test]
+    async fn test_catching() {
+        assert!(!is_caught());
+        assert!(catching(async { is_caught() }).await);
+        // Only for the work it runs
+        assert!(!is_caught());
+    }
+
+    #[tokio::test]
+    async fn test_try_catch() {
+        let mut scope = Scope::default();
+
+        // try { x = 10 / 0 } catch _err { err.kind }
+        let stmt = Statement::Try(
+            Block::new(vec![Statement::Assignment(
+                "x".to_string(),
+                Expr::Binary(BinaryExpr::new(
+                    Expr::Value(Value::Number(Number::Int(10))),
+                    BinaryOp::Div,
+                    Expr::Value(Value::Number(Number::Int(0))),
+                )),
+            )]),
+            "err".to_string(),
+            Block::new(vec![Statement::Expr(Expr::Binary(BinaryExpr::new(
+                Expr::Variable("err".to_string()),
+                BinaryOp::Access,
+                Expr::Variable("kind".to_string()),
+            )))]),
+        );
+
+        let result = stmt.execute(&mut scope).await;
+        assert_eq!(result, Value::Word("DivisionByZero".to_string()));
+        assert_eq!(
+            scope.get("err"),
+            Some(Value::Error(RuntimeError::DivisionByZero))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_try_without_error() {
+        let mut scope = Scope::default();
+
+        let stmt = Statement::Try(
+            Block::new(vec![Statement::Expr(Expr::Value(Value::Number(
+                Number::Int(1),
+            )))]),
+            "err".to_string(),
+            Block::new(vec![Statement::Expr(Expr::Value(Value::Number(
+                Number::Int(2),
+            )))]),
+        );
+
+        assert_eq!(
+            stmt.execute(&mut scope).await,
+            Value::Number(Number::Int(1))
+        );
+        assert_eq!(scope.get("err"), None);
+    }
+
+    /// `while { x < limit } { x = x + 1 }`
+    fn count_to(limit: i64) -> Statement {
+        let x = || Expr::Variable("x".to_string());
+        Statement::While(
+            Block::new(vec![Statement::Expr(Expr::Binary(BinaryExpr::new(
+                x(),
+                BinaryOp::Lt,
+                Expr::Value(Value::Number(Number::Int(limit))),
+            )))]),
+            Block::new(vec![Statement::Assignment(
+                "x".to_string(),
+                Expr::Binary(BinaryExpr::new(
+                    x(),
+                    BinaryOp::Add,
+                    Expr::Value(Value::Number(Number::Int(1))),
+                )),
+            )]),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_while() {
+        let mut scope = Scope::default();
+        scope.set("x".to_string(), Value::from(0)).await;
+
+        // Far more iterations than a mailbox or the stack would allow
+        assert_eq!(count_to(10_000).execute(&mut scope).await, Value::Empty);
+        assert_eq!(scope.get("x"), Some(Value::from(10_000)));
+
+        scope.set("x".to_string(), Value::from("a")).await;
+        assert!(count_to(3).execute(&mut scope).await.is_error());
+    }
+
+    #[tokio::test]
+    async fn test_while_in_block_value() {
+        let mut scope = Scope::default();
+        scope.set("x".to_string(), Value::from(0)).await;
+
+        // Binding the block must not fix `x` to the value it had before the loop
+        let block = Expr::Block(Box::new(Block::new(vec![count_to(5)])));
+        let Value::Block(block) = block.execute(&mut scope).await else {
+            panic!("Expected a block");
+        };
+        let mut inner = Scope::default();
+        inner.set("x".to_string(), Value::from(2)).await;
+        block.execute(&mut inner).await;
+        assert_eq!(inner.get("x"), Some(Value::from(5)));
+    }
+
     #[tokio::test]
     async fn test_variable_not_found() {
         let mut scope = Scope::default();
@@ -660,11 +1036,71 @@ mod tests {
         let var_stmt = Statement::Expr(Expr::Variable("my_list".to_string()));
         let var_result = var_stmt.execute(&mut scope).await;
 
-        // The result should be a channel to a ListAgent
-        if let Value::Channel(channel) = var_result {
-            // okay
-        } else {
-            panic!("Expected a channel to a ListAgent");
-        }
+        // The result should be a plain list value
+        assert_eq!(
+            var_result,
+            Value::from(vec![Value::from(1), Value::from(2), Value::from(3)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_updated_in_place() {
+        let mut scope = Scope::default();
+        let items = Vec::with_capacity(8);
+        let address = items.as_ptr() as usize;
+        scope.set("xs".to_string(), Value::List(items.into())).await;
+
+        let add = |item: i64| {
+            Statement::Assignment(
+                "xs".to_string(),
+                Expr::Call(CallExpr::new(
+                    Expr::Variable("xs".to_string()),
+                    vec![
+                        Expr::Value(Value::Word("add".to_string())).into(),
+                        Expr::Value(Value::from(item)).into(),
+                    ],
+                )),
+            )
+        };
+        Block::new(vec![add(1), add(2)]).execute(&mut scope).await;
+        let Some(Value::List(xs)) = scope.get("xs") else {
+            panic!("Expected a list");
+        };
+        assert_eq!(&xs[..], &[Value::from(1), Value::from(2)]);
+        // Nothing shared the list, so it wasn't copied
+        assert_eq!(xs.as_ptr() as usize, address);
+        drop(xs);
+
+        // A list that is shared is copied, leaving the other name as it was
+        scope.set("ys".to_string(), scope.get("xs").unwrap()).await;
+        add(3).execute(&mut scope).await;
+        let list =
+            |items: &[i64]| Value::from(items.iter().map(|&n| Value::from(n)).collect::<Vec<_>>());
+        assert_eq!(scope.get("xs"), Some(list(&[1, 2, 3])));
+        assert_eq!(scope.get("ys"), Some(list(&[1, 2])));
+    }
+
+    #[tokio::test]
+    async fn test_field_type_of_spawned_value() {
+        let (channel, _listener) = Channel::new(1);
+        let bob = Value::Channel(channel.with_agent_type("Bob"));
+        let mut scope = Scope::default();
+        scope.set("peer".to_string(), bob.clone()).await;
+
+        let field =
+            |typ: ValueType| Statement::Field("peer".to_string(), TypeExpr::HasType(typ), None);
+        assert_eq!(
+            field(ValueType::User("Bob".to_string()))
+                .execute(&mut scope)
+                .await,
+            bob
+        );
+        assert_eq!(field(ValueType::Channel).execute(&mut scope).await, bob);
+        assert!(matches!(
+            field(ValueType::User("Carol".to_string()))
+                .execute(&mut scope)
+                .await,
+            Value::Error(RuntimeError::TypeMismatch(_))
+        ));
     }
 }
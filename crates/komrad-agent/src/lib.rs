@@ -1,8 +1,11 @@
 #![feature(associated_type_defaults)]
 
+pub mod call_chain;
 pub mod closure;
 pub mod execute;
+pub mod list_ops;
 pub mod try_bind;
+pub mod value_call;
 
 pub mod stdlib_agent;
 
@@ -0,0 +1,353 @@
+use crate::execute::Execute;
+use komrad_ast::prelude::{Block, Channel, List, Message, Number, RuntimeError, Value};
+use komrad_ast::scope::Scope;
+use std::cmp::Ordering;
+
+/// Runs one of the operations that read a list without changing it, on a
+/// list value or for a `ListAgent`:
+///
+/// ```text
+/// items
+/// length
+/// get _i
+/// contains _x
+/// index-of _x
+/// foreach _x {block}
+/// map _x {block}
+/// filter _x {block}
+/// reduce _acc _x _initial {block}
+/// sort
+/// sort-by _x {block}
+/// reverse
+/// slice _start [_end]
+/// join _separator
+/// concat _other
+/// zip _other
+/// ```
+///
+/// Blocks arrive already bound by `Closure::closure`, so they run in a
+/// scope of their own holding only the names given here, e.g. `_x`.
+/// `concat` and `zip` take the other list's items; fetching them from an
+/// agent is up to the caller. Returns `None` for anything else.
+pub async fn query(items: &List, command: &str, args: &[Value]) -> Option<Value> {
+    let reply = match (command, args) {
+        ("items", []) => Value::List(items.clone()),
+        ("length", []) => Value::Number(Number::Int(items.len() as i64)),
+        ("get", [index]) => match to_index(index) {
+            Ok(index) => items.get(index).cloned().unwrap_or(Value::Empty),
+            Err(err) => Value::Error(err),
+        },
+        ("contains", [item]) => Value::Boolean(items.contains(item)),
+        ("index-of", [item]) => match items.iter().position(|x| x == item) {
+            Some(index) => Value::Number(Number::Int(index as i64)),
+            None => Value::Empty,
+        },
+        ("foreach", [Value::Word(name), Value::Block(block)]) => {
+            let mut result = Value::Empty;
+            for item in items {
+                if let error @ Value::Error(_) = call(block, &[(name, item)]).await {
+                    result = error;
+                    break;
+                }
+            }
+            result
+        }
+        ("map", [Value::Word(name), Value::Block(block)]) => {
+            let mut mapped = Vec::with_capacity(items.len());
+            for item in items {
+                match call(block, &[(name, item)]).await {
+                    Value::Error(err) => return Some(Value::Error(err)),
+                    value => mapped.push(value),
+                }
+            }
+            Value::from(mapped)
+        }
+        ("filter", [Value::Word(name), Value::Block(block)]) => {
+            let mut kept = Vec::new();
+            for item in items {
+                match call(block, &[(name, item)]).await {
+                    Value::Boolean(true) => kept.push(item.clone()),
+                    Value::Boolean(false) => {}
+                    other => return Some(not_a_boolean(other)),
+                }
+            }
+            Value::from(kept)
+        }
+        (
+            "reduce",
+            [
+                Value::Word(acc),
+                Value::Word(name),
+                initial,
+                Value::Block(block),
+            ],
+        ) => {
+            let mut result = initial.clone();
+            for item in items {
+                result = call(block, &[(acc, &result), (name, item)]).await;
+                if result.is_error() {
+                    break;
+                }
+            }
+            result
+        }
+        ("sort", []) => {
+            let keyed = items.iter().map(|item| (item.clone(), item.clone()));
+            sorted(keyed.collect())
+        }
+        ("sort-by", [Value::Word(name), Value::Block(block)]) => {
+            let mut keyed = Vec::with_capacity(items.len());
+            for item in items {
+                match call(block, &[(name, item)]).await {
+                    Value::Error(err) => return Some(Value::Error(err)),
+                    key => keyed.push((key, item.clone())),
+                }
+            }
+            sorted(keyed)
+        }
+        ("reverse", []) => Value::List(items.iter().rev().cloned().collect()),
+        ("slice", [start]) => {
+            let end = Value::Number(Number::Int(items.len() as i64));
+            slice(items, start, &end)
+        }
+        ("slice", [start, end]) => slice(items, start, end),
+        ("join", [separator]) => {
+            let parts: Vec<String> = items.iter().map(ToString::to_string).collect();
+            Value::String(parts.join(&separator.to_string()))
+        }
+        ("concat", [Value::List(other)]) => {
+            Value::List(items.iter().chain(other).cloned().collect())
+        }
+        ("zip", [Value::List(other)]) => Value::List(
+            items
+                .iter()
+                .zip(other)
+                .map(|(a, b)| Value::from(vec![a.clone(), b.clone()]))
+                .collect(),
+        ),
+        _ => return None,
+    };
+    Some(reply)
+}
+
+/// Runs one of the operations that change a list:
+///
+/// ```text
+/// add _x          // replies ok
+/// set _i _x       // replies with the item it replaced
+/// insert _i _x    // replies ok
+/// remove _i       // replies with the item it removed
+/// ```
+///
+/// A `ListAgent` changes its own list; a list value changes a copy.
+/// Returns `None` for anything else.
+pub fn update(items: &mut List, command: &str, args: &[Value]) -> Option<Value> {
+    let length = items.len();
+    let reply = match (command, args) {
+        ("add", [item]) => {
+            items.push(item.clone());
+            Value::from("ok")
+        }
+        ("set", [index, item]) => match to_index(index) {
+            Ok(i) if i < length => std::mem::replace(&mut items.make_mut()[i], item.clone()),
+            Ok(i) => out_of_range(i, length),
+            Err(err) => Value::Error(err),
+        },
+        ("insert", [index, item]) => match to_index(index) {
+            Ok(i) if i <= length => {
+                items.make_mut().insert(i, item.clone());
+                Value::from("ok")
+            }
+            Ok(i) => out_of_range(i, length),
+            Err(err) => Value::Error(err),
+        },
+        ("remove", [index]) => match to_index(index) {
+            Ok(i) if i < length => items.make_mut().remove(i),
+            Ok(i) => out_of_range(i, length),
+            Err(err) => Value::Error(err),
+        },
+        _ => return None,
+    };
+    Some(reply)
+}
+
+/// A list's items, asking for them if the value is a channel to a list
+/// agent. Other values are returned as they are.
+pub async fn items_of(list: &Value) -> Value {
+    let Value::Channel(channel) = list else {
+        return list.clone();
+    };
+    if !channel.implements("List") {
+        return list.clone();
+    }
+    let (reply_chan, reply_listener) = Channel::new(1);
+    let request = Message::new(vec![Value::Word("items".into())], Some(reply_chan));
+    if channel.send(request).await.is_err() {
+        return Value::Error(RuntimeError::SendError);
+    }
+    match reply_listener
+        .recv_timeout(Channel::default_reply_timeout())
+        .await
+    {
+        Ok(reply) => reply.terms().first().cloned().unwrap_or(Value::Empty),
+        Err(err) => Value::Error(err),
+    }
+}
+
+fn out_of_range(index: usize, length: usize) -> Value {
+    Value::Error(RuntimeError::TypeMismatch(format!(
+        "Index {} is out of range for a list of {}",
+        index, length
+    )))
+}
+
+/// A list index: a whole number that isn't negative.
+pub fn to_index(index: &Value) -> Result<usize, RuntimeError> {
+    match index {
+        Value::Number(n) => n
+            .to_u64()
+            .map(|i| i as usize)
+            .ok_or_else(|| RuntimeError::TypeMismatch(format!("Invalid list index {}", n))),
+        other => Err(RuntimeError::TypeMismatch(format!(
+            "Expected a list index, found {}",
+            other
+        ))),
+    }
+}
+
+async fn call(block: &Block, bindings: &[(&String, &Value)]) -> Value {
+    let mut scope = Scope::new();
+    for (name, value) in bindings {
+        scope.set(name.to_string(), (*value).clone()).await;
+    }
+    block.execute(&mut scope).await
+}
+
+fn not_a_boolean(value: Value) -> Value {
+    match value {
+        Value::Error(err) => Value::Error(err),
+        other => Value::Error(RuntimeError::TypeMismatch(format!(
+            "Expected a Boolean from the filter, found {}",
+            other.get_type()
+        ))),
+    }
+}
+
+/// Sorts items by their keys, keeping equal keys in order. Keys must all be
+/// numbers, all strings or all booleans.
+fn sorted(mut keyed: Vec<(Value, Value)>) -> Value {
+    let orderable =
+        |key: &Value| matches!(key, Value::Number(_) | Value::String(_) | Value::Boolean(_));
+    if let Some((key, _)) = keyed
+        .iter()
+        .find(|(key, _)| !orderable(key) || key.get_type() != keyed[0].0.get_type())
+    {
+        return Value::Error(RuntimeError::TypeMismatch(format!(
+            "Cannot sort {} among {}",
+            key.get_type(),
+            keyed[0].0.get_type()
+        )));
+    }
+    keyed.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    keyed
+        .into_iter()
+        .map(|(_, item)| item)
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// `slice 1 3` is the items at 1 and 2. Bounds past the end are clamped.
+fn slice(items: &[Value], start: &Value, end: &Value) -> Value {
+    match (to_index(start), to_index(end)) {
+        (Ok(start), Ok(end)) => {
+            let end = end.min(items.len());
+            let start = start.min(end);
+            Value::List(items[start..end].into())
+        }
+        (Err(err), _) | (_, Err(err)) => Value::Error(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use komrad_ast::prelude::{BinaryExpr, BinaryOp, Expr, Statement};
+
+    fn word(word: &str) -> Value {
+        Value::Word(word.to_string())
+    }
+
+    fn numbers(numbers: &[i32]) -> List {
+        numbers.iter().map(|n| Value::from(*n)).collect()
+    }
+
+    fn list(items: &[i32]) -> Option<Value> {
+        Some(Value::List(numbers(items)))
+    }
+
+    fn block(left: &str, op: BinaryOp, right: Expr) -> Value {
+        let expr = Expr::Binary(BinaryExpr::new(Expr::Variable(left.to_string()), op, right));
+        Value::Block(Box::new(Block::new(vec![Statement::Expr(expr)])))
+    }
+
+    #[tokio::test]
+    async fn test_query_with_blocks() {
+        let items = numbers(&[3, 1, 2]);
+        let double = block("x", BinaryOp::Mul, Expr::Value(Value::from(2)));
+        assert_eq!(
+            query(&items, "map", &[word("x"), double]).await,
+            list(&[6, 2, 4])
+        );
+        let big = block("x", BinaryOp::Gt, Expr::Value(Value::from(1)));
+        assert_eq!(
+            query(&items, "filter", &[word("x"), big]).await,
+            list(&[3, 2])
+        );
+        let sum = block("acc", BinaryOp::Add, Expr::Variable("x".to_string()));
+        assert_eq!(
+            query(
+                &items,
+                "reduce",
+                &[word("acc"), word("x"), Value::from(10), sum]
+            )
+            .await,
+            Some(Value::from(16))
+        );
+        let negated = block("x", BinaryOp::Mul, Expr::Value(Value::from(-1)));
+        assert_eq!(
+            query(&items, "sort-by", &[word("x"), negated]).await,
+            list(&[3, 2, 1])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query() {
+        let items = numbers(&[3, 1, 2]);
+        assert_eq!(query(&items, "sort", &[]).await, list(&[1, 2, 3]));
+        assert_eq!(
+            query(&items, "slice", &[Value::from(1), Value::from(9)]).await,
+            list(&[1, 2])
+        );
+        assert_eq!(
+            query(&items, "index-of", &[Value::from(2)]).await,
+            Some(Value::from(2))
+        );
+        assert_eq!(
+            query(&items, "join", &[Value::from(", ")]).await,
+            Some(Value::from("3, 1, 2"))
+        );
+        assert_eq!(
+            query(&items, "zip", &[Value::List(numbers(&[4, 5]))]).await,
+            Some(Value::from(vec![
+                Value::List(numbers(&[3, 4])),
+                Value::List(numbers(&[1, 5])),
+            ]))
+        );
+        let mixed = List::from(vec![Value::from(1), Value::from("a")]);
+        assert!(matches!(
+            query(&mixed, "sort", &[]).await,
+            Some(Value::Error(_))
+        ));
+        assert_eq!(query(&items, "pop", &[]).await, None);
+    }
+}
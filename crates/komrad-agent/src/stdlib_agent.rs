@@ -1,9 +1,10 @@
 use crate::execute::Execute;
+use crate::list_ops;
 use crate::AgentBehavior;
 use async_trait::async_trait;
 use komrad_ast::agent::Agent;
 use komrad_ast::prelude::{
-    AgentFactory, Channel, ChannelListener, Message, MessageBuilder, Number, Value,
+    AgentFactory, Channel, ChannelListener, List, Message, MessageBuilder, Value,
 };
 use komrad_ast::scope::Scope;
 use komrad_macros::{agent_stateful_impl, agent_stateless_impl};
@@ -14,8 +15,8 @@ use tracing::error;
 pub struct ListAgent {
     channel: Channel,
     listener: Arc<ChannelListener>,
-    scope: Arc<Mutex<Scope>>,       // required for stateful agents
-    items: Arc<RwLock<Vec<Value>>>, // agent's internal data
+    scope: Arc<Mutex<Scope>>,  // required for stateful agents
+    items: Arc<RwLock<List>>, // agent's internal data
 }
 
 agent_stateful_impl!(ListAgent);
@@ -28,13 +29,13 @@ impl ListAgent {
             channel,
             listener: Arc::new(listener),
             scope: Arc::new(Mutex::new(Scope::new())),
-            items: Arc::new(RwLock::new(initial_items)),
+            items: Arc::new(RwLock::new(initial_items.into())),
         })
     }
 
     // Submethods for internal list operations
     pub async fn handle_items(&self) -> Vec<Value> {
-        self.items.read().await.clone()
+        self.items.read().await.to_vec()
     }
 
     pub async fn handle_add_item(&self, item: Value) {
@@ -54,113 +55,67 @@ impl ListAgent {
 
 /// The `ListAgent` receives messages on its channel (inside its `actor_loop`) and
 /// processes them via this `handle_message` method. It can respond via `reply_to()`.
+///
+/// This is a list that several agents can share and change, made with
+/// `List new`. It changes in place with `list_ops::update`, and answers
+/// everything else with `list_ops::query` as a list value would.
 #[async_trait]
 impl AgentBehavior for ListAgent {
     async fn handle_message(&self, msg: Message) -> bool {
-        match msg.first_word().as_deref() {
-            Some("items") => {
-                if let Some(reply_chan) = msg.reply_to() {
-                    let items = self.items.read().await.clone();
-                    let reply = Message::new(vec![Value::List(items)], None);
-                    if reply_chan.send(reply).await.is_err() {
-                        error!("ListAgent: failed to send 'items' reply");
-                    }
-                } else {
-                    error!("ListAgent: 'items' command requires a reply channel");
-                }
-            }
-            Some("foreach") => {
-                // `array each _x _{block}`
+        let Some(command) = msg.first_word() else {
+            error!("ListAgent: no command in message {:?}", msg);
+            reply(&msg, Value::from("error")).await;
+            return true;
+        };
+        let args = msg.rest();
+
+        let updated = list_ops::update(&mut *self.items.write().await, &command, args);
+        let value = match updated {
+            Some(value) => value,
+            None => {
                 let items = self.items.read().await.clone();
-                let variable = msg.rest().get(0).cloned();
-                let block = msg.rest().get(1).cloned();
-
-                if let Some(Value::Block(block)) = block {
-                    for item in items {
-                        let mut new_scope = self.scope.lock().await.clone();
-                        if let Some(Value::Word(variable)) = variable.clone() {
-                            new_scope.set(variable, item).await;
-                            block.execute(&mut new_scope).await;
-                        } else {
-                            error!("ListAgent: 'each' command requires a variable");
-                        }
-                    }
-                }
-            }
-            Some("add") => {
-                if let Some(item) = msg.rest().get(0) {
-                    self.handle_add_item(item.clone()).await;
-                    if let Some(reply_chan) = msg.reply_to() {
-                        let reply = Message::default().with_terms(vec![Value::from("ok")]);
-                        if reply_chan.send(reply).await.is_err() {
-                            error!("ListAgent: failed to send 'add' reply");
+                let mut resolved = Vec::with_capacity(args.len());
+                for arg in args {
+                    // Asking ourselves for our items would wait on this very message
+                    match arg {
+                        Value::Channel(other) if *other == self.channel => {
+                            resolved.push(Value::List(items.clone()))
                         }
-                    }
-                } else {
-                    error!("ListAgent: 'add' requires an item argument");
-                }
-            }
-            Some("get") => {
-                if let Some(index_val) = msg.rest().get(0) {
-                    let index = match index_val {
-                        Value::Number(Number::Int(i)) => Some(*i as usize),
-                        Value::Number(Number::UInt(u)) => Some(*u as usize),
-                        Value::Number(Number::Float(f)) => Some(*f as usize),
-                        _ => None,
-                    };
-                    if let Some(idx) = index {
-                        if let Some(reply_chan) = msg.reply_to() {
-                            let item = self.handle_get_item(idx).await.unwrap_or(Value::Empty);
-                            let reply = Message::default().with_terms(vec![item]);
-                            if reply_chan.send(reply).await.is_err() {
-                                error!("ListAgent: failed to send 'get' reply");
-                            }
-                        } else {
-                            error!("ListAgent: 'get' requires a reply channel");
-                        }
-                    } else {
-                        error!("ListAgent: 'get' index argument must be a number");
-                    }
-                } else {
-                    error!("ListAgent: 'get' command requires an index argument");
-                }
-            }
-            Some("length") => {
-                if let Some(reply_chan) = msg.reply_to() {
-                    let len = self.handle_get_length().await;
-                    let reply =
-                        Message::default().with_terms(vec![Value::Number(Number::Int(len as i64))]);
-                    if reply_chan.send(reply).await.is_err() {
-                        error!("ListAgent: failed to send 'length' reply");
-                    }
-                } else {
-                    error!("ListAgent: 'length' requires a reply channel");
-                }
-            }
-            Some(other) => {
-                error!("ListAgent: unknown command '{other}'");
-                if let Some(reply_chan) = msg.reply_to() {
-                    error!("ListAgent: unknown command '{other}'");
-                    let reply = Message::default().with_terms(vec![Value::from("error")]);
-                    if reply_chan.send(reply).await.is_err() {
-                        error!("ListAgent: failed to send 'error' reply");
+                        Value::Channel(_) => resolved.push(list_ops::items_of(arg).await),
+                        _ => resolved.push(arg.clone()),
                     }
                 }
-            }
-            None => {
-                error!("ListAgent: no command in message {:?}", msg);
-                if let Some(reply_chan) = msg.reply_to() {
-                    let reply = Message::default().with_terms(vec![Value::from("error")]);
-                    if reply_chan.send(reply).await.is_err() {
-                        error!("ListAgent: failed to send 'error' reply");
+                match list_ops::query(&items, &command, &resolved).await {
+                    Some(value) => value,
+                    None => {
+                        error!("ListAgent: unknown command '{command}'");
+                        Value::from("error")
                     }
                 }
             }
+        };
+
+        if msg.reply_to().is_none()
+            && let Value::Error(err) = &value
+        {
+            error!("ListAgent: {command}: {err}");
         }
+        reply(&msg, value).await;
         true
     }
 }
 
+async fn reply(msg: &Message, value: Value) {
+    if let Some(reply_chan) = msg.reply_to()
+        && reply_chan
+            .send(Message::new(vec![value], None))
+            .await
+            .is_err()
+    {
+        error!("ListAgent: failed to send reply");
+    }
+}
+
 pub struct DictAgent {
     channel: Channel,
     listener: Arc<ChannelListener>,
@@ -289,44 +244,6 @@ mod tests {
     use tokio::time::{sleep, Duration};
     use tracing::info;
 
-    #[tokio::test]
-    async fn test_stdlib_agent_spawn() {
-        let _ = tracing_subscriber::fmt()
-            .with_max_level(tracing::Level::DEBUG)
-            .try_init();
-
-        // 1. Create and spawn a StdLibAgent
-        let stdlib_agent = StdLibAgent::new();
-        let stdlib_chan = stdlib_agent.clone().spawn();
-
-        // 2. Create a reply channel
-        let (reply_chan, reply_listener) = Channel::new(8);
-
-        // 3. Send a "list" command to the agent’s channel
-        let msg = Message::default()
-            .with_terms(vec![Value::Word("List".into())])
-            .with_reply_to(Some(reply_chan));
-        stdlib_chan.send(msg).await.unwrap();
-
-        // 4. Wait for the asynchronous reply
-        sleep(Duration::from_millis(50)).await;
-
-        // 5. Check the reply
-        let reply = reply_listener
-            .recv()
-            .await
-            .expect("Should receive 'list' reply");
-        let first_value = reply.terms().get(0).cloned().unwrap_or(Value::Empty);
-        match first_value {
-            Value::Channel(list_chan) => {
-                info!("Received list channel: {:?}", list_chan);
-            }
-            other => {
-                panic!("Expected a Channel, got: {:?}", other);
-            }
-        }
-    }
-
     #[tokio::test]
     async fn test_list_agent_spawn_items() {
         // 1. Create and spawn a list agent with initial items
@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use komrad_ast::prelude::{ComparisonOp, Message, Number, Pattern, TypeExpr, Typed, Value};
+use komrad_ast::prelude::{ComparisonOp, Message, Pattern, TypeExpr, Value};
 use komrad_ast::scope::Scope;
 
 #[async_trait]
@@ -47,7 +47,7 @@ impl TryBind for Pattern {
                 // For a type hole, check if the value is of the expected type.
                 TypeExpr::HasType(typ) => {
                     // Check if the value is of the expected type.
-                    if !value.get_type().is_subtype_of(typ) {
+                    if !value.has_type(typ) {
                         return None;
                     }
                     // SUCCESS: just move on
@@ -55,7 +55,7 @@ impl TryBind for Pattern {
                 // Type check type holes
                 TypeExpr::TypeHole(name, typ) => {
                     // Check if the value is of the expected type.
-                    if !value.get_type().is_subtype_of(typ) {
+                    if !value.has_type(typ) {
                         return None;
                     }
                     // Bind the value to the name.
@@ -88,20 +88,12 @@ impl TryBind for Pattern {
                         ComparisonOp::Le => value <= expected_value,
                         ComparisonOp::Gt => value > expected_value,
                         ComparisonOp::Ge => value >= expected_value,
-                        ComparisonOp::Divisible => {
-                            let outcome = match (value, expected_value) {
-                                (
-                                    Value::Number(Number::Int(value)),
-                                    Value::Number(Number::Int(expected_value)),
-                                ) => value % expected_value == 0,
-                                (
-                                    Value::Number(Number::UInt(value)),
-                                    Value::Number(Number::UInt(expected_value)),
-                                ) => value % expected_value == 0,
-                                _ => false,
-                            };
-                            outcome
-                        }
+                        ComparisonOp::Divisible => match (value, expected_value) {
+                            (Value::Number(value), Value::Number(divisor)) => {
+                                value.is_divisible_by(divisor)
+                            }
+                            _ => false,
+                        },
                     };
                     if !result {
                         return None;
@@ -232,4 +224,61 @@ mod tests {
             "Expected divisibility check mismatch to fail binding"
         );
     }
+
+    /// Test that predicates compare numbers by value, whatever their representation.
+    #[tokio::test]
+    async fn test_try_bind_predicate_across_number_types() {
+        let pattern = Pattern::new(vec![TypeExpr::Binary(
+            "x".to_string(),
+            ComparisonOp::Gt,
+            Value::Number(Number::UInt(3)),
+        )]);
+        for value in [Number::Int(4), Number::UInt(4), Number::Float(3.5)] {
+            let message = Message::new(vec![Value::Number(value.clone())], None);
+            let bound_scope = pattern.try_bind(message, &mut Scope::new()).await;
+            assert!(bound_scope.is_some(), "Expected {} > 3 to bind", value);
+        }
+        for value in [Number::Int(-4), Number::UInt(3), Number::Float(3.0)] {
+            let message = Message::new(vec![Value::Number(value.clone())], None);
+            let bound_scope = pattern.try_bind(message, &mut Scope::new()).await;
+            assert!(bound_scope.is_none(), "Expected {} > 3 not to bind", value);
+        }
+
+        let pattern = Pattern::new(vec![TypeExpr::Binary(
+            "x".to_string(),
+            ComparisonOp::Eq,
+            Value::Number(Number::UInt(3)),
+        )]);
+        let message = Message::new(vec![Value::Number(Number::Float(3.0))], None);
+        assert!(pattern.try_bind(message, &mut Scope::new()).await.is_some());
+    }
+
+    /// Test that type holes tell agents apart by the definition they were spawned from.
+    #[tokio::test]
+    async fn test_try_bind_agent_type() {
+        use komrad_ast::prelude::{Channel, ValueType};
+
+        let pattern = Pattern::new(vec![TypeExpr::TypeHole(
+            "peer".to_string(),
+            ValueType::User("Bob".to_string()),
+        )]);
+        let (channel, _listener) = Channel::new(1);
+        let bob = Value::Channel(channel.clone().with_agent_type("Bob"));
+        let carol = Value::Channel(channel.clone().with_agent_type("Carol"));
+
+        let message = Message::new(vec![bob.clone()], None);
+        assert!(pattern.try_bind(message, &mut Scope::new()).await.is_some());
+        for value in [carol, Value::Channel(channel)] {
+            let message = Message::new(vec![value], None);
+            assert!(pattern.try_bind(message, &mut Scope::new()).await.is_none());
+        }
+
+        // A Bob is still a Channel
+        let pattern = Pattern::new(vec![TypeExpr::TypeHole(
+            "peer".to_string(),
+            ValueType::Channel,
+        )]);
+        let message = Message::new(vec![bob], None);
+        assert!(pattern.try_bind(message, &mut Scope::new()).await.is_some());
+    }
 }
@@ -0,0 +1,209 @@
+use crate::AgentBehavior;
+use crate::execute::Execute;
+use crate::list_ops;
+use crate::stdlib_agent::ListAgent;
+use komrad_ast::prelude::{List, Map, Number, RuntimeError, Value};
+use komrad_ast::scope::Scope;
+
+/// Handles a message sent to a value that isn't a channel. Lists and maps
+/// answer these themselves, without an agent behind them.
+///
+/// Lists answer the operations in `list_ops`. Those that change a list,
+/// such as `add _x`, reply with a changed copy instead:
+///
+/// ```text
+/// numbers = [3 1 2]
+/// more = numbers add 4     // numbers is still [3 1 2]
+/// ```
+///
+/// `List new` makes a `ListAgent` for a list that's shared and changed in
+/// place, optionally starting with the items of a list value.
+///
+/// Maps answer:
+///
+/// ```text
+/// person get name        // or person.name
+/// person has email
+/// person keys
+/// person values
+/// person length
+/// person set age 42      // a new map; maps are values, not agents
+/// person remove age
+/// person foreach key value { Io println key }
+/// ```
+pub async fn call_value(target: Value, args: Vec<Value>, scope: &mut Scope) -> Value {
+    match target {
+        Value::List(list) => call_list(list, args).await,
+        Value::Map(map) => call_map(map, args, scope).await,
+        Value::Word(word) if word == "List" => new_list(args),
+        Value::Error(err) => Value::Error(err),
+        _ => Value::Error(RuntimeError::SendError),
+    }
+}
+
+async fn call_list(mut list: List, args: Vec<Value>) -> Value {
+    let Some(Value::Word(command)) = args.first() else {
+        return unknown_command("list", &args);
+    };
+    let mut rest = Vec::with_capacity(args.len() - 1);
+    for arg in &args[1..] {
+        // `concat` and `zip` may be given a shared list
+        rest.push(list_ops::items_of(arg).await);
+    }
+    if let Some(value) = list_ops::query(&list, command, &rest).await {
+        return value;
+    }
+    match list_ops::update(&mut list, command, &args[1..]) {
+        Some(Value::Error(err)) => Value::Error(err),
+        Some(_) => Value::List(list),
+        None => unknown_command("list", &args),
+    }
+}
+
+fn new_list(args: Vec<Value>) -> Value {
+    let items = match args.as_slice() {
+        [Value::Word(new)] if new == "new" => vec![],
+        [Value::Word(new), Value::List(items)] if new == "new" => items.to_vec(),
+        _ => return unknown_command("List", &args),
+    };
+    Value::Channel(ListAgent::new(items).spawn().with_agent_type("List"))
+}
+
+async fn call_map(mut map: Map, args: Vec<Value>, scope: &mut Scope) -> Value {
+    let command = match args.first() {
+        Some(Value::Word(command)) => command.as_str(),
+        _ => return unknown_command("map", &args),
+    };
+    match (command, &args[1..]) {
+        ("get", [key]) => get(&map, key).cloned().unwrap_or(Value::Empty),
+        ("has", [key]) => Value::Boolean(get(&map, key).is_some()),
+        ("keys", []) => Value::List(map.keys().cloned().collect()),
+        ("values", []) => Value::List(map.values().cloned().collect()),
+        ("length", []) => Value::Number(Number::Int(map.len() as i64)),
+        ("set", [key, value]) => {
+            map.insert(map_key(key), value.clone());
+            Value::Map(map)
+        }
+        ("remove", [key]) => {
+            map.remove(&map_key(key));
+            map.remove(key);
+            Value::Map(map)
+        }
+        ("foreach", [Value::Word(key), Value::Word(value), Value::Block(block)]) => {
+            for (k, v) in map {
+                let mut iteration = Scope::with_parent(scope.clone());
+                iteration.set(key.clone(), k).await;
+                iteration.set(value.clone(), v).await;
+                if let error @ Value::Error(_) = block.execute(&mut iteration).await {
+                    return error;
+                }
+            }
+            Value::Empty
+        }
+        _ => unknown_command("map", &args),
+    }
+}
+
+/// Bare words name the same entries as the identifier keys of a literal.
+fn map_key(key: &Value) -> Value {
+    match key {
+        Value::Word(word) => Value::String(word.clone()),
+        other => other.clone(),
+    }
+}
+
+fn get<'a>(map: &'a Map, key: &Value) -> Option<&'a Value> {
+    match key {
+        Value::Word(word) => map.get_field(word),
+        other => map.get(other),
+    }
+}
+
+fn unknown_command(target: &str, args: &[Value]) -> Value {
+    let terms: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+    Value::Error(RuntimeError::TypeMismatch(format!(
+        "A {} can't handle [{}]",
+        target,
+        terms.join(" ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AgentBehavior;
+    use crate::execute::ExecuteWithReply;
+    use crate::stdlib_agent::ListAgent;
+    use komrad_ast::prelude::{Block, CallExpr, Expr, Statement};
+
+    fn word(word: &str) -> Value {
+        Value::Word(word.to_string())
+    }
+
+    fn person() -> Value {
+        Value::Map(
+            [
+                (Value::from("name"), Value::from("Ada")),
+                (Value::from("age"), Value::from(36)),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    async fn call(args: Vec<Value>) -> Value {
+        call_value(person(), args, &mut Scope::new()).await
+    }
+
+    #[tokio::test]
+    async fn test_call_map() {
+        assert_eq!(
+            call(vec![word("get"), word("name")]).await,
+            Value::from("Ada")
+        );
+        assert_eq!(
+            call(vec![word("has"), word("email")]).await,
+            Value::Boolean(false)
+        );
+        assert_eq!(
+            call(vec![word("keys")]).await,
+            Value::from(vec![Value::from("name"), Value::from("age")])
+        );
+        let Value::Map(older) = call(vec![word("set"), word("age"), Value::from(37)]).await else {
+            panic!("expected a map");
+        };
+        assert_eq!(older.get_field("age"), Some(&Value::from(37)));
+        assert_eq!(older.len(), 2);
+        assert!(call(vec![word("pop")]).await.is_error());
+    }
+
+    #[tokio::test]
+    async fn test_map_foreach() {
+        let seen = ListAgent::new(vec![]).spawn();
+        let seen = Expr::Value(Value::Channel(seen));
+        let body = Block::new(vec![Statement::Expr(Expr::Call(CallExpr::new(
+            seen.clone(),
+            vec![
+                Expr::Value(word("add")).into(),
+                Expr::Variable("key".into()).into(),
+            ],
+        )))]);
+
+        let mut scope = Scope::new();
+        let args = vec![
+            word("foreach"),
+            word("key"),
+            word("value"),
+            Value::Block(Box::new(body)),
+        ];
+        assert_eq!(call_value(person(), args, &mut scope).await, Value::Empty);
+        // The bindings stay inside the loop
+        assert_eq!(scope.get("key"), None);
+
+        let items = CallExpr::new(seen, vec![Expr::Value(word("items")).into()]);
+        assert_eq!(
+            items.execute_with_reply(&mut scope).await,
+            Value::from(vec![Value::from("name"), Value::from("age")])
+        );
+    }
+}
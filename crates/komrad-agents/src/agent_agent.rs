@@ -3,15 +3,20 @@ use komrad_agent::AgentBehavior;
 use komrad_ast::prelude::{Channel, ChannelListener, Message, ToSexpr, Value};
 use komrad_macros::agent_lifecycle_impl;
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, error};
 
 /// AgentAgent is a syntax proxy bound as `agent`.
 /// It forwards an incoming message such as:
 ///    agent Alice { ... }
 /// as:
 ///    define agent Alice { ... }
-/// to the RegistryAgent.
+/// to the RegistryAgent. If the original message carries a reply channel,
+/// the registry's answer is relayed to it.
+///
+/// Bound as `protocol`, it forwards `protocol Stack { ... }` as
+/// `define protocol Stack { ... }` instead.
 pub struct AgentAgent {
+    keyword: &'static str,
     registry: Channel,
     channel: Channel,
     listener: Arc<ChannelListener>,
@@ -19,8 +24,17 @@ pub struct AgentAgent {
 
 impl AgentAgent {
     pub fn new(registry: Channel) -> Arc<Self> {
+        Self::with_keyword("agent", registry)
+    }
+
+    pub fn protocol(registry: Channel) -> Arc<Self> {
+        Self::with_keyword("protocol", registry)
+    }
+
+    fn with_keyword(keyword: &'static str, registry: Channel) -> Arc<Self> {
         let (channel, listener) = Channel::new(32);
         Arc::new(Self {
+            keyword,
             registry,
             channel,
             listener: Arc::new(listener),
@@ -37,7 +51,7 @@ impl AgentBehavior for AgentAgent {
         // into: [define, agent, Alice, <block>]
         let mut new_terms = Vec::new();
         new_terms.push(Value::Word("define".into()));
-        new_terms.push(Value::Word("agent".into()));
+        new_terms.push(Value::Word(self.keyword.into()));
         for term in msg.terms() {
             new_terms.push(term.clone());
         }
@@ -49,6 +63,14 @@ impl AgentBehavior for AgentAgent {
         let reply_value = reply_chan_rx.recv().await.unwrap();
         debug!("⏭️ Registry reply: {:}", reply_value.to_sexpr().format(0));
 
+        // Relay the registry's answer so `X = agent X {...}` waits for the definition.
+        if let Some(reply_to) = msg.reply_to() {
+            let _ = reply_to.send(reply_value).await;
+        } else if let Some(Value::Error(e)) = reply_value.terms().first() {
+            // Nobody is waiting, e.g. an agent that doesn't implement its protocols
+            error!("define {}: {}", self.keyword, e);
+        }
+
         true
     }
 }
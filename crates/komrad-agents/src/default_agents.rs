@@ -3,8 +3,11 @@ use crate::assert_agent::AssertAgent;
 use crate::fs_agent::FsAgent;
 use crate::io_agent::IoAgent;
 use crate::json_agent::JsonAgent;
+use crate::monitor_agent::MonitorAgent;
 use crate::prelude::StdIo;
 use crate::spawn_agent::SpawnAgent;
+use crate::stop_agent::StopAgent;
+use crate::supervisor::Supervisor;
 use komrad_agent::stdlib_agent::DictAgent;
 use komrad_agent::AgentBehavior;
 use komrad_ast::prelude::Channel;
@@ -15,10 +18,14 @@ pub struct DefaultAgents {
     pub io_agent: Arc<IoAgent>,
     pub fs_agent: Arc<FsAgent>,
     pub agent_agent: Arc<AgentAgent>,
+    pub protocol_agent: Arc<AgentAgent>,
     pub spawn_agent: Arc<SpawnAgent>,
     pub assert_agent: Arc<AssertAgent>,
     pub dict_agent: Arc<DictAgent>,
     pub json_agent: Arc<JsonAgent>,
+    pub monitor_agent: Arc<MonitorAgent>,
+    pub link_agent: Arc<MonitorAgent>,
+    pub stop_agent: Arc<StopAgent>,
 }
 
 pub struct DefaultAgentChannels {
@@ -26,10 +33,14 @@ pub struct DefaultAgentChannels {
     pub fs_agent: Channel,
     pub registry_agent: Channel,
     pub agent_agent: Channel,
+    pub protocol_agent: Channel,
     pub spawn_agent: Channel,
     pub assert_agent: Channel,
     pub dict_agent: Channel,
     pub json_agent: Channel,
+    pub monitor_agent: Channel,
+    pub link_agent: Channel,
+    pub stop_agent: Channel,
 }
 
 /// The channels for each agent constructed within `DefaultAgents`
@@ -39,49 +50,74 @@ pub struct DefaultAgentChannels {
 /// - `Fs` is the file system agent.
 /// - `Registry` is the registry agent.
 /// - `agent` is the agent keyword in Komrad (everything is agents!)
+/// - `protocol` declares a set of handler patterns agents can implement.
 /// - `spawn` is the spawn keyword in Komrad (for spawning agents).
+///   Spawned children are started under the owner's `Supervisor`
+///   once the owner declares `supervise`.
+/// - `monitor` and `link` watch other agents on behalf of the owner.
+/// - `stop` makes another agent exit normally.
 ///
 /// They are organized here to provide a single source of truth.
 ///
 /// One future direction is to use a configuration system to enable
 /// or disable certain agents. (I like the way starlark does this.)
 impl DefaultAgents {
-    pub fn new(registry_channel: Channel) -> (Self, DefaultAgentChannels) {
+    pub fn new(
+        owner: Channel,
+        registry_channel: Channel,
+        supervisor: Arc<Supervisor>,
+    ) -> (Self, DefaultAgentChannels) {
         let io_agent = IoAgent::new(Arc::new(tokio::sync::RwLock::new(StdIo)));
         let fs_agent = FsAgent::new();
         let agent_agent = AgentAgent::new(registry_channel.clone());
-        let spawn_agent = SpawnAgent::new(registry_channel.clone());
+        let protocol_agent = AgentAgent::protocol(registry_channel.clone());
+        let spawn_agent = SpawnAgent::with_supervisor(registry_channel.clone(), supervisor);
         let assert_agent = AssertAgent::new();
         let dict_agent = DictAgent::new();
         let json_agent = JsonAgent::new();
+        let monitor_agent = MonitorAgent::new(owner.clone());
+        let link_agent = MonitorAgent::link(owner);
+        let stop_agent = StopAgent::new();
 
         let io_agent_channel = io_agent.clone().spawn();
         let fs_agent_channel = fs_agent.clone().spawn();
         let agent_agent_channel = agent_agent.clone().spawn();
+        let protocol_agent_channel = protocol_agent.clone().spawn();
         let spawn_agent_channel = spawn_agent.clone().spawn();
         let assert_agent_channel = assert_agent.clone().spawn();
         let dict_agent_channel = dict_agent.clone().spawn();
         let json_agent_channel = json_agent.clone().spawn();
+        let monitor_agent_channel = monitor_agent.clone().spawn();
+        let link_agent_channel = link_agent.clone().spawn();
+        let stop_agent_channel = stop_agent.clone().spawn();
 
         (
             Self {
                 io_agent,
                 fs_agent,
                 agent_agent,
+                protocol_agent,
                 spawn_agent,
                 assert_agent,
                 dict_agent,
                 json_agent,
+                monitor_agent,
+                link_agent,
+                stop_agent,
             },
             DefaultAgentChannels {
                 io_agent: io_agent_channel,
                 fs_agent: fs_agent_channel,
                 registry_agent: registry_channel,
                 agent_agent: agent_agent_channel,
+                protocol_agent: protocol_agent_channel,
                 spawn_agent: spawn_agent_channel,
                 assert_agent: assert_agent_channel,
                 dict_agent: dict_agent_channel,
                 json_agent: json_agent_channel,
+                monitor_agent: monitor_agent_channel,
+                link_agent: link_agent_channel,
+                stop_agent: stop_agent_channel,
             },
         )
     }
@@ -103,10 +139,14 @@ impl DefaultAgentChannels {
 
         // Special Agents (Keywords)
         channels.insert("agent".to_string(), self.agent_agent.clone());
+        channels.insert("protocol".to_string(), self.protocol_agent.clone());
         channels.insert("spawn".to_string(), self.spawn_agent.clone());
         channels.insert("assert".to_string(), self.assert_agent.clone());
         channels.insert("dict".to_string(), self.dict_agent.clone());
         channels.insert("json".to_string(), self.json_agent.clone());
+        channels.insert("monitor".to_string(), self.monitor_agent.clone());
+        channels.insert("link".to_string(), self.link_agent.clone());
+        channels.insert("stop".to_string(), self.stop_agent.clone());
 
         // Return the map of channels
         channels
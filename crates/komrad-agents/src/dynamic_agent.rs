@@ -1,22 +1,45 @@
 use crate::prelude::RegistryAgent;
-use komrad_agent::execute::Execute;
+use crate::supervisor::{Supervisor, SupervisorPolicy};
+use komrad_agent::call_chain;
+use komrad_agent::execute::{Execute, ExecuteWithReply, catching};
 use komrad_agent::try_bind::TryBind;
-use komrad_agent::{AgentBehavior, AgentLifecycle};
+use komrad_agent::{Agent, AgentBehavior, AgentLifecycle};
 use komrad_ast::prelude::{
-    Block, Channel, ChannelListener, Handler, Message, RuntimeError, Statement, ToSexpr, Value,
+    Block, Channel, ChannelListener, Expr, Handler, Message, RuntimeError, Statement, ToSexpr,
+    Value,
 };
 use komrad_ast::scope::Scope;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::{Mutex, RwLock, Semaphore};
+use tokio::task::JoinSet;
 use tracing::{debug, error, trace};
 use tracing_subscriber::registry;
 
 /// A universal dynamic "module" or "agent" that handles an AST block.
+///
+/// The agent's fields and top-level assignments are its state. Each handler
+/// runs in a scope of its own on top of the agent's: assigning a name the
+/// agent already has changes it for the messages that follow, while holes
+/// and any other names a handler binds are gone once it finishes.
+///
+/// An agent handles one message at a time unless its definition says
+/// `concurrent <max-handlers>`. Then each handler runs on a task of its own,
+/// up to that many at once. Their assignments to the agent's state take
+/// effect straight away, so the last to assign a name wins; state that
+/// needs more care belongs in another agent, such as a `List new`. A
+/// handler that panics takes the agent down with it, as it would if the
+/// agent had run it itself.
+#[derive(Clone)]
 pub struct DynamicAgent {
     name: String,             // Possibly store a name for debugging
     scope: Arc<Mutex<Scope>>, // All variables and data
     handlers: Arc<RwLock<Vec<Handler>>>,
+    /// Permits for handlers running at once, if the agent is concurrent.
+    concurrency: Option<Arc<Semaphore>>,
+    /// The handlers running on tasks of their own, if the agent is concurrent.
+    running: Arc<Mutex<JoinSet<()>>>,
+    registry: Channel,
     channel: Channel,
     listener: Arc<ChannelListener>,
 }
@@ -30,10 +53,35 @@ impl DynamicAgent {
         scope: Scope,
         registry_channel: Channel,
     ) -> Arc<Self> {
-        let mut scope = scope.clone();
         let (channel, listener) = Channel::new(32);
-        let (_default_agents, default_channels) =
-            crate::default_agents::DefaultAgents::new(registry_channel.clone());
+        Self::from_block_with_channel(
+            name,
+            block,
+            scope,
+            registry_channel,
+            channel,
+            Arc::new(listener),
+        )
+        .await
+    }
+
+    /// Like `from_block`, but listens on an existing channel. Supervisors use
+    /// this to restart an agent without invalidating its channel.
+    pub async fn from_block_with_channel(
+        name: &str,
+        block: &Block,
+        scope: Scope,
+        registry_channel: Channel,
+        channel: Channel,
+        listener: Arc<ChannelListener>,
+    ) -> Arc<Self> {
+        let mut scope = scope.clone();
+        let supervisor = Supervisor::new(channel.clone());
+        let (_default_agents, default_channels) = crate::default_agents::DefaultAgents::new(
+            channel.clone(),
+            registry_channel.clone(),
+            supervisor.clone(),
+        );
 
         scope
             .set("me".to_string(), Value::Channel(channel.clone()))
@@ -49,15 +97,40 @@ impl DynamicAgent {
         }
 
         let mut collected_handlers = Vec::new();
+        let mut concurrency = None;
 
         // We already have scope from any initial scope block, but now we need to
         // extend this with the scope from the agent's definition block.
-        // This is also where HANDLERS are collected:
+        // This is also where HANDLERS and the SUPERVISION policy are collected:
         for stmt in block.statements() {
             match stmt {
                 Statement::Handler(h) => {
                     collected_handlers.push((**h).clone());
                 }
+                Statement::Expr(Expr::Call(call))
+                    if call.target() == &Expr::Variable("supervise".to_string()) =>
+                {
+                    let mut args = Vec::new();
+                    for arg in call.args() {
+                        args.push(arg.execute(&mut scope).await);
+                    }
+                    match SupervisorPolicy::from_args(&args) {
+                        Ok(policy) => supervisor.set_policy(policy).await,
+                        Err(e) => error!("DynamicAgent {}: {}", name, e),
+                    }
+                }
+                Statement::Expr(Expr::Call(call))
+                    if call.target() == &Expr::Variable("concurrent".to_string()) =>
+                {
+                    let mut args = Vec::new();
+                    for arg in call.args() {
+                        args.push(arg.execute(&mut scope).await);
+                    }
+                    match max_handlers(&args) {
+                        Ok(max) => concurrency = Some(Arc::new(Semaphore::new(max))),
+                        Err(e) => error!("DynamicAgent {}: {}", name, e),
+                    }
+                }
                 _ => {
                     let _ = stmt.execute(&mut scope).await;
                 }
@@ -68,14 +141,55 @@ impl DynamicAgent {
             name: name.to_string(),
             scope: Arc::new(Mutex::new(scope)),
             handlers: Arc::new(RwLock::new(collected_handlers)),
+            concurrency,
+            running: Arc::new(Mutex::new(JoinSet::new())),
+            registry: registry_channel,
             channel,
-            listener: Arc::new(listener),
+            listener,
         })
     }
 
+    /// Evaluates a block inside this agent's scope, as if it had been
+    /// part of the agent's definition. Handlers are appended to the
+    /// agent's handlers and the value of the last statement is returned.
+    /// Calls made as statements wait for their reply, so that it can be.
+    pub async fn eval(&self, block: &Block) -> Value {
+        let mut scope = self.scope.lock().await.clone();
+        let mut last_value = Value::Empty;
+        for stmt in block.statements() {
+            match stmt {
+                Statement::Handler(h) => {
+                    self.handlers.write().await.push((**h).clone());
+                    last_value = Value::Empty;
+                }
+                Statement::NoOp | Statement::Comment(_) => continue,
+                Statement::Expr(Expr::Call(call)) => {
+                    last_value = call.execute_with_reply(&mut scope).await;
+                    if let Value::Error(_) = last_value {
+                        break;
+                    }
+                }
+                _ => {
+                    last_value = stmt.execute(&mut scope).await;
+                    if let Value::Error(_) = last_value {
+                        break;
+                    }
+                }
+            }
+        }
+        last_value
+    }
+
+    /// The agent's bindings, sorted by name.
+    pub async fn bindings(&self) -> Vec<(String, Value)> {
+        let mut bindings: Vec<(String, Value)> = self.scope.lock().await.iter().collect();
+        bindings.sort_by(|a, b| a.0.cmp(&b.0));
+        bindings
+    }
+
     async fn handle_builtins(&self, msg: Message, scope: &mut Scope) -> Option<bool> {
         // Check if the message is a built-in command
-        match msg.first_word().unwrap().as_str() {
+        match msg.first_word()?.as_str() {
             "get" => {
                 if msg.rest().len() != 1 {
                     let reply_value = Value::Error(RuntimeError::InvalidArugments(
@@ -117,47 +231,47 @@ impl DynamicAgent {
                     }
                 }
             }
+            "implements?" => {
+                let reply_value = match msg.rest() {
+                    [Value::Word(protocol)] => self.implements(protocol).await,
+                    _ => Value::Error(RuntimeError::InvalidArugments(
+                        "implements? requires 1 argument (protocol)".to_string(),
+                    )),
+                };
+                if let Some(reply_to) = msg.reply_to() {
+                    let reply_msg = Message::new(vec![reply_value], None);
+                    if let Err(e) = reply_to.send(reply_msg).await {
+                        debug!(
+                            "DynamicAgent {} -> implements? reply error: {:?}",
+                            self.name, e
+                        );
+                    }
+                }
+                return Some(true);
+            }
             _ => {}
         }
         None
     }
-}
-
-#[async_trait::async_trait]
-impl AgentLifecycle for DynamicAgent {
-    async fn get_scope(&self) -> Arc<Mutex<Scope>> {
-        self.scope.clone()
-    }
-
-    fn channel(&self) -> &Channel {
-        &self.channel
-    }
-
-    fn listener(&self) -> Arc<ChannelListener> {
-        self.listener.clone()
-    }
-}
-
-#[async_trait::async_trait]
-impl AgentBehavior for DynamicAgent {
-    async fn handle_message(&self, msg: Message) -> bool {
-        debug!("😎 {} handling {:}", self.name, msg.to_sexpr().format(0));
 
+    /// Runs the first handler whose pattern matches the message, binding
+    /// its holes in `scope`, and replies with its result.
+    async fn dispatch(&self, msg: Message, mut scope: Scope) {
         // Copy out the handlers once, to avoid repeated locking
         let local_handlers = self.handlers.read().await.clone();
 
-        // Lock the scope
-        let mut base_scope = self.scope.lock().await.clone();
-
-        if let Some(result) = self.handle_builtins(msg.clone(), &mut base_scope).await {
-            return result;
-        }
-
         // Pattern match against each handler
         for h in &local_handlers {
-            if let Some(mut bound) = h.pattern().try_bind(msg.clone(), &mut base_scope).await {
+            if let Some(mut bound) = h.pattern().try_bind(msg.clone(), &mut scope).await {
                 let block = h.block();
-                let result = block.execute(&mut bound).await;
+                let me = self.channel.clone().with_agent_type(&self.name);
+                let handling =
+                    call_chain::handling(msg.call_chain(), me, block.execute(&mut bound));
+                let result = match msg.reply_to() {
+                    // An error goes back to the caller, to catch or report
+                    Some(_) => catching(handling).await,
+                    None => handling.await,
+                };
                 if let Some(reply_to) = msg.reply_to() {
                     let reply_msg = Message::new(vec![result.clone()], None);
                     match reply_to.send(reply_msg).await {
@@ -166,6 +280,9 @@ impl AgentBehavior for DynamicAgent {
                         }
                         Err(e) => {
                             debug!("DynamicAgent {} -> reply error: {:?}", self.name, e);
+                            if let Value::Error(err) = &result {
+                                error!("DynamicAgent {} -> {}", self.name, err);
+                            }
                         }
                     }
                 }
@@ -181,7 +298,7 @@ impl AgentBehavior for DynamicAgent {
                         );
                     }
                 }
-                return true; // handled
+                return; // handled
             }
         }
 
@@ -202,8 +319,343 @@ impl AgentBehavior for DynamicAgent {
                 }
             }
         }
+    }
 
-        // No match, but keep running
+    /// Whether the agent handles every pattern of the named protocol,
+    /// whether or not its definition declared it.
+    async fn implements(&self, protocol: &str) -> Value {
+        if self.channel.implements(protocol) {
+            return Value::Boolean(true);
+        }
+        let lookup = Message::new(
+            vec![
+                Value::Word("lookup".into()),
+                Value::Word("protocol".into()),
+                Value::Word(protocol.to_string()),
+            ],
+            None,
+        );
+        let patterns = match self.registry.send_and_recv(lookup).await {
+            Ok(reply) => match reply.terms().first() {
+                Some(Value::Block(block)) => block.clone(),
+                Some(other) => return other.clone(),
+                None => return Value::Empty,
+            },
+            Err(e) => return Value::Error(e),
+        };
+        let handlers = self.handlers.read().await;
+        Value::Boolean(
+            patterns
+                .statements()
+                .iter()
+                .all(|statement| match statement {
+                    Statement::Handler(pattern) => pattern.pattern().is_handled_by(handlers.iter()),
+                    _ => true,
+                }),
+        )
+    }
+}
+
+impl Agent for DynamicAgent {}
+
+#[async_trait::async_trait]
+impl AgentLifecycle for DynamicAgent {
+    async fn get_scope(&self) -> Arc<Mutex<Scope>> {
+        self.scope.clone()
+    }
+
+    fn channel(&self) -> &Channel {
+        &self.channel
+    }
+
+    fn listener(&self) -> Arc<ChannelListener> {
+        self.listener.clone()
+    }
+}
+
+#[async_trait::async_trait]
+impl AgentBehavior for DynamicAgent {
+    async fn handle_message(&self, msg: Message) -> bool {
+        debug!("😎 {} handling {:}", self.name, msg.to_sexpr().format(0));
+
+        // Lock the scope
+        let mut base_scope = self.scope.lock().await.clone();
+
+        if let Some(result) = self.handle_builtins(msg.clone(), &mut base_scope).await {
+            return result;
+        }
+
+        let Some(semaphore) = &self.concurrency else {
+            self.dispatch(msg, Scope::with_parent(base_scope)).await;
+            return true;
+        };
+        // Waiting for a permit holds up the mailbox until a handler finishes
+        let Ok(permit) = semaphore.clone().acquire_owned().await else {
+            return true;
+        };
+        let agent = self.clone();
+        let timeout = Channel::default_reply_timeout();
+        self.running
+            .lock()
+            .await
+            .spawn(Channel::with_reply_timeout(timeout, async move {
+                agent.dispatch(msg, Scope::with_parent(base_scope)).await;
+                drop(permit);
+            }));
         true
     }
+
+    /// Waits for a concurrent handler to finish, and panics the agent's loop
+    /// if the handler panicked, so that monitors and supervisors see it.
+    async fn extra_event_source(&self) -> Option<Message> {
+        let mut running = self.running.lock().await;
+        if running.is_empty() {
+            // The loop comes back here after the next message
+            drop(running);
+            return std::future::pending().await;
+        }
+        match running.join_next().await {
+            Some(Err(err)) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the argument of `concurrent <max-handlers>`.
+fn max_handlers(args: &[Value]) -> Result<usize, RuntimeError> {
+    match args {
+        [Value::Number(n)] => match n.to_u64() {
+            Some(max) if max > 0 => Ok(max as usize),
+            _ => Err(RuntimeError::InvalidArugments(format!(
+                "concurrent needs at least 1 handler, found {}",
+                n
+            ))),
+        },
+        _ => Err(RuntimeError::InvalidArugments(
+            "concurrent takes the most handlers to run at once".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use komrad_ast::prelude::{
+        CallExpr, ControlMessage, ExitReason, Map, Number, Pattern, TypeExpr,
+    };
+    use std::time::Duration;
+
+    /// `[ask _peer] { answer = peer question; answer }`, after `concurrent 2`
+    /// if the agent is concurrent.
+    async fn asker(concurrent: bool) -> Arc<DynamicAgent> {
+        let mut statements = Vec::new();
+        if concurrent {
+            statements.push(Statement::Expr(Expr::Call(CallExpr::new(
+                Expr::Variable("concurrent".into()),
+                vec![Expr::Value(Value::Number(Number::Int(2))).into()],
+            ))));
+        }
+        let question = Expr::Call(CallExpr::new(
+            Expr::Variable("peer".into()),
+            vec![Expr::Value(Value::Word("question".into())).into()],
+        ));
+        statements.push(Statement::Handler(Arc::new(Handler::new(
+            Pattern::new(vec![
+                TypeExpr::Word("ask".into()),
+                TypeExpr::Hole("peer".into()),
+            ]),
+            Block::new(vec![
+                Statement::Assignment("answer".into(), question),
+                Statement::Expr(Expr::Variable("answer".into())),
+            ]),
+        ))));
+        let (registry, _registry_listener) = Channel::new(1);
+        DynamicAgent::from_block("Asker", &Block::new(statements), Scope::new(), registry).await
+    }
+
+    /// Asks the agent twice and counts the questions that reach the peer
+    /// before either is answered.
+    async fn questions_at_once(agent: Arc<DynamicAgent>) -> usize {
+        let channel = agent.spawn();
+        let (peer, peer_listener) = Channel::new(4);
+        let (replies, replies_listener) = Channel::new(4);
+        for _ in 0..2 {
+            let ask = Message::new(
+                vec![Value::Word("ask".into()), Value::Channel(peer.clone())],
+                Some(replies.clone()),
+            );
+            channel.send(ask).await.unwrap();
+        }
+
+        let wait = Some(Duration::from_millis(100));
+        let mut questions = Vec::new();
+        while let Ok(question) = peer_listener.recv_timeout(wait).await {
+            questions.push(question);
+        }
+        let asked = questions.len();
+        for question in questions {
+            let answer = Message::new(vec![Value::Word("answer".into())], None);
+            question.reply_to().unwrap().send(answer).await.unwrap();
+        }
+        let reply = replies_listener.recv_timeout(wait).await.unwrap();
+        assert_eq!(reply.terms()[0], Value::Word("answer".into()));
+        asked
+    }
+
+    #[tokio::test]
+    async fn test_handles_one_message_at_a_time() {
+        assert_eq!(questions_at_once(asker(false).await).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_handlers() {
+        let agent = asker(true).await;
+        assert_eq!(questions_at_once(agent.clone()).await, 2);
+        // What the handlers bound stayed with them
+        let names: Vec<String> = agent
+            .bindings()
+            .await
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert!(!names.contains(&"answer".to_string()));
+        assert!(!names.contains(&"peer".to_string()));
+    }
+
+    /// A counter like the one in `hyper-listener.kom`. `increment-each`
+    /// assigns `count` from a block nested in the handler.
+    const COUNTER: &str = r#"
+count: Number = 0
+
+[increment _by] {
+	step = by
+	count = count + step
+}
+
+[increment-each _amounts] {
+	amounts foreach key amount {
+		count = count + amount
+	}
+}
+
+[count] {
+	count
+}
+"#;
+
+    async fn counter() -> Arc<DynamicAgent> {
+        let module = komrad_parser::parse_source("counter.kom", COUNTER).unwrap();
+        let (registry, _registry_listener) = Channel::new(1);
+        DynamicAgent::from_block("Counter", &module.build_block(), Scope::new(), registry).await
+    }
+
+    async fn count(channel: &Channel) -> Value {
+        let (replies, replies_listener) = Channel::new(1);
+        let count = Message::new(vec![Value::Word("count".into())], Some(replies));
+        channel.send(count).await.unwrap();
+        let reply = replies_listener
+            .recv_timeout(Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+        reply.terms()[0].clone()
+    }
+
+    #[tokio::test]
+    async fn test_handlers_keep_agent_state() {
+        let agent = counter().await;
+        let channel = agent.clone().spawn();
+        for by in [1, 2, 3] {
+            let increment = Message::new(
+                vec![
+                    Value::Word("increment".into()),
+                    Value::Number(Number::Int(by)),
+                ],
+                None,
+            );
+            channel.send(increment).await.unwrap();
+        }
+        assert_eq!(count(&channel).await, Value::Number(Number::Int(6)));
+
+        // Handler variables and holes didn't outlive their handlers
+        let names: Vec<String> = agent
+            .bindings()
+            .await
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert!(names.contains(&"count".to_string()));
+        assert!(!names.contains(&"step".to_string()));
+        assert!(!names.contains(&"by".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_nested_blocks_assign_agent_state() {
+        let channel = counter().await.spawn();
+        let mut amounts = Map::new();
+        amounts.insert(Value::String("a".into()), Value::Number(Number::Int(4)));
+        let increment = Message::new(
+            vec![Value::Word("increment-each".into()), Value::Map(amounts)],
+            None,
+        );
+        channel.send(increment).await.unwrap();
+        // The foreach block's scope rebinds the agent's field instead of its own
+        assert_eq!(count(&channel).await, Value::Number(Number::Int(4)));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_handler_panic_stops_agent() {
+        let agent = asker(true).await;
+        let channel = agent.clone().spawn();
+        let (monitor, monitor_listener) = Channel::new(1);
+        channel
+            .control(ControlMessage::Monitor(monitor))
+            .await
+            .unwrap();
+
+        agent
+            .running
+            .lock()
+            .await
+            .spawn(async { panic!("handler panicked") });
+        // The agent notices once its loop comes round again
+        let ping = Message::new(vec![Value::Word("ping".into())], None);
+        channel.send(ping).await.unwrap();
+
+        let down = monitor_listener
+            .recv_timeout(Some(Duration::from_millis(100)))
+            .await
+            .unwrap();
+        assert_eq!(down.terms()[2], ExitReason::Panicked.to_value());
+    }
+
+    #[tokio::test]
+    async fn test_stop_and_crash_are_ordinary_messages() {
+        let source = "[stop] { \"stopping\" }\n[crash] { \"crashing\" }";
+        let module = komrad_parser::parse_source("quitter.kom", source).unwrap();
+        let (registry, _registry_listener) = Channel::new(1);
+        let channel =
+            DynamicAgent::from_block("Quitter", &module.build_block(), Scope::new(), registry)
+                .await
+                .spawn();
+
+        // The agent's own handlers answer, and the agent keeps running
+        for (word, answer) in [
+            ("stop", "stopping"),
+            ("crash", "crashing"),
+            ("stop", "stopping"),
+        ] {
+            let msg = Message::new(vec![Value::Word(word.into())], None);
+            let reply = channel.send_and_recv(msg).await.unwrap();
+            assert_eq!(reply.terms()[0], Value::String(answer.into()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_without_a_leading_word() {
+        let channel = counter().await.spawn();
+        let msg = Message::new(vec![Value::Number(Number::Int(1))], None);
+        channel.send(msg).await.unwrap();
+        // The agent ignores it rather than panicking
+        assert_eq!(count(&channel).await, Value::Number(Number::Int(0)));
+    }
 }
@@ -108,7 +108,7 @@ impl FsAgent {
                     }
                 }
                 if let Some(reply_chan) = msg.reply_to() {
-                    let reply = Message::new(vec![Value::List(names)], None);
+                    let reply = Message::new(vec![Value::List(names.into())], None);
                     let _ = reply_chan.send(reply).await;
                 }
             }
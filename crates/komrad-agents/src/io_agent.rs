@@ -1,6 +1,7 @@
+use komrad_agent::list_ops;
 use komrad_agent::{AgentBehavior, AgentLifecycle};
 use komrad_ast::prelude::Message;
-use komrad_ast::prelude::{Channel, ChannelListener, Value};
+use komrad_ast::prelude::{Channel, ChannelListener, ToKomrad, Value};
 use komrad_macros::agent_lifecycle_impl;
 use owo_colors::colored::Color;
 use owo_colors::OwoColorize;
@@ -64,36 +65,7 @@ impl IoAgent {
 
     /// **Helper**: actual logic for "println" commands.
     async fn handle_println(&self, msg: &Message) {
-        let output: Vec<String> = msg.terms()[1..]
-            .iter()
-            .map(|part| match part {
-                Value::List(l) => {
-                    let mut s = String::new();
-                    s.push_str("[");
-                    let inner_s = l
-                        .iter()
-                        .map(|v| match v {
-                            Value::String(s) => s.clone(),
-                            Value::Number(n) => n.to_string(),
-                            Value::Boolean(b) => b.to_string(),
-                            Value::Channel(ch) => format!("Channel: {}", ch.uuid()),
-                            Value::Embedded(b) => b.text().to_string(),
-                            _ => format!("(no formatter: {:?})", v),
-                        })
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    s.push_str(&inner_s);
-                    s.push_str("]");
-                    s
-                }
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Boolean(b) => b.to_string(),
-                Value::Channel(ch) => format!("Channel: {}", ch.uuid()),
-                Value::Embedded(b) => b.text().to_string(),
-                _ => format!("(no formatter: {:?})", part),
-            })
-            .collect();
+        let output = format_terms(&msg.terms()[1..]).await;
 
         {
             let mut io = self.io_interface.write().await;
@@ -111,36 +83,7 @@ impl IoAgent {
 
     /// **Helper**: actual logic for "print" commands.
     async fn handle_print(&self, msg: &Message) {
-        let output: Vec<String> = msg.terms()[1..]
-            .iter()
-            .map(|part| match part {
-                Value::List(l) => {
-                    let mut s = String::new();
-                    s.push_str("[");
-                    let inner_s = l
-                        .iter()
-                        .map(|v| match v {
-                            Value::String(s) => s.clone(),
-                            Value::Number(n) => n.to_string(),
-                            Value::Boolean(b) => b.to_string(),
-                            Value::Channel(ch) => format!("Channel: {}", ch.uuid()),
-                            Value::Embedded(b) => b.text().to_string(),
-                            _ => format!("(no formatter: {:?})", v),
-                        })
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    s.push_str(&inner_s);
-                    s.push_str("]");
-                    s
-                }
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Boolean(b) => b.to_string(),
-                Value::Channel(ch) => format!("Channel: {}", ch.uuid()),
-                Value::Embedded(b) => b.text().to_string(),
-                _ => format!("(no formatter: {:?})", part),
-            })
-            .collect();
+        let output = format_terms(&msg.terms()[1..]).await;
 
         {
             let mut io = self.io_interface.write().await;
@@ -157,6 +100,31 @@ impl IoAgent {
     }
 }
 
+/// Formats each term for printing. A shared list is printed as its items.
+async fn format_terms(terms: &[Value]) -> Vec<String> {
+    let mut output = Vec::with_capacity(terms.len());
+    for term in terms {
+        output.push(format_value(&list_ops::items_of(term).await));
+    }
+    output
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::List(items) => {
+            let items: Vec<String> = items.iter().map(format_value).collect();
+            format!("[{}]", items.join(" "))
+        }
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Channel(ch) => format!("Channel: {}", ch.uuid()),
+        Value::Embedded(b) => b.text().to_string(),
+        Value::Map(_) => value.to_komrad(),
+        _ => format!("(no formatter: {:?})", value),
+    }
+}
+
 agent_lifecycle_impl!(IoAgent);
 
 #[async_trait::async_trait]
@@ -213,4 +181,11 @@ mod tests {
         let reply = reply_listener.recv().await.unwrap();
         assert_eq!(reply.terms()[0], Value::String("ack".into()));
     }
+
+    #[test]
+    fn test_format_nested_list() {
+        let inner = Value::from(vec![Value::from(2), Value::from("b")]);
+        let list = Value::from(vec![Value::from(1), inner, Value::Boolean(true)]);
+        assert_eq!(format_value(&list), "[1 [2 b] true]");
+    }
 }
@@ -1,3 +1,4 @@
+use komrad_agent::list_ops;
 use komrad_agent::{Agent, AgentBehavior};
 use komrad_ast::prelude::{Channel, ChannelListener, Message, Value};
 use komrad_macros::agent_stateless_impl;
@@ -23,7 +24,9 @@ impl AgentBehavior for JsonAgent {
         match cmd.as_str() {
             "encode" => {
                 if let Some(value) = msg.terms().get(1) {
-                    match serde_json::to_string(value) {
+                    // A shared list is encoded as its items
+                    let value = list_ops::items_of(value).await;
+                    match serde_json::to_string(&value.to_json()) {
                         Ok(json_str) => {
                             reply_if_possible(&msg, Value::String(json_str)).await;
                         }
@@ -40,9 +43,9 @@ impl AgentBehavior for JsonAgent {
 
             "decode" => {
                 if let Some(Value::String(json_str)) = msg.terms().get(1) {
-                    match serde_json::from_str::<Value>(json_str) {
-                        Ok(val) => {
-                            reply_if_possible(&msg, val).await;
+                    match serde_json::from_str(json_str) {
+                        Ok(json) => {
+                            reply_if_possible(&msg, Value::from_json(json)).await;
                         }
                         Err(e) => {
                             error!("decode: failed to parse JSON: {}", e);
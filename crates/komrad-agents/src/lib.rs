@@ -6,14 +6,20 @@ mod io_agent;
 
 mod assert_agent;
 mod json_agent;
+mod monitor_agent;
 mod registry_agent;
 mod spawn_agent;
+mod stop_agent;
+mod supervisor;
 
 pub mod prelude {
     pub use crate::agent_agent::AgentAgent;
     pub use crate::default_agents::DefaultAgents;
     pub use crate::dynamic_agent::DynamicAgent;
     pub use crate::io_agent::{IoAgent, StdIo};
+    pub use crate::monitor_agent::MonitorAgent;
     pub use crate::registry_agent::{RegistryAgent, RegistryFactory};
     pub use crate::spawn_agent::SpawnAgent;
+    pub use crate::stop_agent::StopAgent;
+    pub use crate::supervisor::{ChildStart, RestartStrategy, Supervisor, SupervisorPolicy};
 }
@@ -0,0 +1,200 @@
+use komrad_agent::AgentBehavior;
+use komrad_ast::prelude::{
+    Channel, ChannelListener, ControlMessage, ExitReason, Message, RuntimeError, ToSexpr, Value,
+};
+use komrad_macros::agent_lifecycle_impl;
+use std::sync::Arc;
+use tracing::debug;
+
+/// MonitorAgent is a syntax proxy bound as `monitor` and `link`.
+/// Every DynamicAgent gets its own pair, created with its channel as `owner`.
+///
+///    monitor bob
+///
+/// asks bob to send `[down bob <reason>]` to the owner when bob exits.
+///
+///    link bob
+///
+/// does the same in both directions. If bob is already gone, the owner
+/// immediately receives `[down bob noproc]`. Replies with bob's channel.
+pub struct MonitorAgent {
+    owner: Channel,
+    link: bool,
+    channel: Channel,
+    listener: Arc<ChannelListener>,
+}
+
+impl MonitorAgent {
+    pub fn new(owner: Channel) -> Arc<Self> {
+        Self::create(owner, false)
+    }
+
+    pub fn link(owner: Channel) -> Arc<Self> {
+        Self::create(owner, true)
+    }
+
+    fn create(owner: Channel, link: bool) -> Arc<Self> {
+        let (channel, listener) = Channel::new(32);
+        Arc::new(Self {
+            owner,
+            link,
+            channel,
+            listener: Arc::new(listener),
+        })
+    }
+}
+
+agent_lifecycle_impl!(MonitorAgent);
+
+#[async_trait::async_trait]
+impl AgentBehavior for MonitorAgent {
+    async fn handle_message(&self, msg: Message) -> bool {
+        debug!("👀 MonitorAgent {:}", msg.to_sexpr().format(0));
+        let reply = match msg.terms().as_slice() {
+            [Value::Channel(target)] => {
+                let control = if self.link {
+                    ControlMessage::Link(self.owner.clone())
+                } else {
+                    ControlMessage::Monitor(self.owner.clone())
+                };
+                if target.control(control).await.is_err() {
+                    let down = Message::new(
+                        vec![
+                            Value::Word("down".to_string()),
+                            Value::Channel(target.clone()),
+                            ExitReason::NoProc.to_value(),
+                        ],
+                        None,
+                    );
+                    let _ = self.owner.send(down).await;
+                }
+                Value::Channel(target.clone())
+            }
+            _ => Value::Error(RuntimeError::InvalidArugments(
+                "monitor and link take an agent's channel".to_string(),
+            )),
+        };
+        if let Some(reply_to) = msg.reply_to() {
+            let _ = reply_to.send(Message::new(vec![reply], None)).await;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Quitter {
+        channel: Channel,
+        listener: Arc<ChannelListener>,
+    }
+
+    agent_lifecycle_impl!(Quitter);
+
+    #[async_trait::async_trait]
+    impl AgentBehavior for Quitter {
+        async fn handle_message(&self, msg: Message) -> bool {
+            match msg.first_word().as_deref() {
+                Some("crash") => panic!("Quitter crashed"),
+                Some("quit") => false,
+                _ => true,
+            }
+        }
+    }
+
+    fn quitter() -> Channel {
+        let (channel, listener) = Channel::new(8);
+        Arc::new(Quitter {
+            channel,
+            listener: Arc::new(listener),
+        })
+        .spawn()
+    }
+
+    async fn expect_down(listener: &ChannelListener, agent: &Channel, reason: ExitReason) {
+        let down = listener.recv().await.unwrap();
+        assert_eq!(
+            down.terms(),
+            &vec![
+                Value::Word("down".to_string()),
+                Value::Channel(agent.clone()),
+                reason.to_value(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_monitor_reports_panic() {
+        let (owner, owner_listener) = Channel::new(8);
+        let monitor = MonitorAgent::new(owner).spawn();
+        let target = quitter();
+
+        monitor
+            .send_and_recv(Message::new(vec![Value::Channel(target.clone())], None))
+            .await
+            .unwrap();
+        target
+            .send(Message::new(vec![Value::Word("crash".to_string())], None))
+            .await
+            .unwrap();
+
+        expect_down(&owner_listener, &target, ExitReason::Panicked).await;
+    }
+
+    #[tokio::test]
+    async fn test_monitor_reports_stop() {
+        let (owner, owner_listener) = Channel::new(8);
+        let monitor = MonitorAgent::new(owner).spawn();
+        let target = quitter();
+
+        monitor
+            .send_and_recv(Message::new(vec![Value::Channel(target.clone())], None))
+            .await
+            .unwrap();
+        target
+            .send(Message::new(vec![Value::Word("quit".to_string())], None))
+            .await
+            .unwrap();
+
+        expect_down(&owner_listener, &target, ExitReason::Stopped).await;
+    }
+
+    #[tokio::test]
+    async fn test_link_is_mutual() {
+        let alice = quitter();
+        let (bob, bob_listener) = Channel::new(8);
+        let link = MonitorAgent::link(bob.clone()).spawn();
+
+        // Link bob (not an agent, just a listener) to alice, then make alice die.
+        link.send_and_recv(Message::new(vec![Value::Channel(alice.clone())], None))
+            .await
+            .unwrap();
+        alice
+            .send(Message::new(vec![Value::Word("crash".to_string())], None))
+            .await
+            .unwrap();
+        expect_down(&bob_listener, &alice, ExitReason::Panicked).await;
+
+        // Alice asked bob to monitor her back.
+        match bob_listener.recv_control().await.unwrap() {
+            ControlMessage::Monitor(monitor) => assert_eq!(monitor, alice),
+            _ => panic!("Expected a Monitor control message"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_monitor_dead_agent() {
+        let (owner, owner_listener) = Channel::new(8);
+        let monitor = MonitorAgent::new(owner).spawn();
+        let (target, target_listener) = Channel::new(8);
+        drop(target_listener);
+
+        monitor
+            .send_and_recv(Message::new(vec![Value::Channel(target.clone())], None))
+            .await
+            .unwrap();
+
+        expect_down(&owner_listener, &target, ExitReason::NoProc).await;
+    }
+}
@@ -2,7 +2,10 @@ use crate::dynamic_agent::DynamicAgent;
 use komrad_agent::execute::Execute;
 use komrad_agent::stdlib_agent::ListAgentFactory;
 use komrad_agent::{AgentBehavior, AgentFactory, AgentLifecycle};
-use komrad_ast::prelude::{Block, Channel, ChannelListener, Message, RuntimeError, ToSexpr, Value};
+use komrad_ast::prelude::{
+    Block, Channel, ChannelListener, Handler, Message, Pattern, RuntimeError, Statement, ToKomrad,
+    ToSexpr, Value,
+};
 use komrad_ast::scope::Scope;
 
 #[cfg(feature = "templates")]
@@ -28,9 +31,13 @@ pub enum RegistryFactory {
     FromFactory(Arc<dyn AgentFactory>),
 }
 
-/// RegistryAgent holds definitions of agents as AST Blocks.
+/// RegistryAgent holds definitions of agents as AST Blocks, and the
+/// protocols (named sets of handler patterns) they may implement.
 pub struct RegistryAgent {
     pub registry: RwLock<HashMap<String, RegistryFactory>>,
+    pub protocols: RwLock<HashMap<String, Vec<Pattern>>>,
+    /// The protocols each agent defined from a block declared.
+    implements: RwLock<HashMap<String, Vec<String>>>,
     channel: Channel,
     listener: Arc<ChannelListener>,
 }
@@ -75,12 +82,108 @@ impl RegistryAgent {
         );
         let registry = RwLock::new(initial_registry);
 
+        // The protocol the list expander `...` relies on
+        let mut protocols = HashMap::new();
+        protocols.insert("List".to_string(), Pattern::list_protocol());
+
         Arc::new(Self {
             registry,
+            protocols: RwLock::new(protocols),
+            implements: RwLock::new(HashMap::new()),
             channel,
             listener: Arc::new(listener),
         })
     }
+
+    /// Registers `[define agent Name Block]`, or
+    /// `[define agent Name implements Protocol... Block]` once every pattern
+    /// of the named protocols is handled by one of the block's handlers.
+    async fn define_agent(&self, name: &str, rest: &[Value]) -> Value {
+        let Some((Value::Block(block), clause)) = rest.split_last() else {
+            return Value::Error(RuntimeError::InvalidAgentDefinition);
+        };
+        let protocols = match clause {
+            [] => Vec::new(),
+            [Value::Word(keyword), names @ ..] if keyword == "implements" && !names.is_empty() => {
+                let mut protocols = Vec::new();
+                for name in names {
+                    match name {
+                        Value::Word(name) => protocols.push(name.clone()),
+                        _ => return Value::Error(RuntimeError::InvalidAgentDefinition),
+                    }
+                }
+                protocols
+            }
+            _ => return Value::Error(RuntimeError::InvalidAgentDefinition),
+        };
+
+        {
+            let defined = self.protocols.read().await;
+            for protocol in &protocols {
+                let Some(patterns) = defined.get(protocol) else {
+                    return Value::Error(RuntimeError::ProtocolNotDefined(protocol.clone()));
+                };
+                let missing: Vec<String> = patterns
+                    .iter()
+                    .filter(|pattern| !pattern.is_handled_by(handlers(block)))
+                    .map(|pattern| pattern.to_komrad())
+                    .collect();
+                if !missing.is_empty() {
+                    return Value::Error(RuntimeError::ProtocolNotImplemented(format!(
+                        "{} has no handler for {} from {}",
+                        name,
+                        missing.join(", "),
+                        protocol
+                    )));
+                }
+            }
+        }
+
+        self.registry
+            .write()
+            .await
+            .insert(name.to_string(), RegistryFactory::FromBlock(*block.clone()));
+        self.implements
+            .write()
+            .await
+            .insert(name.to_string(), protocols);
+        Value::Word(name.to_string())
+    }
+
+    /// Registers `[define protocol Name Block]`. Only the patterns of the
+    /// block's handlers matter; their bodies are ignored.
+    async fn define_protocol(&self, name: &str, block: &Block) -> Value {
+        let patterns = handlers(block)
+            .map(|handler| handler.pattern().clone())
+            .collect();
+        self.protocols
+            .write()
+            .await
+            .insert(name.to_string(), patterns);
+        Value::Word(name.to_string())
+    }
+}
+
+fn handlers(block: &Block) -> impl Iterator<Item = &Handler> {
+    block
+        .statements()
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Handler(handler) => Some(handler.as_ref()),
+            _ => None,
+        })
+}
+
+/// A protocol's patterns as handlers with empty bodies, the way it is declared.
+fn protocol_block(patterns: &[Pattern]) -> Block {
+    Block::new(
+        patterns
+            .iter()
+            .map(|pattern| {
+                Statement::Handler(Arc::new(Handler::new(pattern.clone(), Block::new(vec![]))))
+            })
+            .collect(),
+    )
 }
 
 #[async_trait::async_trait]
@@ -107,72 +210,22 @@ impl AgentBehavior for RegistryAgent {
             match cmd.as_str() {
                 "define" => {
                     debug!("RegistryAgent: define command received");
-                    let terms = msg.terms();
-                    if terms.len() < 4 {
-                        if let Some(reply_chan) = msg.reply_to() {
-                            let reply = Message::new(
-                                vec![Value::Error(RuntimeError::InvalidAgentDefinition)],
-                                None,
-                            );
-                            let _ = reply_chan.send(reply).await;
-                        }
-                        return true;
-                    }
-                    // Check that the second term is "agent"
-                    if let Value::Word(ref keyword) = terms[1] {
-                        if keyword != "agent" {
-                            if let Some(reply_chan) = msg.reply_to() {
-                                let reply = Message::new(
-                                    vec![Value::Error(RuntimeError::InvalidAgentDefinition)],
-                                    None,
-                                );
-                                let _ = reply_chan.send(reply).await;
-                            }
-                            return true;
-                        }
-                    } else {
-                        if let Some(reply_chan) = msg.reply_to() {
-                            let reply = Message::new(
-                                vec![Value::Error(RuntimeError::InvalidAgentDefinition)],
-                                None,
-                            );
-                            let _ = reply_chan.send(reply).await;
-                        }
-                        return true;
-                    }
-                    // Third term is the agent name.
-                    let agent_name = if let Value::Word(ref name) = terms[2] {
-                        name.clone()
-                    } else {
-                        if let Some(reply_chan) = msg.reply_to() {
-                            let reply = Message::new(
-                                vec![Value::Error(RuntimeError::InvalidAgentDefinition)],
-                                None,
-                            );
-                            let _ = reply_chan.send(reply).await;
-                        }
-                        return true;
-                    };
-                    // Fourth term must be a Block.
-                    if let Value::Block(boxed_block) = &terms[3] {
-                        let block = *boxed_block.clone();
+                    let reply = match msg.terms().as_slice() {
+                        [_, Value::Word(keyword), Value::Word(name), rest @ ..]
+                            if keyword == "agent" =>
                         {
-                            let mut reg = self.registry.write().await;
-                            reg.insert(agent_name.clone(), RegistryFactory::FromBlock(block));
-                        }
-                        if let Some(reply_chan) = msg.reply_to() {
-                            // We send a confirmation string on success.
-                            let reply = Message::new(vec![Value::Word(agent_name)], None);
-                            let _ = reply_chan.send(reply).await;
-                        }
-                    } else {
-                        if let Some(reply_chan) = msg.reply_to() {
-                            let reply = Message::new(
-                                vec![Value::Error(RuntimeError::InvalidAgentDefinition)],
-                                None,
-                            );
-                            let _ = reply_chan.send(reply).await;
+                            self.define_agent(name, rest).await
                         }
+                        [
+                            _,
+                            Value::Word(keyword),
+                            Value::Word(name),
+                            Value::Block(block),
+                        ] if keyword == "protocol" => self.define_protocol(name, block).await,
+                        _ => Value::Error(RuntimeError::InvalidAgentDefinition),
+                    };
+                    if let Some(reply_chan) = msg.reply_to() {
+                        let _ = reply_chan.send(Message::new(vec![reply], None)).await;
                     }
                 }
                 "spawn" => {
@@ -249,11 +302,24 @@ impl AgentBehavior for RegistryAgent {
                         // Invoke the correct factory method
                         let agent_chan = match reg.get(&agent_name).unwrap() {
                             RegistryFactory::FromBlock(block) => {
-                                let agent = DynamicAgent::from_block(
+                                let protocols = self
+                                    .implements
+                                    .read()
+                                    .await
+                                    .get(&agent_name)
+                                    .cloned()
+                                    .unwrap_or_default();
+                                // Tagged before the agent binds it as `me`
+                                let (channel, listener) = Channel::new(32);
+                                let agent = DynamicAgent::from_block_with_channel(
                                     &agent_name,
                                     block,
                                     initial_scope,
                                     self.channel.clone(),
+                                    channel
+                                        .with_agent_type(&agent_name)
+                                        .with_protocols(protocols),
+                                    Arc::new(listener),
                                 )
                                 .await;
                                 info!("RegistryAgent: spawning agent {} from block", agent_name);
@@ -262,7 +328,7 @@ impl AgentBehavior for RegistryAgent {
                             RegistryFactory::FromFactory(factory) => {
                                 let agent = factory.create_agent(&agent_name, initial_scope);
                                 info!("RegistryAgent: spawning agent {} from factory", agent_name);
-                                agent.clone().spawn()
+                                agent.clone().spawn().with_agent_type(&agent_name)
                             }
                         };
                         if let Some(reply_chan) = msg.reply_to() {
@@ -279,6 +345,55 @@ impl AgentBehavior for RegistryAgent {
                         }
                     }
                 }
+                "lookup" => {
+                    // lookup agent Name -> the defining Block followed by the
+                    // protocols it implements, so callers such as supervisors
+                    // can build (and rebuild) the agent themselves.
+                    // lookup protocol Name -> the protocol's patterns as a Block.
+                    debug!("RegistryAgent: lookup command received");
+                    let reply = match msg.terms().as_slice() {
+                        [_, Value::Word(keyword), Value::Word(agent_name)]
+                            if keyword == "agent" =>
+                        {
+                            match self.registry.read().await.get(agent_name) {
+                                Some(RegistryFactory::FromBlock(block)) => {
+                                    let mut terms = vec![Value::Block(Box::new(block.clone()))];
+                                    if let Some(protocols) =
+                                        self.implements.read().await.get(agent_name)
+                                    {
+                                        terms.extend(protocols.iter().cloned().map(Value::Word));
+                                    }
+                                    terms
+                                }
+                                Some(RegistryFactory::FromFactory(_)) => {
+                                    vec![Value::Error(RuntimeError::InvalidArugments(format!(
+                                        "{} is a native agent and has no block",
+                                        agent_name
+                                    )))]
+                                }
+                                None => vec![Value::Error(RuntimeError::AgentNotRegistered(
+                                    agent_name.clone(),
+                                ))],
+                            }
+                        }
+                        [_, Value::Word(keyword), Value::Word(protocol)]
+                            if keyword == "protocol" =>
+                        {
+                            match self.protocols.read().await.get(protocol) {
+                                Some(patterns) => {
+                                    vec![Value::Block(Box::new(protocol_block(patterns)))]
+                                }
+                                None => vec![Value::Error(RuntimeError::ProtocolNotDefined(
+                                    protocol.clone(),
+                                ))],
+                            }
+                        }
+                        _ => vec![Value::Error(RuntimeError::InvalidAgentDefinition)],
+                    };
+                    if let Some(reply_chan) = msg.reply_to() {
+                        let _ = reply_chan.send(Message::new(reply, None)).await;
+                    }
+                }
                 _ => {
                     // Unknown command; for now, ignore.
                 }
@@ -291,7 +406,7 @@ impl AgentBehavior for RegistryAgent {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use komrad_ast::prelude::{Block, Message, Statement, Value};
+    use komrad_ast::prelude::{Block, Message, Statement, TypeExpr, Value, ValueType};
 
     #[tokio::test]
     async fn test_define_agent_valid() {
@@ -381,7 +496,7 @@ mod tests {
 
         let reply = reply_listener.recv().await.unwrap();
         match reply.terms().get(0) {
-            Some(Value::Channel(_ch)) => { /* success */ }
+            Some(Value::Channel(ch)) => assert_eq!(ch.agent_type(), Some("Alice")),
             other => panic!("Expected a channel, got {:?}", other),
         }
     }
@@ -405,7 +520,157 @@ mod tests {
         let reply = reply_listener.recv().await.unwrap();
         assert_eq!(
             reply.terms(),
-            &[Value::Error(RuntimeError::AgentNotRegistered)]
+            &[Value::Error(RuntimeError::AgentNotRegistered(
+                "Bob".to_string()
+            ))]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_agent() {
+        let registry = RegistryAgent::new();
+        let reg_chan = registry.clone().spawn();
+
+        let block = Block::new(vec![Statement::NoOp]);
+        {
+            let mut reg_map = registry.registry.write().await;
+            reg_map.insert(
+                "Alice".to_string(),
+                RegistryFactory::FromBlock(block.clone()),
+            );
+        }
+
+        let (reply_chan, reply_listener) = Channel::new(10);
+        let msg = Message::new(
+            vec![
+                Value::Word("lookup".into()),
+                Value::Word("agent".into()),
+                Value::Word("Alice".into()),
+            ],
+            Some(reply_chan.clone()),
+        );
+        reg_chan.send(msg).await.unwrap();
+
+        let reply = reply_listener.recv().await.unwrap();
+        assert_eq!(reply.terms(), &[Value::Block(Box::new(block))]);
+    }
+
+    fn handler(terms: Vec<TypeExpr>) -> Statement {
+        Statement::Handler(Arc::new(Handler::new(
+            Pattern::new(terms),
+            Block::new(vec![]),
+        )))
+    }
+
+    async fn define(reg_chan: &Channel, terms: Vec<Value>) -> Vec<Value> {
+        let mut message = vec![Value::Word("define".into())];
+        message.extend(terms);
+        let reply = reg_chan
+            .send_and_recv(Message::new(message, None))
+            .await
+            .unwrap();
+        reply.terms().clone()
+    }
+
+    #[tokio::test]
+    async fn test_define_agent_implementing_protocol() {
+        let registry = RegistryAgent::new();
+        let reg_chan = registry.clone().spawn();
+
+        let stack = Block::new(vec![
+            handler(vec![
+                TypeExpr::Word("push".into()),
+                TypeExpr::Hole("x".into()),
+            ]),
+            handler(vec![TypeExpr::Word("pop".into())]),
+        ]);
+        let reply = define(
+            &reg_chan,
+            vec![
+                Value::Word("protocol".into()),
+                Value::Word("Stack".into()),
+                Value::Block(Box::new(stack.clone())),
+            ],
+        )
+        .await;
+        assert_eq!(reply, vec![Value::Word("Stack".into())]);
+
+        // `[pop]` is missing
+        let partial = Block::new(vec![handler(vec![
+            TypeExpr::Word("push".into()),
+            TypeExpr::Hole("item".into()),
+        ])]);
+        let reply = define(
+            &reg_chan,
+            vec![
+                Value::Word("agent".into()),
+                Value::Word("Pile".into()),
+                Value::Word("implements".into()),
+                Value::Word("Stack".into()),
+                Value::Block(Box::new(partial)),
+            ],
+        )
+        .await;
+        assert_eq!(
+            reply,
+            vec![Value::Error(RuntimeError::ProtocolNotImplemented(
+                "Pile has no handler for [pop] from Stack".into()
+            ))]
+        );
+        assert!(!registry.registry.read().await.contains_key("Pile"));
+
+        let reply = define(
+            &reg_chan,
+            vec![
+                Value::Word("agent".into()),
+                Value::Word("Pile".into()),
+                Value::Word("implements".into()),
+                Value::Word("Stack".into()),
+                Value::Block(Box::new(stack)),
+            ],
+        )
+        .await;
+        assert_eq!(reply, vec![Value::Word("Pile".into())]);
+
+        let spawn = Message::new(
+            vec![
+                Value::Word("spawn".into()),
+                Value::Word("agent".into()),
+                Value::Word("Pile".into()),
+            ],
+            None,
+        );
+        let reply = reg_chan.send_and_recv(spawn).await.unwrap();
+        match reply.terms().first() {
+            Some(Value::Channel(ch)) => {
+                assert!(ch.implements("Stack"));
+                assert!(Value::Channel(ch.clone()).has_type(&ValueType::User("Stack".into())));
+            }
+            other => panic!("Expected a channel, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_define_agent_unknown_protocol() {
+        let registry = RegistryAgent::new();
+        let reg_chan = registry.clone().spawn();
+
+        let reply = define(
+            &reg_chan,
+            vec![
+                Value::Word("agent".into()),
+                Value::Word("Pile".into()),
+                Value::Word("implements".into()),
+                Value::Word("Queue".into()),
+                Value::Block(Box::new(Block::new(vec![]))),
+            ],
+        )
+        .await;
+        assert_eq!(
+            reply,
+            vec![Value::Error(RuntimeError::ProtocolNotDefined(
+                "Queue".into()
+            ))]
         );
     }
 }
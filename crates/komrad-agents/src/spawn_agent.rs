@@ -1,9 +1,13 @@
+use crate::dynamic_agent::DynamicAgent;
 use crate::registry_agent::RegistryAgent;
-use komrad_agent::AgentBehavior;
-use komrad_ast::prelude::{Channel, ChannelListener, Message, ToSexpr, Value};
+use crate::supervisor::{ChildStart, Supervisor};
+use komrad_agent::execute::Execute;
+use komrad_agent::{Agent, AgentBehavior};
+use komrad_ast::prelude::{Block, Channel, ChannelListener, Message, ToSexpr, Value};
+use komrad_ast::scope::Scope;
 use komrad_macros::agent_lifecycle_impl;
 use std::sync::Arc;
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// SpawnAgent is a syntax proxy bound as `spawn`.
 /// It forwards messages like:
@@ -11,8 +15,13 @@ use tracing::debug;
 /// as:
 ///    spawn agent Bob { ... }
 /// to the RegistryAgent.
+///
+/// When the owning agent declares `supervise`, agents defined from blocks
+/// are instead started under its `Supervisor`, which can rebuild them
+/// from the definition looked up in the registry.
 pub struct SpawnAgent {
     registry: Channel,
+    supervisor: Option<Arc<Supervisor>>,
     channel: Channel,
     listener: Arc<ChannelListener>,
 }
@@ -23,10 +32,83 @@ impl SpawnAgent {
         let (channel, listener) = Channel::new(32);
         Arc::new(Self {
             registry,
+            supervisor: None,
             channel,
             listener: Arc::new(listener),
         })
     }
+
+    /// Creates a SpawnAgent whose children are supervised by `supervisor`
+    /// once it has a policy.
+    pub fn with_supervisor(registry: Channel, supervisor: Arc<Supervisor>) -> Arc<Self> {
+        let (channel, listener) = Channel::new(32);
+        Arc::new(Self {
+            registry,
+            supervisor: Some(supervisor),
+            channel,
+            listener: Arc::new(listener),
+        })
+    }
+
+    /// Starts `name` under the supervisor. Returns `None` if the agent has no
+    /// block definition (native agents), so the caller can fall back to the registry.
+    async fn spawn_supervised(
+        &self,
+        supervisor: &Arc<Supervisor>,
+        name: &str,
+        init: Block,
+    ) -> Option<Value> {
+        let lookup = Message::new(
+            vec![
+                Value::Word("lookup".into()),
+                Value::Word("agent".into()),
+                Value::Word(name.to_string()),
+            ],
+            None,
+        );
+        let (block, protocols) = match self.registry.send_and_recv(lookup).await {
+            Ok(reply) => match reply.terms().split_first() {
+                Some((Value::Block(block), protocols)) => (
+                    *block.clone(),
+                    protocols
+                        .iter()
+                        .filter_map(|protocol| match protocol {
+                            Value::Word(protocol) => Some(protocol.clone()),
+                            _ => None,
+                        })
+                        .collect(),
+                ),
+                Some((Value::Error(e), _)) => {
+                    warn!("SpawnAgent: cannot supervise {}: {}", name, e);
+                    return None;
+                }
+                _ => return None,
+            },
+            Err(e) => return Some(Value::Error(e)),
+        };
+
+        let registry = self.registry.clone();
+        let agent_name = name.to_string();
+        let start: ChildStart = Arc::new(move |channel, listener| {
+            let registry = registry.clone();
+            let name = agent_name.clone();
+            let block = block.clone();
+            let init = init.clone();
+            Box::pin(async move {
+                let mut scope = Scope::new();
+                init.execute(&mut scope).await;
+                let agent: Arc<dyn Agent> = DynamicAgent::from_block_with_channel(
+                    &name, &block, scope, registry, channel, listener,
+                )
+                .await;
+                agent
+            })
+        });
+
+        Some(Value::Channel(
+            supervisor.start_child(name, protocols, start).await,
+        ))
+    }
 }
 
 agent_lifecycle_impl!(SpawnAgent);
@@ -34,6 +116,22 @@ agent_lifecycle_impl!(SpawnAgent);
 #[async_trait::async_trait]
 impl AgentBehavior for SpawnAgent {
     async fn handle_message(&self, msg: Message) -> bool {
+        if let Some(supervisor) = &self.supervisor
+            && supervisor.is_active().await
+            && let Some(Value::Word(name)) = msg.terms().first()
+        {
+            let init = match msg.terms().get(1) {
+                Some(Value::Block(block)) => *block.clone(),
+                _ => Block::new(vec![]),
+            };
+            if let Some(reply) = self.spawn_supervised(supervisor, name, init).await {
+                if let Some(reply_chan) = msg.reply_to() {
+                    let _ = reply_chan.send(Message::new(vec![reply], None)).await;
+                }
+                return true;
+            }
+        }
+
         // Transform a message like: [Bob, ...] into: [spawn, agent, Bob, ...]
         let mut new_terms = Vec::new();
         new_terms.push(Value::Word("spawn".into()));
@@ -91,12 +189,9 @@ mod tests {
 
         let (reply_chan, reply_listener) = Channel::new(10);
 
-        // Send a properly formatted "spawn agent" message with an undefined agent.
+        // Ask for an undefined agent; the SpawnAgent adds "spawn agent" itself.
         let msg = Message::new(
-            vec![
-                Value::Word("agent".into()),
-                Value::Word("NonExistent".into()),
-            ],
+            vec![Value::Word("NonExistent".into())],
             Some(reply_chan.clone()),
         );
 
@@ -112,4 +207,36 @@ mod tests {
             ))]
         );
     }
+
+    #[tokio::test]
+    async fn test_spawn_agent_supervised() {
+        use crate::supervisor::SupervisorPolicy;
+
+        let registry = RegistryAgent::new();
+        let registry_channel = registry.clone().spawn();
+        {
+            let mut reg_map = registry.registry.write().await;
+            reg_map.insert(
+                "Bob".to_string(),
+                RegistryFactory::FromBlock(komrad_ast::prelude::Block::new(vec![])),
+            );
+        }
+
+        let (parent, _parent_listener) = Channel::new(10);
+        let supervisor = Supervisor::new(parent);
+        supervisor.set_policy(SupervisorPolicy::default()).await;
+
+        let spawn_agent = SpawnAgent::with_supervisor(registry_channel, supervisor.clone());
+        let spawn_chan = spawn_agent.clone().spawn();
+
+        let (reply_chan, reply_listener) = Channel::new(10);
+        let msg = Message::new(vec![Value::Word("Bob".into())], Some(reply_chan.clone()));
+        spawn_chan.send(msg).await.unwrap();
+
+        let reply = reply_listener.recv().await.unwrap();
+        match reply.terms().get(0) {
+            Some(Value::Channel(_ch)) => { /* success */ }
+            other => panic!("Expected a channel, got {:?}", other),
+        }
+    }
 }
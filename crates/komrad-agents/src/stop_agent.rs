@@ -0,0 +1,76 @@
+use komrad_agent::AgentBehavior;
+use komrad_ast::prelude::{
+    Channel, ChannelListener, ControlMessage, Message, RuntimeError, ToSexpr, Value,
+};
+use komrad_macros::agent_lifecycle_impl;
+use std::sync::Arc;
+use tracing::debug;
+
+/// StopAgent is a syntax proxy bound as `stop`.
+///
+///    stop bob
+///
+/// sends bob `ControlMessage::Stop`, so bob exits normally and its monitors
+/// receive `[down bob stopped]`. Being a control message, it doesn't go
+/// through bob's handlers, and a `[stop]` handler is just another handler.
+/// Replies with bob's channel.
+pub struct StopAgent {
+    channel: Channel,
+    listener: Arc<ChannelListener>,
+}
+
+impl StopAgent {
+    pub fn new() -> Arc<Self> {
+        let (channel, listener) = Channel::new(32);
+        Arc::new(Self {
+            channel,
+            listener: Arc::new(listener),
+        })
+    }
+}
+
+agent_lifecycle_impl!(StopAgent);
+
+#[async_trait::async_trait]
+impl AgentBehavior for StopAgent {
+    async fn handle_message(&self, msg: Message) -> bool {
+        debug!("✋ StopAgent {:}", msg.to_sexpr().format(0));
+        let reply = match msg.terms().as_slice() {
+            [Value::Channel(target)] => {
+                // An agent that's already gone has nothing left to stop
+                if let Err(e) = target.control(ControlMessage::Stop).await {
+                    debug!("StopAgent -> stop error: {:?}", e);
+                }
+                Value::Channel(target.clone())
+            }
+            _ => Value::Error(RuntimeError::InvalidArugments(
+                "stop takes an agent's channel".to_string(),
+            )),
+        };
+        if let Some(reply_to) = msg.reply_to() {
+            let _ = reply_to.send(Message::new(vec![reply], None)).await;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stop_sends_control_message() {
+        let stop = StopAgent::new().spawn();
+        let (target, target_listener) = Channel::new(8);
+
+        let reply = stop
+            .send_and_recv(Message::new(vec![Value::Channel(target.clone())], None))
+            .await
+            .unwrap();
+        assert_eq!(reply.terms(), &vec![Value::Channel(target)]);
+        assert!(matches!(
+            target_listener.recv_control().await,
+            Ok(ControlMessage::Stop)
+        ));
+    }
+}
@@ -0,0 +1,501 @@
+use komrad_agent::Agent;
+use komrad_ast::prelude::{
+    Channel, ChannelListener, ControlMessage, ExitReason, Message, Number, RuntimeError, Value,
+};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tokio::task::{AbortHandle, JoinHandle};
+use tracing::{error, info, warn};
+
+/// Builds a fresh incarnation of a child on an existing channel, so that
+/// everyone holding the child's `Channel` keeps talking to it across restarts.
+pub type ChildStart = Arc<
+    dyn Fn(Channel, Arc<ChannelListener>) -> Pin<Box<dyn Future<Output = Arc<dyn Agent>> + Send>>
+        + Send
+        + Sync,
+>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestartStrategy {
+    /// Restart only the child that died.
+    OneForOne,
+    /// Restart every child when one dies.
+    OneForAll,
+    /// Restart the child that died and every child started after it.
+    RestForOne,
+}
+
+/// How a supervisor reacts to a child dying.
+///
+/// Declared in an agent's definition block:
+///
+/// ```komrad
+/// agent App {
+///     supervise one-for-one 3 5
+/// }
+/// ```
+///
+/// restarts crashed children one at a time, giving up if more than
+/// 3 restarts happen within 5 seconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupervisorPolicy {
+    pub strategy: RestartStrategy,
+    pub max_restarts: usize,
+    pub within: Duration,
+}
+
+impl Default for SupervisorPolicy {
+    fn default() -> Self {
+        SupervisorPolicy {
+            strategy: RestartStrategy::OneForOne,
+            max_restarts: 3,
+            within: Duration::from_secs(5),
+        }
+    }
+}
+
+impl SupervisorPolicy {
+    /// Parses the arguments of `supervise <strategy> [max-restarts] [within-seconds]`.
+    pub fn from_args(args: &[Value]) -> Result<Self, RuntimeError> {
+        let mut policy = SupervisorPolicy::default();
+
+        policy.strategy = match args.first() {
+            Some(Value::Word(strategy)) => match strategy.as_str() {
+                "one-for-one" => RestartStrategy::OneForOne,
+                "one-for-all" => RestartStrategy::OneForAll,
+                "rest-for-one" => RestartStrategy::RestForOne,
+                other => {
+                    return Err(RuntimeError::InvalidArugments(format!(
+                        "unknown supervision strategy {}",
+                        other
+                    )));
+                }
+            },
+            None => policy.strategy,
+            Some(other) => {
+                return Err(RuntimeError::InvalidArugments(format!(
+                    "expected a supervision strategy, found {}",
+                    other
+                )));
+            }
+        };
+        if let Some(max_restarts) = args.get(1) {
+            policy.max_restarts = as_u64(max_restarts)? as usize;
+        }
+        if let Some(within) = args.get(2) {
+            policy.within = Duration::from_secs(as_u64(within)?);
+        }
+        if args.len() > 3 {
+            return Err(RuntimeError::InvalidArugments(
+                "supervise takes a strategy, max restarts and a period in seconds".to_string(),
+            ));
+        }
+
+        Ok(policy)
+    }
+}
+
+fn as_u64(value: &Value) -> Result<u64, RuntimeError> {
+    let n = match value {
+        Value::Number(n) => n.to_u64(),
+        _ => None,
+    };
+    n.ok_or_else(|| {
+        RuntimeError::InvalidArugments(format!("expected a non-negative number, found {}", value))
+    })
+}
+
+struct Child {
+    /// Tells the child apart once others before it are gone.
+    id: u64,
+    name: String,
+    start: ChildStart,
+    channel: Channel,
+    listener: Arc<ChannelListener>,
+    abort: AbortHandle,
+    generation: u64,
+}
+
+/// Restarts the children of one agent when they crash.
+///
+/// Every `DynamicAgent` owns a supervisor, shared with its `spawn` agent.
+/// It stays inactive until the agent declares `supervise`; after that,
+/// children spawned by the agent are started here instead of as detached
+/// tasks. When a child panics, it is rebuilt on the same channel according
+/// to the policy, and the parent receives `[supervisor restarted <Name> <count>]`.
+/// When the restart intensity is exceeded, all children are stopped and the
+/// parent receives `[supervisor gave-up <Name>]`. `<Name>` is the child's
+/// agent name, as a string.
+///
+/// Children that stop normally (e.g. via `ControlMessage::Stop`) are not
+/// restarted, and the supervisor forgets them.
+pub struct Supervisor {
+    parent: Channel,
+    policy: RwLock<Option<SupervisorPolicy>>,
+    /// The running children, oldest first.
+    children: Mutex<Vec<Child>>,
+    next_id: AtomicU64,
+    restarts: Mutex<VecDeque<Instant>>,
+    /// The reply timeout of the agent that created the supervisor, which
+    /// restarted children keep.
+    reply_timeout: Option<Duration>,
+}
+
+impl Supervisor {
+    pub fn new(parent: Channel) -> Arc<Self> {
+        Arc::new(Self {
+            parent,
+            policy: RwLock::new(None),
+            children: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(0),
+            restarts: Mutex::new(VecDeque::new()),
+            reply_timeout: Channel::default_reply_timeout(),
+        })
+    }
+
+    pub async fn set_policy(&self, policy: SupervisorPolicy) {
+        *self.policy.write().await = Some(policy);
+    }
+
+    /// Whether the owning agent declared `supervise`.
+    pub async fn is_active(&self) -> bool {
+        self.policy.read().await.is_some()
+    }
+
+    /// Starts a supervised child and returns its channel.
+    pub async fn start_child(
+        self: &Arc<Self>,
+        name: &str,
+        protocols: Vec<String>,
+        start: ChildStart,
+    ) -> Channel {
+        let (channel, listener) = Channel::new(32);
+        let channel = channel.with_agent_type(name).with_protocols(protocols);
+        let listener = Arc::new(listener);
+        let handle = self.run(&start, &channel, &listener).await;
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.children.lock().await.push(Child {
+            id,
+            name: name.to_string(),
+            start,
+            channel: channel.clone(),
+            listener,
+            abort: handle.abort_handle(),
+            generation: 0,
+        });
+        self.watch(id, 0, handle);
+        channel
+    }
+
+    async fn run(
+        &self,
+        start: &ChildStart,
+        channel: &Channel,
+        listener: &Arc<ChannelListener>,
+    ) -> JoinHandle<ExitReason> {
+        let timeout = self.reply_timeout;
+        let agent =
+            Channel::with_reply_timeout(timeout, start(channel.clone(), listener.clone())).await;
+        tokio::spawn(Channel::with_reply_timeout(
+            timeout,
+            agent.actor_loop(channel.clone()),
+        ))
+    }
+
+    fn watch(self: &Arc<Self>, id: u64, generation: u64, handle: JoinHandle<ExitReason>) {
+        let supervisor: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let reason = ExitReason::from(handle.await);
+            if let Some(supervisor) = supervisor.upgrade() {
+                supervisor.child_exited(id, generation, reason).await;
+            }
+        });
+    }
+
+    async fn child_exited(self: Arc<Self>, id: u64, generation: u64, reason: ExitReason) {
+        let mut children = self.children.lock().await;
+        let Some(index) = children.iter().position(|child| child.id == id) else {
+            return;
+        };
+        let child = &children[index];
+        if child.generation != generation {
+            // An older incarnation that we replaced ourselves.
+            return;
+        }
+        let name = child.name.clone();
+
+        // Monitors see every exit, even ones we are about to restart.
+        child.listener.notify_down(&child.channel, &reason).await;
+
+        if reason != ExitReason::Panicked {
+            info!("Supervisor: child {} exited: {:?}", name, reason);
+            children.remove(index);
+            return;
+        }
+        warn!("Supervisor: child {} crashed", name);
+
+        let Some(policy) = self.policy.read().await.clone() else {
+            children.remove(index);
+            return;
+        };
+
+        if !self.allow_restart(&policy).await {
+            error!(
+                "Supervisor: giving up after {} restarts within {:?}",
+                policy.max_restarts, policy.within
+            );
+            for child in children.drain(..) {
+                child.abort.abort();
+                let _ = child.channel.control(ControlMessage::Stop).await;
+            }
+            drop(children);
+            self.notify(vec![
+                Value::Word("supervisor".into()),
+                Value::Word("gave-up".into()),
+                Value::String(name),
+            ])
+            .await;
+            return;
+        }
+
+        let restart: Vec<usize> = match policy.strategy {
+            RestartStrategy::OneForOne => vec![index],
+            RestartStrategy::OneForAll => (0..children.len()).collect(),
+            RestartStrategy::RestForOne => (index..children.len()).collect(),
+        };
+
+        for i in restart {
+            let child = &mut children[i];
+            child.abort.abort();
+            child.generation += 1;
+            let handle = self
+                .run(&child.start, &child.channel, &child.listener)
+                .await;
+            child.abort = handle.abort_handle();
+            info!("Supervisor: restarted {}", child.name);
+            self.watch(child.id, child.generation, handle);
+        }
+        drop(children);
+
+        let count = self.restarts.lock().await.len();
+        self.notify(vec![
+            Value::Word("supervisor".into()),
+            Value::Word("restarted".into()),
+            Value::String(name),
+            Value::Number(Number::UInt(count as u64)),
+        ])
+        .await;
+    }
+
+    /// Records a restart and checks it against the restart intensity.
+    async fn allow_restart(&self, policy: &SupervisorPolicy) -> bool {
+        let now = Instant::now();
+        let mut restarts = self.restarts.lock().await;
+        while let Some(oldest) = restarts.front() {
+            if now.duration_since(*oldest) > policy.within {
+                restarts.pop_front();
+            } else {
+                break;
+            }
+        }
+        restarts.push_back(now);
+        restarts.len() <= policy.max_restarts
+    }
+
+    async fn notify(&self, terms: Vec<Value>) {
+        if let Err(e) = self.parent.send(Message::new(terms, None)).await {
+            warn!("Supervisor: failed to notify parent: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use komrad_agent::AgentBehavior;
+    use komrad_macros::agent_lifecycle_impl;
+    use std::sync::atomic::AtomicUsize;
+
+    /// Panics on `[crash]`, replies with its incarnation number on `[id]`.
+    struct Fragile {
+        incarnation: usize,
+        channel: Channel,
+        listener: Arc<ChannelListener>,
+    }
+
+    agent_lifecycle_impl!(Fragile);
+
+    #[async_trait::async_trait]
+    impl AgentBehavior for Fragile {
+        async fn handle_message(&self, msg: Message) -> bool {
+            match msg.first_word().as_deref() {
+                Some("crash") => panic!("Fragile crashed on purpose"),
+                Some("id") => {
+                    if let Some(reply_to) = msg.reply_to() {
+                        let id = Value::Number(Number::UInt(self.incarnation as u64));
+                        let _ = reply_to.send(Message::new(vec![id], None)).await;
+                    }
+                }
+                _ => {}
+            }
+            true
+        }
+    }
+
+    impl Agent for Fragile {}
+
+    fn fragile() -> ChildStart {
+        let incarnations = Arc::new(AtomicUsize::new(0));
+        Arc::new(move |channel, listener| {
+            let incarnation = incarnations.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async move {
+                Arc::new(Fragile {
+                    incarnation,
+                    channel,
+                    listener,
+                }) as Arc<dyn Agent>
+            })
+        })
+    }
+
+    async fn id(channel: &Channel) -> Value {
+        let reply = channel
+            .send_and_recv(Message::new(vec![Value::Word("id".into())], None))
+            .await
+            .unwrap();
+        reply.terms()[0].clone()
+    }
+
+    async fn crash(channel: &Channel) {
+        channel
+            .send(Message::new(vec![Value::Word("crash".into())], None))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    #[test]
+    fn test_policy_from_args() {
+        let policy = SupervisorPolicy::from_args(&[
+            Value::Word("rest-for-one".into()),
+            Value::Number(Number::UInt(10)),
+            Value::Number(Number::UInt(60)),
+        ])
+        .unwrap();
+        assert_eq!(policy.strategy, RestartStrategy::RestForOne);
+        assert_eq!(policy.max_restarts, 10);
+        assert_eq!(policy.within, Duration::from_secs(60));
+
+        assert!(SupervisorPolicy::from_args(&[Value::Word("all-for-none".into())]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_one_for_one_restarts_on_same_channel() {
+        let (parent, parent_listener) = Channel::new(8);
+        let supervisor = Supervisor::new(parent);
+        supervisor.set_policy(SupervisorPolicy::default()).await;
+
+        let a = supervisor.start_child("A", vec![], fragile()).await;
+        let b = supervisor.start_child("B", vec![], fragile()).await;
+        assert_eq!(id(&a).await, Value::Number(Number::UInt(0)));
+
+        crash(&a).await;
+        assert_eq!(id(&a).await, Value::Number(Number::UInt(1)));
+        // B was left alone
+        assert_eq!(id(&b).await, Value::Number(Number::UInt(0)));
+
+        let event = parent_listener.recv().await.unwrap();
+        assert_eq!(
+            event.terms(),
+            &vec![
+                Value::Word("supervisor".into()),
+                Value::Word("restarted".into()),
+                Value::String("A".into()),
+                Value::Number(Number::UInt(1)),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_one_for_all_restarts_siblings() {
+        let (parent, _parent_listener) = Channel::new(8);
+        let supervisor = Supervisor::new(parent);
+        supervisor
+            .set_policy(SupervisorPolicy {
+                strategy: RestartStrategy::OneForAll,
+                ..Default::default()
+            })
+            .await;
+
+        let a = supervisor.start_child("A", vec![], fragile()).await;
+        let b = supervisor.start_child("B", vec![], fragile()).await;
+
+        crash(&a).await;
+        assert_eq!(id(&a).await, Value::Number(Number::UInt(1)));
+        assert_eq!(id(&b).await, Value::Number(Number::UInt(1)));
+    }
+
+    #[tokio::test]
+    async fn test_rest_for_one_restarts_younger_siblings() {
+        let (parent, _parent_listener) = Channel::new(8);
+        let supervisor = Supervisor::new(parent);
+        supervisor
+            .set_policy(SupervisorPolicy {
+                strategy: RestartStrategy::RestForOne,
+                ..Default::default()
+            })
+            .await;
+
+        let a = supervisor.start_child("A", vec![], fragile()).await;
+        let b = supervisor.start_child("B", vec![], fragile()).await;
+        let c = supervisor.start_child("C", vec![], fragile()).await;
+
+        crash(&b).await;
+        assert_eq!(id(&a).await, Value::Number(Number::UInt(0)));
+        assert_eq!(id(&b).await, Value::Number(Number::UInt(1)));
+        assert_eq!(id(&c).await, Value::Number(Number::UInt(1)));
+    }
+
+    #[tokio::test]
+    async fn test_forgets_children_that_exit() {
+        let (parent, _parent_listener) = Channel::new(8);
+        let supervisor = Supervisor::new(parent);
+        supervisor.set_policy(SupervisorPolicy::default()).await;
+
+        let a = supervisor.start_child("A", vec![], fragile()).await;
+        crash(&a).await;
+        crash(&a).await;
+        // Restarts replace the child rather than adding to it
+        assert_eq!(supervisor.children.lock().await.len(), 1);
+
+        a.control(ControlMessage::Stop).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(supervisor.children.lock().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_restarts() {
+        let (parent, parent_listener) = Channel::new(8);
+        let supervisor = Supervisor::new(parent);
+        supervisor
+            .set_policy(SupervisorPolicy {
+                max_restarts: 1,
+                ..Default::default()
+            })
+            .await;
+
+        let a = supervisor.start_child("A", vec![], fragile()).await;
+        crash(&a).await;
+        crash(&a).await;
+
+        let _restarted = parent_listener.recv().await.unwrap();
+        let gave_up = parent_listener.recv().await.unwrap();
+        assert_eq!(gave_up.terms()[1], Value::Word("gave-up".into()));
+    }
+}
@@ -102,7 +102,7 @@ mod tests {
     async fn test_spawn_agent_bob() {
         // Create a RegistryAgent and spawn it.
         let registry = RegistryAgent::new();
-        let _reg_chan = registry.clone().spawn();
+        let reg_chan = registry.clone().spawn();
 
         // Pre-register Bob with a dummy block (for example, one that prints a message).
         let bob_block = Block::new(vec![Statement::Expr(Expr::Call(CallExpr::new(
@@ -118,7 +118,7 @@ mod tests {
         }
 
         // Use SpawnAgent to spawn Bob.
-        let spawn_agent = SpawnAgent::new(registry.clone());
+        let spawn_agent = SpawnAgent::new(reg_chan.clone());
         let spawn_chan = spawn_agent.clone().spawn();
 
         let (reply_chan, reply_listener) = Channel::new(10);
@@ -137,7 +137,7 @@ mod tests {
     async fn test_alice_sends_to_bob() {
         // Create the RegistryAgent and spawn it.
         let registry = RegistryAgent::new();
-        let _ = registry.clone().spawn();
+        let reg_chan = registry.clone().spawn();
 
         // Pre-register Bob with a block that (for this test) simulates handling a "foo" message.
         let bob_block = Block::new(vec![Statement::Expr(Expr::Call(CallExpr::new(
@@ -156,7 +156,7 @@ mod tests {
         // Register Alice using AgentAgent.
         // AgentAgent expects a message of the form: [Alice, <block>]
         // where <block> is the agent’s definition.
-        let agent_agent = AgentAgent::new(registry.clone());
+        let agent_agent = AgentAgent::new(reg_chan.clone());
         let agent_chan = agent_agent.clone().spawn();
 
         let (reply_chan_alice, reply_listener_alice) = Channel::new(10);
@@ -194,7 +194,7 @@ mod tests {
         // Now, simulate execution of Alice’s block.
         // Typically, Alice’s block would be executed by the VM.
         // For testing, we simulate the spawn of Bob and then sending "foo".
-        let spawn_agent = SpawnAgent::new(registry.clone());
+        let spawn_agent = SpawnAgent::new(reg_chan.clone());
         let spawn_chan = spawn_agent.clone().spawn();
         let (reply_chan_bob, reply_listener_bob) = Channel::new(10);
         let msg_spawn_bob = Message::new(
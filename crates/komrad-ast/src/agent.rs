@@ -7,6 +7,7 @@ use async_trait::async_trait;
 use std::sync::{mpsc, Arc};
 use tokio::select;
 use tokio::sync::{watch, Mutex};
+use tokio::task::JoinError;
 use tracing::{debug, error, info, trace};
 
 pub enum AgentControl {
@@ -18,6 +19,42 @@ pub enum AgentState {
     Stopped,
 }
 
+/// Why an agent's `actor_loop` exited. Monitors receive it as the
+/// `_reason` of `[down _agent _reason]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExitReason {
+    /// Stopped by `ControlMessage::Stop`, or a handler asked to stop.
+    Stopped,
+    /// The agent's channel was closed.
+    Closed,
+    /// A handler panicked.
+    Panicked,
+    /// The agent was already gone when it was monitored.
+    NoProc,
+}
+
+impl ExitReason {
+    pub fn to_value(&self) -> Value {
+        let word = match self {
+            ExitReason::Stopped => "stopped",
+            ExitReason::Closed => "closed",
+            ExitReason::Panicked => "panicked",
+            ExitReason::NoProc => "noproc",
+        };
+        Value::Word(word.to_string())
+    }
+}
+
+impl From<Result<ExitReason, JoinError>> for ExitReason {
+    fn from(result: Result<ExitReason, JoinError>) -> Self {
+        match result {
+            Ok(reason) => reason,
+            Err(err) if err.is_panic() => ExitReason::Panicked,
+            Err(_) => ExitReason::Stopped,
+        }
+    }
+}
+
 pub struct AgentData {
     pub name: String,
     pub scope: Arc<Mutex<Scope>>,
@@ -76,10 +113,27 @@ pub trait AgentLifecycle: Send + Sync + 'static {
 /// Extension trait providing default implementations.
 #[async_trait]
 pub trait AgentBehavior: AgentLifecycle {
+    /// Runs the agent on its own task. A second task waits for it to exit
+    /// and notifies its monitors, even when a handler panics. The agent
+    /// keeps the reply timeout of the task that spawns it.
     fn spawn(self: Arc<Self>) -> Channel {
         let chan = self.channel().clone();
-        let agent = self.clone();
-        tokio::spawn(Self::actor_loop(agent, chan.clone()));
+        let listener = self.listener();
+        let timeout = Channel::default_reply_timeout();
+        let task = tokio::spawn(Channel::with_reply_timeout(
+            timeout,
+            Self::actor_loop(self, chan.clone()),
+        ));
+        let down_chan = chan.clone();
+        tokio::spawn(async move {
+            let reason = ExitReason::from(task.await);
+            debug!(
+                "Agent {} exited: {:?}",
+                down_chan.to_sexpr().format(0),
+                reason
+            );
+            listener.notify_down(&down_chan, &reason).await;
+        });
         chan
     }
 
@@ -90,7 +144,7 @@ pub trait AgentBehavior: AgentLifecycle {
 
     async fn handle_extra_message(&self, _msg: Message) {}
 
-    async fn actor_loop(self: Arc<Self>, _chan: Channel) {
+    async fn actor_loop(self: Arc<Self>, _chan: Channel) -> ExitReason {
         debug!(
             "Starting actor loop for agent {}",
             self.channel().to_sexpr().format(0)
@@ -105,7 +159,7 @@ pub trait AgentBehavior: AgentLifecycle {
             self.clone().init(&mut scope).await
         };
 
-        loop {
+        let reason = loop {
             // The listener has internal locking that allows us to await
             // both recv and recv_control without deadlocking.
             let listener = self.listener().clone();
@@ -122,30 +176,39 @@ pub trait AgentBehavior: AgentLifecycle {
                 msg = listener.recv() => match msg {
                     Ok(msg) => {
                         if !Self::handle_message(&self, msg).await {
-                            break;
+                            break ExitReason::Stopped;
                         }
                     }
-                    Err(_) => break,
+                    Err(_) => break ExitReason::Closed,
                 },
-                // Receive a control message (just stop for now)
+                // Receive a control message
                 msg = listener.recv_control() => match msg {
                     Ok(msg) => {
                         match msg {
                             ControlMessage::Stop => {
                                 debug!("Received Stop message");
                                 self.stop().await;
-                                break;
+                                break ExitReason::Stopped;
+                            }
+                            ControlMessage::Monitor(monitor) => {
+                                listener.add_monitor(monitor).await;
+                            }
+                            ControlMessage::Link(other) => {
+                                listener.add_monitor(other.clone()).await;
+                                let me = self.channel().clone();
+                                let _ = other.control(ControlMessage::Monitor(me)).await;
                             }
                         }
                     }
                     Err(e) => {
                         error!("Error receiving control message: {:?}", e);
-                        break
+                        break ExitReason::Closed
                     },
                 }
             }
-        }
+        };
         trace!("Agent loop exited");
+        reason
     }
 
     async fn send(&self, msg: Message) -> Result<(), RuntimeError> {
@@ -1,23 +1,28 @@
-use crate::operators::BinaryExpr;
-use crate::prelude::BinaryOp;
+use crate::location::SourceLocation;
+use crate::operators::{BinaryExpr, UnaryExpr};
+use crate::prelude::{BinaryOp, UnaryOp};
 use crate::type_expr::TypeExpr;
 use crate::value::Value;
 use serde::{Deserialize, Serialize};
 use std::hash::Hash;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CallExpr {
     target: Box<Expr>,
     args: Vec<Box<Expr>>,
+    reply_timeout: Option<Duration>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Expr {
     Value(Value),
-    List(Vec<Expr>),  // e.g., [1 2 3]
-    Variable(String), // e.g., "foo"
+    List(Vec<Expr>),        // e.g., [1 2 3]
+    Map(Vec<(Expr, Expr)>), // e.g., {: name = "x", n = 1 :}
+    Variable(String),       // e.g., "foo"
     Binary(BinaryExpr),
+    Unary(UnaryExpr), // e.g., -x, !flag, ++n
     Call(CallExpr),
     Block(Box<Block>),
 }
@@ -31,11 +36,22 @@ pub enum Statement {
     Assignment(String, Expr),
     Field(String, TypeExpr, Option<Expr>),
     Handler(Arc<Handler>),
+    /// `try { ... } catch _err { ... }`: runs the first block and, if it
+    /// yields an error, binds the error to the name and runs the second.
+    Try(Block, String, Block),
+    /// `while { condition } { ... }`: runs the body for as long as the
+    /// condition yields true, without growing the stack or the mailbox.
+    While(Block, Block),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A sequence of statements. Parsed blocks also remember where each
+/// statement came from, so runtime errors can point at the source.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Block {
     statements: Vec<Statement>,
+    /// Empty, or one location per statement.
+    #[serde(skip)]
+    locations: Vec<Option<SourceLocation>>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -71,6 +87,23 @@ impl BinaryExpr {
     }
 }
 
+impl UnaryExpr {
+    pub fn new(op: UnaryOp, expr: Expr) -> Self {
+        UnaryExpr {
+            op,
+            expr: Box::new(expr),
+        }
+    }
+
+    pub fn operator(&self) -> &UnaryOp {
+        &self.op
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+}
+
 impl CallExpr {
     pub fn target(&self) -> &Expr {
         &self.target
@@ -80,12 +113,23 @@ impl CallExpr {
         &self.args
     }
 
+    /// How long to wait for a reply, overriding the default (`foo bar @500ms`).
+    pub fn reply_timeout(&self) -> Option<Duration> {
+        self.reply_timeout
+    }
+
     pub fn new(target: Expr, args: Vec<Box<Expr>>) -> Self {
         CallExpr {
             target: Box::new(target),
             args,
+            reply_timeout: None,
         }
     }
+
+    pub fn with_reply_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.reply_timeout = timeout;
+        self
+    }
 }
 
 impl Expr {
@@ -101,6 +145,9 @@ impl Expr {
     pub fn is_binary(&self) -> bool {
         matches!(self, Expr::Binary(_))
     }
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Expr::Unary(_))
+    }
     pub fn is_call(&self) -> bool {
         matches!(self, Expr::Call(_))
     }
@@ -128,16 +175,80 @@ impl Statement {
     pub fn is_handler(&self) -> bool {
         matches!(self, Statement::Handler(_))
     }
+    pub fn is_try(&self) -> bool {
+        matches!(self, Statement::Try(_, _, _))
+    }
+    pub fn is_while(&self) -> bool {
+        matches!(self, Statement::While(_, _))
+    }
 }
 
 impl Block {
     pub fn new(statements: Vec<Statement>) -> Self {
-        Block { statements }
+        Block {
+            statements,
+            locations: Vec::new(),
+        }
+    }
+
+    /// A block whose statements came from the given source locations.
+    pub fn with_locations(
+        statements: Vec<Statement>,
+        locations: Vec<Option<SourceLocation>>,
+    ) -> Self {
+        debug_assert_eq!(statements.len(), locations.len());
+        Block {
+            statements,
+            locations,
+        }
+    }
+
+    /// A block with new statements in place of this one's, keeping their
+    /// locations. Used when rewriting a block statement by statement.
+    pub fn with_statements(&self, statements: Vec<Statement>) -> Self {
+        let locations = if statements.len() == self.locations.len() {
+            self.locations.clone()
+        } else {
+            Vec::new()
+        };
+        Block {
+            statements,
+            locations,
+        }
     }
 
     pub fn statements(&self) -> &Vec<Statement> {
         &self.statements
     }
+
+    /// Where the statement at `index` came from, if the block was parsed.
+    pub fn location(&self, index: usize) -> Option<&SourceLocation> {
+        self.locations.get(index).and_then(Option::as_ref)
+    }
+}
+
+impl std::fmt::Debug for Block {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Block")
+            .field("statements", &self.statements)
+            .finish()
+    }
+}
+
+/// Locations don't take part in equality: the same code parsed from two
+/// places is the same block.
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements
+    }
+}
+
+impl Eq for Block {}
+
+impl Hash for Block {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.statements.hash(state);
+    }
 }
 
 impl Pattern {
@@ -1,19 +1,36 @@
+use crate::agent::ExitReason;
 use crate::error::RuntimeError;
 use crate::message::Message;
 use crate::prelude::Value;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 use uuid::Uuid;
 
 const CHANNEL_DIGEST_LEN: usize = 8;
 
+tokio::task_local! {
+    /// How long calls wait for a reply, set by the `System` an agent runs in.
+    static REPLY_TIMEOUT: Option<Duration>;
+}
+
 pub enum ControlMessage {
     Stop,
+    /// Send `[down <agent> <reason>]` to the given channel when this agent exits.
+    Monitor(Channel),
+    /// Monitor in both directions: the receiving agent monitors the given
+    /// channel's agent as well as being monitored by it.
+    Link(Channel),
 }
 
 #[derive(Clone)]
 pub struct Channel {
     uuid: Uuid,
+    /// The agent definition this channel's agent was spawned from.
+    agent_type: Option<Arc<str>>,
+    /// The protocols its agent definition declared it implements.
+    protocols: Arc<[String]>,
     sender: mpsc::Sender<Message>,
     control_sender: mpsc::Sender<ControlMessage>,
 }
@@ -28,6 +45,8 @@ impl<'de> Deserialize<'de> for Channel {
         let (control_sender, _control_receiver) = mpsc::channel(1);
         let channel = Channel {
             uuid,
+            agent_type: None,
+            protocols: Arc::new([]),
             sender,
             control_sender,
         };
@@ -49,7 +68,21 @@ impl Channel {
         let (response_sender, response_receiver) = Channel::new(1);
         let msg_with_response = Message::new(msg.terms().clone(), Some(response_sender));
         self.send(msg_with_response).await?;
-        response_receiver.recv().await
+        response_receiver
+            .recv_timeout(Channel::default_reply_timeout())
+            .await
+    }
+
+    /// The reply timeout used by calls that do not specify their own: that
+    /// of the system the running agent belongs to. `None` waits forever.
+    pub fn default_reply_timeout() -> Option<Duration> {
+        REPLY_TIMEOUT.try_with(|timeout| *timeout).ok().flatten()
+    }
+
+    /// Runs `task` with `timeout` as its default reply timeout. Agents
+    /// spawned while it runs keep the same timeout.
+    pub async fn with_reply_timeout<F: Future>(timeout: Option<Duration>, task: F) -> F::Output {
+        REPLY_TIMEOUT.scope(timeout, task).await
     }
 
     pub async fn get(&self, key: &str) -> Result<Value, RuntimeError> {
@@ -107,6 +140,8 @@ pub struct ChannelListener {
     // Separate the receivers into two distinct Mutexes
     message_receiver: Mutex<mpsc::Receiver<Message>>,
     control_receiver: Mutex<mpsc::Receiver<ControlMessage>>,
+    // Kept with the listener rather than the agent so they outlive a panicking handler
+    monitors: Mutex<Vec<Channel>>,
 }
 
 impl Channel {
@@ -117,6 +152,8 @@ impl Channel {
         (
             Channel {
                 uuid,
+                agent_type: None,
+                protocols: Arc::new([]),
                 sender: sender.clone(),
                 control_sender: control_sender.clone(),
             },
@@ -124,6 +161,7 @@ impl Channel {
                 uuid,
                 message_receiver: Mutex::new(message_receiver),
                 control_receiver: Mutex::new(control_receiver),
+                monitors: Mutex::new(Vec::new()),
             },
         )
     }
@@ -132,6 +170,34 @@ impl Channel {
         self.uuid
     }
 
+    /// Tags the channel with the name of the agent definition it was
+    /// spawned from, which becomes its type: a `Bob` matches `_(peer:Bob)`.
+    pub fn with_agent_type(mut self, name: &str) -> Self {
+        self.agent_type = Some(name.into());
+        self
+    }
+
+    pub fn agent_type(&self) -> Option<&str> {
+        self.agent_type.as_deref()
+    }
+
+    /// Tags the channel with the protocols its agent was verified to
+    /// implement, so that a `List` also matches `_(items:List)`.
+    pub fn with_protocols(mut self, protocols: Vec<String>) -> Self {
+        self.protocols = protocols.into();
+        self
+    }
+
+    pub fn protocols(&self) -> &[String] {
+        &self.protocols
+    }
+
+    /// Whether the agent was spawned from the named definition or
+    /// declared the named protocol.
+    pub fn implements(&self, name: &str) -> bool {
+        self.agent_type() == Some(name) || self.protocols.iter().any(|p| p == name)
+    }
+
     pub async fn send(&self, message: Message) -> Result<(), RuntimeError> {
         self.sender
             .send(message)
@@ -153,6 +219,16 @@ impl ChannelListener {
         receiver.recv().await.ok_or(RuntimeError::ReceiveError)
     }
 
+    /// Like `recv`, but gives up with `RuntimeError::Timeout` after `timeout`.
+    pub async fn recv_timeout(&self, timeout: Option<Duration>) -> Result<Message, RuntimeError> {
+        match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.recv())
+                .await
+                .unwrap_or(Err(RuntimeError::Timeout(timeout))),
+            None => self.recv().await,
+        }
+    }
+
     pub async fn recv_control(&self) -> Result<ControlMessage, RuntimeError> {
         let mut receiver = self.control_receiver.lock().await;
         receiver
@@ -164,4 +240,87 @@ impl ChannelListener {
     pub fn uuid(&self) -> Uuid {
         self.uuid
     }
+
+    pub async fn add_monitor(&self, monitor: Channel) {
+        self.monitors.lock().await.push(monitor);
+    }
+
+    /// Sends `[down <channel> <reason>]` to every monitor of `channel`,
+    /// including monitors whose request was still queued when the agent exited.
+    /// Monitors fire once and are then forgotten.
+    pub async fn notify_down(&self, channel: &Channel, reason: &ExitReason) {
+        let mut monitors = std::mem::take(&mut *self.monitors.lock().await);
+        {
+            let mut receiver = self.control_receiver.lock().await;
+            while let Ok(msg) = receiver.try_recv() {
+                match msg {
+                    ControlMessage::Monitor(monitor) | ControlMessage::Link(monitor) => {
+                        monitors.push(monitor)
+                    }
+                    ControlMessage::Stop => {}
+                }
+            }
+        }
+
+        for monitor in monitors {
+            let down = Message::new(
+                vec![
+                    Value::Word("down".to_string()),
+                    Value::Channel(channel.clone()),
+                    reason.to_value(),
+                ],
+                None,
+            );
+            let _ = monitor.send(down).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_recv_timeout() {
+        let (_channel, listener) = Channel::new(1);
+        let timeout = Duration::from_millis(10);
+        assert_eq!(
+            listener.recv_timeout(Some(timeout)).await.unwrap_err(),
+            RuntimeError::Timeout(timeout)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_recv_timeout_receives() {
+        let (channel, listener) = Channel::new(1);
+        channel.send(Message::default()).await.unwrap();
+        let msg = listener.recv_timeout(Some(Duration::from_millis(10))).await;
+        assert!(msg.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_notify_down() {
+        let (channel, listener) = Channel::new(1);
+        let (monitor, monitor_listener) = Channel::new(1);
+        let (queued, queued_listener) = Channel::new(1);
+        listener.add_monitor(monitor).await;
+        channel
+            .control(ControlMessage::Monitor(queued))
+            .await
+            .unwrap();
+
+        listener.notify_down(&channel, &ExitReason::Panicked).await;
+
+        for listener in [monitor_listener, queued_listener] {
+            let down = listener.recv().await.unwrap();
+            assert_eq!(
+                down.terms(),
+                &vec![
+                    Value::Word("down".to_string()),
+                    Value::Channel(channel.clone()),
+                    Value::Word("panicked".to_string()),
+                ]
+            );
+        }
+    }
 }
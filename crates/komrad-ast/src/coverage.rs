@@ -0,0 +1,205 @@
+use crate::prelude::{ComparisonOp, Handler, Pattern, TypeExpr, Value};
+use crate::typed::Typed;
+use crate::value_type::ValueType;
+
+/// Reasoning about which messages a pattern binds without sending any,
+/// following the same rules as `try_bind`.
+impl Pattern {
+    /// Whether every message `other` binds is also bound by this pattern.
+    pub fn covers(&self, other: &Pattern) -> bool {
+        self.terms().len() == other.terms().len()
+            && self
+                .terms()
+                .iter()
+                .zip(other.terms())
+                .all(|(term, other)| term.covers(other))
+    }
+
+    /// Whether some term can never bind, e.g. `_(x %% 0)`.
+    pub fn is_unmatchable(&self) -> bool {
+        self.terms().iter().any(TypeExpr::is_unmatchable)
+    }
+
+    /// The patterns of the built-in `List` protocol, as `ListAgent` handles
+    /// them and the list expander `...` expects.
+    pub fn list_protocol() -> Vec<Pattern> {
+        let word = |word: &str| TypeExpr::Word(word.to_string());
+        let hole = |name: &str| TypeExpr::Hole(name.to_string());
+        vec![
+            Pattern::new(vec![word("items")]),
+            Pattern::new(vec![word("add"), hole("x")]),
+            Pattern::new(vec![word("get"), hole("i")]),
+            Pattern::new(vec![word("length")]),
+        ]
+    }
+
+    /// Whether one of the handlers binds every message this pattern does,
+    /// which is what implementing a protocol's pattern means.
+    pub fn is_handled_by<'a>(&self, handlers: impl IntoIterator<Item = &'a Handler>) -> bool {
+        handlers
+            .into_iter()
+            .any(|handler| !handler.pattern().is_unmatchable() && handler.pattern().covers(self))
+    }
+}
+
+impl TypeExpr {
+    /// Whether every value `other` accepts is also accepted by this term.
+    pub fn covers(&self, other: &TypeExpr) -> bool {
+        if let Some(value) = exact_value(other) {
+            return self.accepts(&value);
+        }
+        match (self, other) {
+            (TypeExpr::Hole(_) | TypeExpr::BlockHole(_), _) => true,
+            (TypeExpr::HasType(typ) | TypeExpr::TypeHole(_, typ), _) => {
+                accepted_type(other).is_some_and(|other| other.is_subtype_of(typ))
+            }
+            (TypeExpr::Binary(_, op, bound), TypeExpr::Binary(_, other_op, other_bound)) => {
+                implies(other_op, other_bound, op, bound)
+            }
+            // Values of another type are never equal to the bound
+            (TypeExpr::Binary(_, ComparisonOp::Ne, bound), _) => {
+                accepted_type(other).is_some_and(|typ| typ != bound.get_type())
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether the term binds `value`.
+    pub fn accepts(&self, value: &Value) -> bool {
+        match self {
+            TypeExpr::Hole(_) | TypeExpr::BlockHole(_) => true,
+            TypeExpr::HasType(typ) | TypeExpr::TypeHole(_, typ) => value.has_type(typ),
+            TypeExpr::Binary(_, op, bound) => compare(value, op, bound),
+            _ => exact_value(self).is_some_and(|exact| exact == *value),
+        }
+    }
+
+    /// Whether the term is a predicate hole whose comparison can't succeed
+    /// for any value.
+    pub fn is_unmatchable(&self) -> bool {
+        match self {
+            TypeExpr::Binary(_, ComparisonOp::Divisible, value) => {
+                !matches!(value, Value::Number(n) if !n.is_zero())
+            }
+            TypeExpr::Binary(
+                _,
+                ComparisonOp::Lt | ComparisonOp::Le | ComparisonOp::Gt | ComparisonOp::Ge,
+                value,
+            ) => !is_ordered(value),
+            _ => false,
+        }
+    }
+}
+
+/// The only value a term accepts, if there is just one.
+fn exact_value(term: &TypeExpr) -> Option<Value> {
+    match term {
+        TypeExpr::Empty => Some(Value::Empty),
+        TypeExpr::Value(value) => Some(value.clone()),
+        TypeExpr::Word(word) => Some(Value::Word(word.clone())),
+        TypeExpr::Binary(_, ComparisonOp::Eq, value) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// A type that every value the term accepts belongs to.
+fn accepted_type(term: &TypeExpr) -> Option<ValueType> {
+    match term {
+        TypeExpr::HasType(typ) | TypeExpr::TypeHole(_, typ) => Some(typ.clone()),
+        TypeExpr::Binary(_, ComparisonOp::Divisible, _) => Some(ValueType::Number),
+        TypeExpr::Binary(_, ComparisonOp::Ne, _) => None,
+        // Ordering only succeeds between values of the same type
+        TypeExpr::Binary(_, _, value) => Some(value.get_type()),
+        _ => exact_value(term).map(|value| value.get_type()),
+    }
+}
+
+/// Evaluates a predicate hole's comparison, treating values that can't be
+/// compared as a failed match.
+fn compare(value: &Value, op: &ComparisonOp, bound: &Value) -> bool {
+    let comparable = || value.get_type() == bound.get_type() && is_ordered(bound);
+    match op {
+        ComparisonOp::Eq => value == bound,
+        ComparisonOp::Ne => value != bound,
+        ComparisonOp::Lt => comparable() && value < bound,
+        ComparisonOp::Le => comparable() && value <= bound,
+        ComparisonOp::Gt => comparable() && value > bound,
+        ComparisonOp::Ge => comparable() && value >= bound,
+        ComparisonOp::Divisible => match (value, bound) {
+            (Value::Number(value), Value::Number(divisor)) => value.is_divisible_by(divisor),
+            _ => false,
+        },
+    }
+}
+
+/// Whether `x op bound` holds for every `x` that satisfies
+/// `x other_op other_bound`.
+fn implies(other_op: &ComparisonOp, other_bound: &Value, op: &ComparisonOp, bound: &Value) -> bool {
+    use ComparisonOp::*;
+    if other_op == op && other_bound == bound {
+        return true;
+    }
+    if let (Divisible, Divisible) = (other_op, op) {
+        // Multiples of 15 are all multiples of 3
+        return compare(other_bound, &Divisible, bound);
+    }
+    if other_bound.get_type() != bound.get_type() || !is_ordered(bound) {
+        return false;
+    }
+    match (other_op, op) {
+        (Gt, Gt) | (Ge, Ge) | (Gt, Ge) => other_bound >= bound,
+        (Ge, Gt) => other_bound > bound,
+        (Lt, Lt) | (Le, Le) | (Lt, Le) => other_bound <= bound,
+        (Le, Lt) => other_bound < bound,
+        _ => false,
+    }
+}
+
+fn is_ordered(value: &Value) -> bool {
+    matches!(
+        value,
+        Value::Number(_) | Value::String(_) | Value::Boolean(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::Number;
+
+    fn pattern(terms: Vec<TypeExpr>) -> Pattern {
+        Pattern::new(terms)
+    }
+
+    #[test]
+    fn test_covers() {
+        let hole = pattern(vec![
+            TypeExpr::Word("add".to_string()),
+            TypeExpr::Hole("x".to_string()),
+        ]);
+        let typed = pattern(vec![
+            TypeExpr::Word("add".to_string()),
+            TypeExpr::TypeHole("x".to_string(), ValueType::Number),
+        ]);
+        assert!(hole.covers(&typed));
+        assert!(!typed.covers(&hole));
+        assert!(hole.covers(&hole));
+    }
+
+    #[test]
+    fn test_is_unmatchable() {
+        let never = TypeExpr::Binary(
+            "x".to_string(),
+            ComparisonOp::Divisible,
+            Value::Number(Number::Int(0)),
+        );
+        assert!(never.is_unmatchable());
+        assert!(pattern(vec![never]).is_unmatchable());
+        let ordered = TypeExpr::Binary(
+            "x".to_string(),
+            ComparisonOp::Gt,
+            Value::Number(Number::Int(0)),
+        );
+        assert!(!ordered.is_unmatchable());
+    }
+}
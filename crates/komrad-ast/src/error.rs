@@ -1,9 +1,11 @@
+use crate::location::SourceLocation;
 use miette::{Diagnostic, NamedSource, SourceSpan};
 use nom::error::{ErrorKind as NomErrorKind, FromExternalError, ParseError as NomParseError};
 use nom_locate::LocatedSpan;
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
 pub type Span<'a> = LocatedSpan<&'a str, Arc<NamedSource<String>>>;
@@ -68,14 +70,27 @@ impl ParserError {
             kind,
         }
     }
+
+    /// An error for whatever is at the start of `input`, pointing at the
+    /// rest of its line rather than the rest of the file.
+    pub fn unexpected(input: Span<'_>) -> Self {
+        let line = input.fragment().lines().next().unwrap_or("").trim_end();
+        let kind = if input.fragment().trim().is_empty() {
+            ErrorKind::UnexpectedEndOfInput
+        } else {
+            ErrorKind::UnexpectedToken(line.to_string())
+        };
+        Self {
+            src: input.extra.clone(),
+            span: SourceSpan::new(input.location_offset().into(), line.len().max(1)),
+            kind,
+        }
+    }
 }
 
 impl<'a> NomParseError<Span<'a>> for ParserError {
     fn from_error_kind(input: Span<'a>, _kind: NomErrorKind) -> Self {
-        Self::new(
-            ErrorKind::UnexpectedToken(input.fragment().to_string()),
-            input,
-        )
+        Self::unexpected(input)
     }
 
     fn append(_input: Span<'a>, _kind: NomErrorKind, other: Self) -> Self {
@@ -83,10 +98,17 @@ impl<'a> NomParseError<Span<'a>> for ParserError {
     }
 
     fn from_char(input: Span<'a>, _c: char) -> Self {
-        Self::new(
-            ErrorKind::UnexpectedToken(input.fragment().to_string()),
-            input,
-        )
+        Self::unexpected(input)
+    }
+
+    /// Of two failed alternatives, the one that got further is the better
+    /// guess at what was meant.
+    fn or(self, other: Self) -> Self {
+        if self.span.offset() > other.span.offset() {
+            self
+        } else {
+            other
+        }
     }
 }
 
@@ -169,6 +191,9 @@ pub enum RuntimeError {
     #[error("Division by zero")]
     DivisionByZero,
 
+    #[error("Arithmetic overflow")]
+    Overflow,
+
     #[error("Invalid agent definition")]
     InvalidAgentDefinition,
 
@@ -178,6 +203,12 @@ pub enum RuntimeError {
     #[error("Type mismatch: {0}")]
     TypeMismatch(String),
 
+    #[error("Protocol not defined: {0}")]
+    ProtocolNotDefined(String),
+
+    #[error("Protocol not implemented: {0}")]
+    ProtocolNotImplemented(String),
+
     #[error("Index out of bounds: {0}")]
     IndexOutOfBounds(usize),
 
@@ -186,4 +217,62 @@ pub enum RuntimeError {
 
     #[error("External service error")]
     ExternalServiceError,
+
+    #[error("Timed out after {0:?} waiting for a reply")]
+    Timeout(Duration),
+
+    #[error("Deadlock waiting for a reply: {0}")]
+    Deadlock(String),
+}
+
+/// A runtime error and the statement that raised it, reported like a
+/// parse error with the offending line underlined.
+#[derive(Clone, Debug, Error, Diagnostic)]
+#[error("{error}")]
+pub struct LocatedError {
+    #[source_code]
+    pub src: Arc<NamedSource<String>>,
+
+    #[label("Error occurred here.")]
+    pub span: SourceSpan,
+
+    pub error: RuntimeError,
+}
+
+impl RuntimeError {
+    /// Attaches the location of the statement that raised this error.
+    pub fn at(self, location: &SourceLocation) -> LocatedError {
+        LocatedError {
+            src: location.src().clone(),
+            span: location.span(),
+            error: self,
+        }
+    }
+
+    /// The name of the error variant, e.g. `DivisionByZero`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RuntimeError::ParseError(_) => "ParseError",
+            RuntimeError::AssertionFailed(_) => "AssertionFailed",
+            RuntimeError::NotImplemented(_) => "NotImplemented",
+            RuntimeError::InvalidArugments(_) => "InvalidArguments",
+            RuntimeError::NameNotFound(_) => "NameNotFound",
+            RuntimeError::SendError => "SendError",
+            RuntimeError::ReceiveError => "ReceiveError",
+            RuntimeError::SendControlError => "SendControlError",
+            RuntimeError::ReceiveControlError => "ReceiveControlError",
+            RuntimeError::DivisionByZero => "DivisionByZero",
+            RuntimeError::Overflow => "Overflow",
+            RuntimeError::InvalidAgentDefinition => "InvalidAgentDefinition",
+            RuntimeError::AgentNotRegistered(_) => "AgentNotRegistered",
+            RuntimeError::TypeMismatch(_) => "TypeMismatch",
+            RuntimeError::ProtocolNotDefined(_) => "ProtocolNotDefined",
+            RuntimeError::ProtocolNotImplemented(_) => "ProtocolNotImplemented",
+            RuntimeError::IndexOutOfBounds(_) => "IndexOutOfBounds",
+            RuntimeError::HandlerNotFound(_) => "HandlerNotFound",
+            RuntimeError::ExternalServiceError => "ExternalServiceError",
+            RuntimeError::Timeout(_) => "Timeout",
+            RuntimeError::Deadlock(_) => "Deadlock",
+        }
+    }
 }
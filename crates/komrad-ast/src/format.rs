@@ -0,0 +1,432 @@
+use crate::prelude::{BinaryOp, Block, EmbeddedBlock, Expr, Handler, Number, Pattern, Statement};
+use crate::type_expr::TypeExpr;
+use crate::value::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Renders AST nodes as Komrad source, for showing values, patterns and
+/// expressions in output and diagnostics.
+///
+/// The output is canonical: tabs for indentation, single spaces between
+/// terms, and at most one blank line between statements. Parsing the
+/// output gives back the same AST. It isn't a formatter for source files,
+/// since most comments aren't in the AST: `komrad fmt` and
+/// `komrad parse --fmt komrad` print from the syntax tree instead.
+pub trait ToKomrad {
+    fn to_komrad(&self) -> String;
+}
+
+/// Renders the statements of a module, one per line, without the braces
+/// that a block expression would have.
+pub fn format_statements(block: &Block) -> String {
+    let mut printer = Printer::default();
+    printer.statements(block);
+    printer.out
+}
+
+impl ToKomrad for Block {
+    fn to_komrad(&self) -> String {
+        let mut printer = Printer::default();
+        printer.block(self);
+        printer.out
+    }
+}
+
+impl ToKomrad for Statement {
+    fn to_komrad(&self) -> String {
+        let mut printer = Printer::default();
+        printer.statement(self);
+        printer.out
+    }
+}
+
+impl ToKomrad for Expr {
+    fn to_komrad(&self) -> String {
+        let mut printer = Printer::default();
+        printer.expr(self);
+        printer.out
+    }
+}
+
+impl ToKomrad for Pattern {
+    fn to_komrad(&self) -> String {
+        let terms: Vec<String> = self.terms().iter().map(ToKomrad::to_komrad).collect();
+        format!("[{}]", terms.join(" "))
+    }
+}
+
+impl ToKomrad for TypeExpr {
+    fn to_komrad(&self) -> String {
+        match self {
+            TypeExpr::Empty => "_".to_string(),
+            TypeExpr::Value(value) => value.to_komrad(),
+            TypeExpr::HasType(value_type) => value_type.to_string(),
+            TypeExpr::Word(word) => word.clone(),
+            TypeExpr::Hole(name) => format!("_{}", name),
+            TypeExpr::TypeHole(name, value_type) => format!("_({}:{})", name, value_type),
+            TypeExpr::BlockHole(name) => format!("_{{{}}}", name),
+            TypeExpr::Binary(name, op, value) => {
+                format!("_({} {} {})", name, op, value.to_komrad())
+            }
+        }
+    }
+}
+
+/// Literal values. Values that have no literal syntax, such as channels,
+/// are rendered as their display text.
+impl ToKomrad for Value {
+    fn to_komrad(&self) -> String {
+        match self {
+            Value::Boolean(b) => b.to_string(),
+            Value::Word(word) => word.clone(),
+            Value::String(s) => string_literal(s),
+            Value::Number(n) => n.to_komrad(),
+            Value::Embedded(block) => block.to_komrad(),
+            Value::Map(map) if map.is_empty() => "{::}".to_string(),
+            Value::Map(map) => {
+                let entries: Vec<String> = map
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", map_key(key), value.to_komrad()))
+                    .collect();
+                format!("{{: {} :}}", entries.join(", "))
+            }
+            other => other.to_string(),
+        }
+    }
+}
+
+/// Numbers keep their type: an `Int` is written with a sign and a `Float`
+/// always has a `.` or an exponent.
+impl ToKomrad for Number {
+    fn to_komrad(&self) -> String {
+        match self {
+            Number::Int(i) if *i >= 0 => format!("+{}", i),
+            Number::Float(f) => format!("{:?}", f),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl ToKomrad for EmbeddedBlock {
+    fn to_komrad(&self) -> String {
+        format!(
+            "```{}\n{}```",
+            self.tags().join(" "),
+            self.text().replace('\\', "\\\\")
+        )
+    }
+}
+
+/// String keys that are identifiers are written bare, as they're parsed.
+fn map_key(key: &Value) -> String {
+    match key {
+        Value::String(s) if is_identifier(s) => s.clone(),
+        other => other.to_komrad(),
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && !s.ends_with('-')
+}
+
+/// Strings that need escapes, or that span lines, are triple-quoted.
+fn string_literal(s: &str) -> String {
+    let plain = !s.is_empty() && !s.contains(['"', '\\', '\n', '\r']);
+    if plain {
+        return format!("\"{}\"", s);
+    }
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\r', "\\r");
+    format!("\"\"\"{}\"\"\"", escaped)
+}
+
+fn reply_timeout(timeout: Duration) -> String {
+    let millis = timeout.as_millis();
+    if millis.is_multiple_of(60_000) {
+        format!("@{}m", millis / 60_000)
+    } else if millis.is_multiple_of(1000) {
+        format!("@{}s", millis / 1000)
+    } else {
+        format!("@{}ms", millis)
+    }
+}
+
+/// Whether the source had a blank line between two parsed statements.
+fn blank_line_between(block: &Block, index: usize) -> bool {
+    let (Some(before), Some(after)) = (block.location(index), block.location(index + 1)) else {
+        return false;
+    };
+    if !Arc::ptr_eq(before.src(), after.src()) {
+        return false;
+    }
+    let start = before.span().offset() + before.span().len();
+    let end = after.span().offset();
+    before
+        .src()
+        .inner()
+        .get(start..end)
+        .is_some_and(|between| between.matches('\n').count() > 1)
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+}
+
+impl Printer {
+    fn newline(&mut self) {
+        self.out.push('\n');
+        for _ in 0..self.indent {
+            self.out.push('\t');
+        }
+    }
+
+    /// Writes each statement on its own line at the current indent.
+    fn statements(&mut self, block: &Block) {
+        let mut first = true;
+        for (index, statement) in block.statements().iter().enumerate() {
+            // Blank lines in the source are kept by looking at the locations
+            if statement.is_no_op() {
+                continue;
+            }
+            if !first {
+                self.newline();
+            }
+            first = false;
+            self.statement(statement);
+            if blank_line_between(block, index) {
+                self.out.push('\n');
+            }
+        }
+        if !first {
+            self.out.push('\n');
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        if block.statements().iter().all(Statement::is_no_op) {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push('{');
+        self.indent += 1;
+        self.newline();
+        self.statements(block);
+        self.out.pop();
+        self.indent -= 1;
+        self.newline();
+        self.out.push('}');
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::NoOp => {}
+            Statement::Expander(expr) => {
+                self.out.push('*');
+                self.expr(expr);
+            }
+            Statement::Comment(text) => {
+                self.out.push_str("//");
+                self.out.push_str(text);
+            }
+            Statement::Expr(expr) => self.expr(expr),
+            Statement::Assignment(name, expr) => {
+                self.out.push_str(name);
+                self.out.push_str(" = ");
+                self.expr(expr);
+            }
+            Statement::Field(name, type_expr, default) => {
+                self.out.push_str(name);
+                self.out.push_str(": ");
+                self.out.push_str(&type_expr.to_komrad());
+                if let Some(expr) = default {
+                    self.out.push_str(" = ");
+                    self.expr(expr);
+                }
+            }
+            Statement::Handler(handler) => self.handler(handler),
+            Statement::Try(body, name, catch) => {
+                self.out.push_str("try ");
+                self.block(body);
+                self.out.push_str(" catch _");
+                self.out.push_str(name);
+                self.out.push(' ');
+                self.block(catch);
+            }
+            Statement::While(condition, body) => {
+                self.out.push_str("while ");
+                self.block(condition);
+                self.out.push(' ');
+                self.block(body);
+            }
+        }
+    }
+
+    fn handler(&mut self, handler: &Handler) {
+        self.out.push_str(&handler.pattern().to_komrad());
+        self.out.push(' ');
+        self.block(handler.block());
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Value(value) => self.out.push_str(&value.to_komrad()),
+            Expr::List(items) => {
+                self.out.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push(' ');
+                    }
+                    self.expr(item);
+                }
+                self.out.push(']');
+            }
+            Expr::Map(entries) if entries.is_empty() => self.out.push_str("{::}"),
+            Expr::Map(entries) => {
+                self.out.push_str("{: ");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    match key {
+                        Expr::Value(key) => self.out.push_str(&map_key(key)),
+                        other => self.expr(other),
+                    }
+                    self.out.push_str(" = ");
+                    self.expr(value);
+                }
+                self.out.push_str(" :}");
+            }
+            Expr::Variable(name) => self.out.push_str(name),
+            Expr::Binary(binary) => {
+                self.expr(binary.left());
+                // `a.b` reads better tight, but `1.b` would lex as a float
+                let is_number = |e: &Expr| matches!(e, Expr::Value(Value::Number(_)));
+                let tight = *binary.operator() == BinaryOp::Access
+                    && !is_number(binary.left())
+                    && !is_number(binary.right());
+                if tight {
+                    self.out.push('.');
+                } else {
+                    self.out.push_str(&format!(" {} ", binary.operator()));
+                }
+                self.expr(binary.right());
+            }
+            Expr::Unary(unary) => {
+                self.out.push_str(&unary.operator().to_string());
+                self.expr(unary.expr());
+            }
+            Expr::Call(call) => {
+                self.expr(call.target());
+                for arg in call.args() {
+                    self.out.push(' ');
+                    self.expr(arg);
+                }
+                if let Some(timeout) = call.reply_timeout() {
+                    self.out.push(' ');
+                    self.out.push_str(&reply_timeout(timeout));
+                }
+            }
+            Expr::Block(block) => self.block(block),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{BinaryExpr, CallExpr, ComparisonOp, UnaryExpr, UnaryOp, ValueType};
+
+    fn var(name: &str) -> Expr {
+        Expr::Variable(name.to_string())
+    }
+
+    #[test]
+    fn test_literals() {
+        assert_eq!(Number::UInt(3).to_komrad(), "3");
+        assert_eq!(Number::Int(3).to_komrad(), "+3");
+        assert_eq!(Number::Int(-3).to_komrad(), "-3");
+        assert_eq!(Number::Float(1.0).to_komrad(), "1.0");
+
+        assert_eq!(Value::String("hi".into()).to_komrad(), "\"hi\"");
+        assert_eq!(Value::String("".into()).to_komrad(), "\"\"\"\"\"\"");
+        assert_eq!(
+            Value::String("say \"hi\"\n".into()).to_komrad(),
+            "\"\"\"say \\\"hi\\\"\n\"\"\""
+        );
+        assert_eq!(
+            EmbeddedBlock::new(vec!["html".into()], "<p>\\</p>\n".into()).to_komrad(),
+            "```html\n<p>\\\\</p>\n```"
+        );
+    }
+
+    #[test]
+    fn test_pattern() {
+        let pattern = Pattern::new(vec![
+            TypeExpr::Word("check".into()),
+            TypeExpr::Hole("a".into()),
+            TypeExpr::TypeHole("b".into(), ValueType::Number),
+            TypeExpr::Binary("n".into(), ComparisonOp::Gt, Value::Number(Number::UInt(3))),
+            TypeExpr::BlockHole("then".into()),
+            TypeExpr::Value(Value::String("x".into())),
+        ]);
+        assert_eq!(
+            pattern.to_komrad(),
+            "[check _a _(b:Number) _(n > 3) _{then} \"x\"]"
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        let sum = Expr::Binary(BinaryExpr::new(
+            var("a"),
+            BinaryOp::Add,
+            Expr::Binary(BinaryExpr::new(var("u"), BinaryOp::Access, var("age"))),
+        ));
+        assert_eq!(sum.to_komrad(), "a + u.age");
+
+        let index = Expr::Binary(BinaryExpr::new(
+            var("list"),
+            BinaryOp::Access,
+            Expr::Value(Value::Number(Number::UInt(0))),
+        ));
+        assert_eq!(index.to_komrad(), "list . 0");
+
+        let call = Expr::Call(
+            CallExpr::new(
+                var("calc"),
+                vec![
+                    var("divide").into(),
+                    Expr::Unary(UnaryExpr::new(UnaryOp::Neg, var("x"))).into(),
+                ],
+            )
+            .with_reply_timeout(Some(Duration::from_millis(1500))),
+        );
+        assert_eq!(call.to_komrad(), "calc divide -x @1500ms");
+    }
+
+    #[test]
+    fn test_blocks() {
+        let handler = Statement::Handler(Arc::new(Handler::new(
+            Pattern::new(vec![TypeExpr::Word("main".into())]),
+            Block::new(vec![
+                Statement::Comment(" greet".into()),
+                Statement::Try(
+                    Block::new(vec![Statement::Expr(var("x"))]),
+                    "err".into(),
+                    Block::new(vec![]),
+                ),
+            ]),
+        )));
+        assert_eq!(
+            handler.to_komrad(),
+            "[main] {\n\t// greet\n\ttry {\n\t\tx\n\t} catch _err {}\n}"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use crate::map::Map;
+use crate::number::Number;
+use crate::value::Value;
+use serde_json::Value as Json;
+
+/// Conversion to and from plain JSON, where maps are objects and lists are
+/// arrays, rather than serde's encoding of the `Value` enum.
+impl Value {
+    /// Values with no JSON counterpart, such as channels and blocks, keep
+    /// their serde encoding.
+    pub fn to_json(&self) -> Json {
+        match self {
+            Value::Empty => Json::Null,
+            Value::Boolean(b) => Json::Bool(*b),
+            Value::Word(s) | Value::String(s) => Json::String(s.clone()),
+            Value::Number(n) => serde_json::to_value(n).unwrap_or(Json::Null),
+            Value::List(items) => Json::Array(items.iter().map(Value::to_json).collect()),
+            Value::Map(map) => Json::Object(
+                map.iter()
+                    .map(|(key, value)| (json_key(key), value.to_json()))
+                    .collect(),
+            ),
+            other => serde_json::to_value(other).unwrap_or(Json::Null),
+        }
+    }
+
+    /// Objects become maps with string keys, in the order they were written.
+    pub fn from_json(json: Json) -> Value {
+        match json {
+            Json::Null => Value::Empty,
+            Json::Bool(b) => Value::Boolean(b),
+            Json::Number(n) => serde_json::from_value::<Number>(Json::Number(n))
+                .map(Value::Number)
+                .unwrap_or(Value::Empty),
+            Json::String(s) => Value::String(s),
+            Json::Array(items) => Value::List(items.into_iter().map(Value::from_json).collect()),
+            Json::Object(object) => Value::Map(
+                object
+                    .into_iter()
+                    .map(|(key, value)| (Value::String(key), Value::from_json(value)))
+                    .collect::<Map>(),
+            ),
+        }
+    }
+}
+
+/// JSON keys are strings, so other keys are written as their text.
+fn json_key(key: &Value) -> String {
+    match key {
+        Value::Word(s) | Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let text = r#"{"name":"Ada","tags":["x",1,-2,2.5],"address":{"zip":null,"ok":true}}"#;
+        let value = Value::from_json(serde_json::from_str(text).unwrap());
+        let Value::Map(map) = &value else {
+            panic!("expected a map, found {:?}", value);
+        };
+        assert_eq!(map.get_field("name"), Some(&Value::from("Ada")));
+        assert!(matches!(map.get_field("address"), Some(Value::Map(_))));
+        assert_eq!(serde_json::to_string(&value.to_json()).unwrap(), text);
+    }
+}
@@ -4,7 +4,13 @@ pub mod agent;
 mod ast;
 mod channel;
 mod convert;
+mod coverage;
 mod error;
+mod json;
+pub mod format;
+mod list;
+mod location;
+mod map;
 mod message;
 mod number;
 mod operators;
@@ -22,6 +28,10 @@ pub mod prelude {
     pub use crate::channel::*;
     pub use crate::convert::*;
     pub use crate::error::*;
+    pub use crate::format::*;
+    pub use crate::list::*;
+    pub use crate::location::*;
+    pub use crate::map::*;
     pub use crate::message::*;
     pub use crate::number::*;
     pub use crate::operators::*;
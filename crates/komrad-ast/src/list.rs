@@ -0,0 +1,99 @@
+use crate::value::Value;
+use serde::{Deserialize, Serialize};
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// An immutable list of values, e.g. `[1 2 3]`.
+///
+/// Copies share their items, so passing a list around or binding it to
+/// another name doesn't copy it. Changing a list makes a new one and
+/// leaves other copies as they were.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct List(Arc<Vec<Value>>);
+
+impl List {
+    pub fn new() -> Self {
+        List::default()
+    }
+
+    /// Appends an item, copying the items first if another list shares them.
+    pub fn push(&mut self, item: Value) {
+        Arc::make_mut(&mut self.0).push(item);
+    }
+
+    /// The items, to change as a `Vec`.
+    pub fn make_mut(&mut self) -> &mut Vec<Value> {
+        Arc::make_mut(&mut self.0)
+    }
+
+    pub fn into_vec(self) -> Vec<Value> {
+        Arc::unwrap_or_clone(self.0)
+    }
+}
+
+impl Deref for List {
+    type Target = [Value];
+
+    fn deref(&self) -> &[Value] {
+        &self.0
+    }
+}
+
+impl Debug for List {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<Vec<Value>> for List {
+    fn from(items: Vec<Value>) -> Self {
+        List(Arc::new(items))
+    }
+}
+
+impl From<&[Value]> for List {
+    fn from(items: &[Value]) -> Self {
+        List(Arc::new(items.to_vec()))
+    }
+}
+
+impl FromIterator<Value> for List {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        List(Arc::new(iter.into_iter().collect()))
+    }
+}
+
+impl<'a> IntoIterator for &'a List {
+    type Item = &'a Value;
+    type IntoIter = std::slice::Iter<'a, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl IntoIterator for List {
+    type Item = Value;
+    type IntoIter = std::vec::IntoIter<Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_vec().into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_leave_copies_alone() {
+        let list: List = vec![Value::from(1), Value::from(2)].into();
+        let mut longer = list.clone();
+        longer.push(Value::from(3));
+        assert_eq!(list.len(), 2);
+        assert_eq!(longer.len(), 3);
+        assert_eq!(&longer[..2], &list[..]);
+    }
+}
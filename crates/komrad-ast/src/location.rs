@@ -0,0 +1,51 @@
+use miette::{NamedSource, SourceSpan};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+/// Where an AST node came from: the source file and the span within it.
+#[derive(Clone)]
+pub struct SourceLocation {
+    src: Arc<NamedSource<String>>,
+    span: SourceSpan,
+}
+
+impl SourceLocation {
+    pub fn new(src: Arc<NamedSource<String>>, offset: usize, len: usize) -> Self {
+        Self {
+            src,
+            span: SourceSpan::new(offset.into(), len),
+        }
+    }
+
+    pub fn src(&self) -> &Arc<NamedSource<String>> {
+        &self.src
+    }
+
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+
+    /// The name of the source file, e.g. `examples/hello-world.kom`.
+    pub fn name(&self) -> &str {
+        self.src.name()
+    }
+
+    /// The 1-based line the span starts on.
+    pub fn line(&self) -> usize {
+        let text = self.src.inner();
+        let offset = self.span.offset().min(text.len());
+        text[..offset].matches('\n').count() + 1
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name(), self.line())
+    }
+}
+
+impl Debug for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SourceLocation({})", self)
+    }
+}
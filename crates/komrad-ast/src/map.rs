@@ -0,0 +1,153 @@
+use crate::value::Value;
+use indexmap::IndexMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// A dictionary that keeps its entries in the order they were first
+/// inserted, e.g. `{: name = "x", n = 1 :}`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Map(IndexMap<Value, Value>);
+
+impl Map {
+    pub fn new() -> Self {
+        Map(IndexMap::new())
+    }
+
+    pub fn get(&self, key: &Value) -> Option<&Value> {
+        self.0.get(key)
+    }
+
+    /// Looks up a field named in source, as in `person.name`: identifier
+    /// keys are stored as strings, but a word key is found too.
+    pub fn get_field(&self, name: &str) -> Option<&Value> {
+        self.get(&Value::String(name.to_string()))
+            .or_else(|| self.get(&Value::Word(name.to_string())))
+    }
+
+    /// Inserts or replaces an entry. A replaced entry keeps its position.
+    pub fn insert(&mut self, key: Value, value: Value) -> Option<Value> {
+        self.0.insert(key, value)
+    }
+
+    /// Removes an entry, keeping the order of the rest.
+    pub fn remove(&mut self, key: &Value) -> Option<Value> {
+        self.0.shift_remove(key)
+    }
+
+    pub fn contains_key(&self, key: &Value) -> bool {
+        self.0.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&Value, &Value)> {
+        self.0.iter()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Value> {
+        self.0.keys()
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.0.values()
+    }
+}
+
+/// Equal maps may list their entries in different orders, so the entries
+/// are hashed one by one and summed.
+impl Hash for Map {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let entries = self.iter().fold(0u64, |sum, entry| {
+            let mut hasher = DefaultHasher::new();
+            entry.hash(&mut hasher);
+            sum.wrapping_add(hasher.finish())
+        });
+        state.write_usize(self.len());
+        state.write_u64(entries);
+    }
+}
+
+impl FromIterator<(Value, Value)> for Map {
+    fn from_iter<I: IntoIterator<Item = (Value, Value)>>(iter: I) -> Self {
+        Map(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Map {
+    type Item = (Value, Value);
+    type IntoIter = indexmap::map::IntoIter<Value, Value>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+/// Keys can be any value, so a map is written as a sequence of pairs
+/// rather than an object.
+impl Serialize for Map {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Map {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entries = Vec::<(Value, Value)>::deserialize(deserializer)?;
+        Ok(entries.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_keeps_insertion_order() {
+        let mut map: Map = [
+            (Value::from("b"), Value::from(1)),
+            (Value::from("a"), Value::from(2)),
+        ]
+        .into_iter()
+        .collect();
+        map.insert(Value::from("b"), Value::from(3));
+        map.insert(Value::Word("c".to_string()), Value::from(4));
+        assert_eq!(
+            map.keys().cloned().collect::<Vec<_>>(),
+            vec![
+                Value::from("b"),
+                Value::from("a"),
+                Value::Word("c".to_string())
+            ]
+        );
+        assert_eq!(map.get_field("b"), Some(&Value::from(3)));
+        assert_eq!(map.get_field("c"), Some(&Value::from(4)));
+        map.remove(&Value::from("b"));
+        assert_eq!(map.keys().next(), Some(&Value::from("a")));
+    }
+
+    #[test]
+    fn test_equal_maps_hash_alike() {
+        fn hash(map: &Map) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            map.hash(&mut hasher);
+            hasher.finish()
+        }
+        let entries = [
+            (Value::from("a"), Value::from(1)),
+            (Value::from("b"), Value::from(2)),
+        ];
+        let forwards: Map = entries.clone().into_iter().collect();
+        let backwards: Map = entries.into_iter().rev().collect();
+        assert_eq!(forwards, backwards);
+        assert_eq!(hash(&forwards), hash(&backwards));
+
+        let other: Map = [(Value::from("a"), Value::from(2))].into_iter().collect();
+        assert_ne!(hash(&forwards), hash(&other));
+    }
+}
@@ -5,17 +5,24 @@ use crate::value::Value;
 pub struct Message {
     terms: Vec<Value>,
     reply_to: Option<Channel>,
+    /// The agents waiting, in order, for the reply to this message.
+    call_chain: Vec<Channel>,
 }
 
 impl Message {
     pub fn new(terms: Vec<Value>, reply_to: Option<Channel>) -> Self {
-        Message { terms, reply_to }
+        Message {
+            terms,
+            reply_to,
+            call_chain: Vec::new(),
+        }
     }
 
     pub fn default() -> Self {
         Message {
             terms: Vec::new(),
             reply_to: None,
+            call_chain: Vec::new(),
         }
     }
 
@@ -26,11 +33,16 @@ impl Message {
     pub fn reply_to(&self) -> Option<Channel> {
         self.reply_to.clone()
     }
+
+    pub fn call_chain(&self) -> &[Channel] {
+        &self.call_chain
+    }
 }
 
 pub trait MessageBuilder {
     fn with_terms(self, terms: Vec<Value>) -> Self;
     fn with_reply_to(self, reply_to: Option<Channel>) -> Self;
+    fn with_call_chain(self, call_chain: Vec<Channel>) -> Self;
 
     fn with_term(self, term: Value) -> Self;
 }
@@ -46,6 +58,11 @@ impl MessageBuilder for Message {
         self
     }
 
+    fn with_call_chain(mut self, call_chain: Vec<Channel>) -> Self {
+        self.call_chain = call_chain;
+        self
+    }
+
     fn with_term(mut self, term: Value) -> Self {
         self.terms.push(term);
         self
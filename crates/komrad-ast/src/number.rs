@@ -1,15 +1,50 @@
+use crate::error::RuntimeError;
 use crate::prelude::literal;
+#[cfg(feature = "bignum")]
+use num_bigint::BigInt;
+#[cfg(feature = "bignum")]
+use rust_decimal::Decimal;
+#[cfg(feature = "bignum")]
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::fmt::Display;
 use std::hash::Hash;
-use std::ops::{Add, Div, Mul, Rem, Sub};
+use std::num::IntErrorKind;
+use std::ops::{Add, Div, Mul, Neg, Rem, Sub};
+use std::str::FromStr;
 
+/// A Komrad number.
+///
+/// Arithmetic between different representations promotes both sides first:
+///
+/// - `UInt` with `UInt` stays `UInt`, unless the result is negative (`3 - 5` is `Int(-2)`).
+/// - `Int` with `UInt` is `Int`, unless the result only fits a `UInt`.
+/// - Anything with a `Float` is a `Float`.
+/// - With the `bignum` feature, integers with a `Decimal` are `Decimal`, and integer
+///   results too large for 64 bits become `BigInt` instead of overflowing.
+///
+/// Results that cannot be represented are `RuntimeError::Overflow`. Numbers compare
+/// and hash by value, so `Int(3) == UInt(3) == Float(3.0)`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum Number {
     Int(literal::Int),
     UInt(literal::UInt),
     Float(literal::Float),
+    #[cfg(feature = "bignum")]
+    BigInt(BigInt),
+    #[cfg(feature = "bignum")]
+    Decimal(Decimal),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
 }
 
 impl Number {
@@ -18,16 +53,191 @@ impl Number {
             Number::Int(i) => *i == 0,
             Number::UInt(u) => *u == 0,
             Number::Float(f) => *f == 0.0,
+            #[cfg(feature = "bignum")]
+            Number::BigInt(b) => b.sign() == num_bigint::Sign::NoSign,
+            #[cfg(feature = "bignum")]
+            Number::Decimal(d) => d.is_zero(),
+        }
+    }
+
+    /// `a %% b`: whether `b` divides `a` evenly, across number types.
+    /// Always false for a zero divisor.
+    pub fn is_divisible_by(&self, divisor: &Number) -> bool {
+        matches!(self.clone() % divisor.clone(), Ok(rem) if rem.is_zero())
+    }
+
+    /// The nearest `f64`, which may lose precision.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Number::Int(i) => *i as f64,
+            Number::UInt(u) => *u as f64,
+            Number::Float(f) => *f,
+            #[cfg(feature = "bignum")]
+            Number::BigInt(b) => b.to_f64().unwrap_or(f64::NAN),
+            #[cfg(feature = "bignum")]
+            Number::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+        }
+    }
+
+    /// The number as an `i64`, if it is integral and in range.
+    pub fn to_i64(&self) -> Option<i64> {
+        self.to_i128().and_then(|i| i64::try_from(i).ok())
+    }
+
+    /// The number as a `u64`, if it is integral and in range.
+    pub fn to_u64(&self) -> Option<u64> {
+        self.to_i128().and_then(|i| u64::try_from(i).ok())
+    }
+
+    /// The exact integral value, if there is one that fits an `i128`.
+    fn to_i128(&self) -> Option<i128> {
+        match self {
+            Number::Int(i) => Some(*i as i128),
+            Number::UInt(u) => Some(*u as i128),
+            Number::Float(f) if f.fract() == 0.0 && f.abs() < 2f64.powi(127) => Some(*f as i128),
+            Number::Float(_) => None,
+            #[cfg(feature = "bignum")]
+            Number::BigInt(b) => b.to_i128(),
+            #[cfg(feature = "bignum")]
+            Number::Decimal(d) if d.fract().is_zero() => d.to_i128(),
+            #[cfg(feature = "bignum")]
+            Number::Decimal(_) => None,
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, Number::Float(_))
+    }
+
+    #[cfg(feature = "bignum")]
+    fn is_decimal(&self) -> bool {
+        matches!(self, Number::Decimal(_))
+    }
+
+    #[cfg(feature = "bignum")]
+    fn to_bigint(&self) -> Option<BigInt> {
+        match self {
+            Number::BigInt(b) => Some(b.clone()),
+            _ => self.to_i128().map(BigInt::from),
+        }
+    }
+
+    #[cfg(feature = "bignum")]
+    fn to_decimal(&self) -> Option<Decimal> {
+        match self {
+            Number::Decimal(d) => Some(*d),
+            _ => self.to_i128().and_then(Decimal::from_i128),
+        }
+    }
+
+    /// The smallest representation of an integer result. Results of unsigned
+    /// arithmetic prefer `UInt`; everything else prefers `Int`.
+    fn from_i128(value: i128, unsigned: bool) -> Result<Number, RuntimeError> {
+        if let Some(u) = u64::try_from(value).ok().filter(|_| unsigned) {
+            return Ok(Number::UInt(u));
+        }
+        if let Ok(i) = i64::try_from(value) {
+            return Ok(Number::Int(i));
         }
+        if let Ok(u) = u64::try_from(value) {
+            return Ok(Number::UInt(u));
+        }
+        #[cfg(feature = "bignum")]
+        return Ok(Number::BigInt(BigInt::from(value)));
+        #[cfg(not(feature = "bignum"))]
+        Err(RuntimeError::Overflow)
+    }
+
+    #[cfg(feature = "bignum")]
+    fn from_bigint(value: BigInt, unsigned: bool) -> Result<Number, RuntimeError> {
+        match value.to_i128() {
+            Some(i) => Number::from_i128(i, unsigned),
+            None => Ok(Number::BigInt(value)),
+        }
+    }
+
+    fn arith(self, other: Number, op: Arith) -> Result<Number, RuntimeError> {
+        if matches!(op, Arith::Div | Arith::Rem) && other.is_zero() {
+            return Err(RuntimeError::DivisionByZero);
+        }
+
+        if self.is_float() || other.is_float() {
+            let (a, b) = (self.to_f64(), other.to_f64());
+            let result = match op {
+                Arith::Add => a + b,
+                Arith::Sub => a - b,
+                Arith::Mul => a * b,
+                Arith::Div => a / b,
+                Arith::Rem => a % b,
+            };
+            if result.is_infinite() && a.is_finite() && b.is_finite() {
+                return Err(RuntimeError::Overflow);
+            }
+            return Ok(Number::Float(result));
+        }
+
+        #[cfg(feature = "bignum")]
+        if self.is_decimal() || other.is_decimal() {
+            let (Some(a), Some(b)) = (self.to_decimal(), other.to_decimal()) else {
+                return Err(RuntimeError::Overflow);
+            };
+            let result = match op {
+                Arith::Add => a.checked_add(b),
+                Arith::Sub => a.checked_sub(b),
+                Arith::Mul => a.checked_mul(b),
+                Arith::Div => a.checked_div(b),
+                Arith::Rem => a.checked_rem(b),
+            };
+            return result.map(Number::Decimal).ok_or(RuntimeError::Overflow);
+        }
+
+        let unsigned = matches!(self, Number::UInt(_)) && matches!(other, Number::UInt(_));
+        if let (Some(a), Some(b)) = (self.to_i128(), other.to_i128()) {
+            let result = match op {
+                Arith::Add => a.checked_add(b),
+                Arith::Sub => a.checked_sub(b),
+                Arith::Mul => a.checked_mul(b),
+                Arith::Div => a.checked_div(b),
+                Arith::Rem => a.checked_rem(b),
+            };
+            if let Some(result) = result {
+                return Number::from_i128(result, unsigned);
+            }
+        }
+
+        #[cfg(feature = "bignum")]
+        if let (Some(a), Some(b)) = (self.to_bigint(), other.to_bigint()) {
+            let result = match op {
+                Arith::Add => a + b,
+                Arith::Sub => a - b,
+                Arith::Mul => a * b,
+                Arith::Div => a / b,
+                Arith::Rem => a % b,
+            };
+            return Number::from_bigint(result, unsigned);
+        }
+
+        Err(RuntimeError::Overflow)
+    }
+
+    /// `++n`: the number plus one, keeping its type where it fits.
+    pub fn add1(self) -> Result<Number, RuntimeError> {
+        self + Number::UInt(1)
+    }
+
+    /// `--n`: the number minus one. Unsigned zero becomes `Int(-1)`.
+    pub fn sub1(self) -> Result<Number, RuntimeError> {
+        self - Number::UInt(1)
     }
 }
 
 impl Hash for Number {
+    /// Equal numbers hash alike whatever their representation, so integral
+    /// values hash as integers and everything else as its `f64` bits.
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        match self {
-            Number::Int(i) => i.hash(state),
-            Number::UInt(u) => u.hash(state),
-            Number::Float(f) => f.to_bits().hash(state),
+        match self.to_i128() {
+            Some(i) => i.hash(state),
+            None => self.to_f64().to_bits().hash(state),
         }
     }
 }
@@ -50,103 +260,142 @@ impl From<literal::Float> for Number {
     }
 }
 
+#[cfg(feature = "bignum")]
+impl From<BigInt> for Number {
+    fn from(value: BigInt) -> Self {
+        Number::BigInt(value)
+    }
+}
+
+#[cfg(feature = "bignum")]
+impl From<Decimal> for Number {
+    fn from(value: Decimal) -> Self {
+        Number::Decimal(value)
+    }
+}
+
+/// Parses a numeric literal: unsigned integers are `UInt`, signed ones are `Int`
+/// and anything with a fraction or exponent is a `Float`.
+impl FromStr for Number {
+    type Err = RuntimeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || RuntimeError::InvalidArugments(format!("Not a number: {}", s));
+        if s.contains(['.', 'e', 'E']) {
+            return s.parse().map(Number::Float).map_err(|_| invalid());
+        }
+        let unsigned = !s.starts_with(['+', '-']);
+        match s.parse::<i128>() {
+            Ok(i) => Number::from_i128(i, unsigned),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+                ) =>
+            {
+                #[cfg(feature = "bignum")]
+                return s.parse().map(Number::BigInt).map_err(|_| invalid());
+                #[cfg(not(feature = "bignum"))]
+                Err(RuntimeError::Overflow)
+            }
+            Err(_) => Err(invalid()),
+        }
+    }
+}
+
 impl Add for Number {
-    type Output = Number;
+    type Output = Result<Number, RuntimeError>;
 
     fn add(self, other: Number) -> Self::Output {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => Number::Int(i1 + i2),
-            (Number::UInt(u1), Number::UInt(u2)) => Number::UInt(u1 + u2),
-            (Number::Float(f1), Number::Float(f2)) => Number::Float(f1 + f2),
-            _ => panic!("Cannot add different number types"),
-        }
+        self.arith(other, Arith::Add)
     }
 }
 
 impl Sub for Number {
-    type Output = Number;
+    type Output = Result<Number, RuntimeError>;
 
     fn sub(self, other: Number) -> Self::Output {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => Number::Int(i1 - i2),
-            (Number::UInt(u1), Number::UInt(u2)) => Number::UInt(u1 - u2),
-            (Number::Float(f1), Number::Float(f2)) => Number::Float(f1 - f2),
-            _ => panic!("Cannot subtract different number types"),
-        }
+        self.arith(other, Arith::Sub)
     }
 }
 
 impl Mul for Number {
-    type Output = Number;
+    type Output = Result<Number, RuntimeError>;
 
     fn mul(self, other: Number) -> Self::Output {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => Number::Int(i1 * i2),
-            (Number::UInt(u1), Number::UInt(u2)) => Number::UInt(u1 * u2),
-            (Number::Float(f1), Number::Float(f2)) => Number::Float(f1 * f2),
-            _ => panic!("Cannot multiply different number types"),
-        }
+        self.arith(other, Arith::Mul)
     }
 }
 
+/// Integer division truncates toward zero: `7 / 2` is `3`.
 impl Div for Number {
-    type Output = Number;
+    type Output = Result<Number, RuntimeError>;
 
     fn div(self, other: Number) -> Self::Output {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => Number::Int(i1 / i2),
-            (Number::UInt(u1), Number::UInt(u2)) => Number::UInt(u1 / u2),
-            (Number::Float(f1), Number::Float(f2)) => Number::Float(f1 / f2),
-            _ => panic!("Cannot divide different number types"),
-        }
+        self.arith(other, Arith::Div)
     }
 }
 
 impl Rem for Number {
-    type Output = Number;
+    type Output = Result<Number, RuntimeError>;
 
     fn rem(self, other: Number) -> Self::Output {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => Number::Int(i1 % i2),
-            (Number::UInt(u1), Number::UInt(u2)) => Number::UInt(u1 % u2),
-            (Number::Float(f1), Number::Float(f2)) => Number::Float(f1 % f2),
-            _ => panic!("Cannot calculate remainder of different number types"),
+        self.arith(other, Arith::Rem)
+    }
+}
+
+impl Neg for Number {
+    type Output = Result<Number, RuntimeError>;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Number::Float(f) => Ok(Number::Float(-f)),
+            #[cfg(feature = "bignum")]
+            Number::Decimal(d) => Ok(Number::Decimal(-d)),
+            n => Number::Int(0) - n,
         }
     }
 }
 
 impl PartialEq for Number {
     fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => i1 == i2,
-            (Number::UInt(u1), Number::UInt(u2)) => u1 == u2,
-            (Number::Float(f1), Number::Float(f2)) => f1 == f2,
-            _ => false,
-        }
+        self.partial_cmp(other) == Some(Ordering::Equal)
     }
 }
 
 impl Eq for Number {}
 
+/// Numbers of different types compare by value: `-1 < 0u`, `1 < 1.5`.
+/// `NaN` is unordered, which `Ord` cannot express.
+#[allow(clippy::non_canonical_partial_ord_impl)]
 impl PartialOrd for Number {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => i1.partial_cmp(i2),
-            (Number::UInt(u1), Number::UInt(u2)) => u1.partial_cmp(u2),
-            (Number::Float(f1), Number::Float(f2)) => f1.partial_cmp(f2),
-            _ => None,
+            (Number::Float(a), Number::Float(b)) => a.partial_cmp(b),
+            (a, b) if a.is_float() || b.is_float() => match (a.to_i128(), b.to_i128()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => a.to_f64().partial_cmp(&b.to_f64()),
+            },
+            #[cfg(feature = "bignum")]
+            (a, b) if a.is_decimal() || b.is_decimal() => match (a.to_decimal(), b.to_decimal()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                _ => a.to_f64().partial_cmp(&b.to_f64()),
+            },
+            (a, b) => match (a.to_i128(), b.to_i128()) {
+                (Some(a), Some(b)) => a.partial_cmp(&b),
+                #[cfg(feature = "bignum")]
+                _ => a.to_bigint()?.partial_cmp(&b.to_bigint()?),
+                #[cfg(not(feature = "bignum"))]
+                _ => None,
+            },
         }
     }
 }
 
 impl Ord for Number {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match (self, other) {
-            (Number::Int(i1), Number::Int(i2)) => i1.cmp(i2),
-            (Number::UInt(u1), Number::UInt(u2)) => u1.cmp(u2),
-            (Number::Float(f1), Number::Float(f2)) => f1.partial_cmp(f2).unwrap(),
-            _ => panic!("Cannot compare different number types"),
-        }
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.partial_cmp(other)
+            .expect("Cannot compare NaN with another number")
     }
 }
 
@@ -156,8 +405,122 @@ impl Display for Number {
             Number::Int(i) => write!(f, "{}", i),
             Number::UInt(u) => write!(f, "{}", u),
             Number::Float(fl) => write!(f, "{}", fl),
+            #[cfg(feature = "bignum")]
+            Number::BigInt(b) => write!(f, "{}", b),
+            #[cfg(feature = "bignum")]
+            Number::Decimal(d) => write!(f, "{}", d),
         }
     }
 }
 
-impl Number {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::Hasher;
+
+    fn hash_of(n: &Number) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        n.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn test_promotion() {
+        assert!(matches!(
+            Number::UInt(3) + Number::UInt(4),
+            Ok(Number::UInt(7))
+        ));
+        assert!(matches!(
+            Number::UInt(3) - Number::UInt(5),
+            Ok(Number::Int(-2))
+        ));
+        assert!(matches!(
+            Number::Int(-3) + Number::UInt(5),
+            Ok(Number::Int(2))
+        ));
+        assert!(matches!(
+            Number::Int(1) + Number::UInt(u64::MAX - 1),
+            Ok(Number::UInt(u64::MAX))
+        ));
+        assert!(matches!(
+            Number::UInt(7) / Number::UInt(2),
+            Ok(Number::UInt(3))
+        ));
+        assert!(matches!(Number::UInt(1) + Number::Float(0.5), Ok(Number::Float(f)) if f == 1.5));
+        assert!(matches!(-Number::UInt(5), Ok(Number::Int(-5))));
+        assert!(matches!(Number::UInt(0).sub1(), Ok(Number::Int(-1))));
+    }
+
+    #[test]
+    fn test_checked_arithmetic() {
+        assert_eq!(
+            Number::UInt(1) / Number::Int(0),
+            Err(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            Number::Float(1.0) % Number::Float(0.0),
+            Err(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            Number::Float(f64::MAX) * Number::Float(2.0),
+            Err(RuntimeError::Overflow)
+        );
+    }
+
+    #[cfg(not(feature = "bignum"))]
+    #[test]
+    fn test_overflow() {
+        assert_eq!(
+            Number::UInt(u64::MAX) + Number::UInt(1),
+            Err(RuntimeError::Overflow)
+        );
+        assert_eq!(
+            Number::Int(i64::MIN) - Number::UInt(u64::MAX),
+            Err(RuntimeError::Overflow)
+        );
+        assert_eq!(-Number::UInt(u64::MAX), Err(RuntimeError::Overflow));
+        assert_eq!(
+            "18446744073709551616".parse::<Number>(),
+            Err(RuntimeError::Overflow)
+        );
+    }
+
+    #[cfg(feature = "bignum")]
+    #[test]
+    fn test_bignum() {
+        let big = (Number::UInt(u64::MAX) + Number::UInt(1)).unwrap();
+        assert!(matches!(big, Number::BigInt(_)));
+        assert_eq!(big.to_string(), "18446744073709551616");
+        assert!(matches!(
+            big.clone() - Number::UInt(1),
+            Ok(Number::UInt(u64::MAX))
+        ));
+        assert_eq!("18446744073709551616".parse::<Number>(), Ok(big));
+
+        let price = Number::Decimal(Decimal::new(1999, 2));
+        assert_eq!((price * Number::UInt(3)).unwrap().to_string(), "59.97");
+    }
+
+    #[test]
+    fn test_equality_across_types() {
+        let three = [Number::Int(3), Number::UInt(3), Number::Float(3.0)];
+        for a in &three {
+            for b in &three {
+                assert_eq!(a, b);
+                assert_eq!(hash_of(a), hash_of(b));
+            }
+        }
+        assert_ne!(Number::Int(-1), Number::UInt(u64::MAX));
+        assert_ne!(Number::Float(3.5), Number::Int(3));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert!(matches!("42".parse(), Ok(Number::UInt(42))));
+        assert!(matches!("-42".parse(), Ok(Number::Int(-42))));
+        assert!(matches!("+42".parse(), Ok(Number::Int(42))));
+        assert!(matches!("4.2e1".parse(), Ok(Number::Float(f)) if f == 42.0));
+        assert!("4x".parse::<Number>().is_err());
+    }
+}
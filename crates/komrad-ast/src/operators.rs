@@ -39,6 +39,11 @@ pub enum BinaryOp {
     Or,
     Eq,
     Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Divisible,
     Access,
 }
 
@@ -50,6 +55,41 @@ pub enum UnaryOp {
     Dec,
 }
 
+impl Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "%",
+            BinaryOp::And => "&&",
+            BinaryOp::Or => "||",
+            BinaryOp::Eq => "==",
+            BinaryOp::Ne => "!=",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Divisible => "%%",
+            BinaryOp::Access => ".",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            UnaryOp::Neg => "-",
+            UnaryOp::Not => "!",
+            UnaryOp::Inc => "++",
+            UnaryOp::Dec => "--",
+        };
+        write!(f, "{}", op)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct BinaryExpr {
     pub op: BinaryOp,
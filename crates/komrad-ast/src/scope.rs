@@ -1,5 +1,6 @@
 use crate::prelude::Value;
 use dashmap::DashMap;
+use std::collections::HashSet;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
 
@@ -38,6 +39,48 @@ impl Scope {
         self.bindings.insert(name, value);
     }
 
+    /// Rebinds a name where it's already bound, in this scope or the nearest
+    /// parent that has it. A name bound nowhere is bound here.
+    pub async fn assign(&mut self, name: String, value: Value) {
+        let mut scope = &*self;
+        loop {
+            if scope.bindings.contains_key(&name) {
+                break;
+            }
+            match &scope.parent {
+                Some(parent) => scope = parent,
+                None => {
+                    scope = &*self;
+                    break;
+                }
+            }
+        }
+        scope.bindings.insert(name, value);
+    }
+
+    /// Takes a name's value out of the nearest scope that binds it, leaving
+    /// it bound to `Value::Empty` until it's assigned again.
+    pub fn take(&mut self, name: &str) -> Option<Value> {
+        if let Some(mut value) = self.bindings.get_mut(name) {
+            return Some(std::mem::replace(&mut *value, Value::Empty));
+        }
+        self.parent.as_mut()?.take(name)
+    }
+
+    /// A copy of the scope and its parents, without the given names.
+    pub fn without(&self, names: &HashSet<String>) -> Scope {
+        let scope = match &self.parent {
+            Some(parent) => parent.without(names),
+            None => Scope::new(),
+        };
+        for (name, value) in self.iter() {
+            if !names.contains(&name) {
+                scope.bindings.insert(name, value);
+            }
+        }
+        scope
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (String, Value)> {
         self.bindings.iter().map(|entry| {
             let (key, value) = entry.pair();
@@ -108,4 +151,18 @@ mod tests {
             Some(Value::Number(Number::Float(2.0))),
         );
     }
+
+    #[tokio::test]
+    async fn test_assign() {
+        let mut parent_scope = Scope::new();
+        parent_scope.set("x".to_string(), Value::from(1)).await;
+
+        let mut child_scope = Scope::with_parent(parent_scope.clone());
+        child_scope.assign("x".to_string(), Value::from(2)).await;
+        child_scope.assign("y".to_string(), Value::from(3)).await;
+
+        assert_eq!(parent_scope.get("x"), Some(Value::from(2)));
+        assert_eq!(parent_scope.get("y"), None);
+        assert_eq!(child_scope.get("y"), Some(Value::from(3)));
+    }
 }
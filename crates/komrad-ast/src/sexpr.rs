@@ -106,6 +106,11 @@ impl ToSexpr for BinaryOp {
             BinaryOp::Or => Sexpr::Atom("or".to_string()),
             BinaryOp::Eq => Sexpr::Atom("==".to_string()),
             BinaryOp::Ne => Sexpr::Atom("!=".to_string()),
+            BinaryOp::Lt => Sexpr::Atom("<".to_string()),
+            BinaryOp::Le => Sexpr::Atom("<=".to_string()),
+            BinaryOp::Gt => Sexpr::Atom(">".to_string()),
+            BinaryOp::Ge => Sexpr::Atom(">=".to_string()),
+            BinaryOp::Divisible => Sexpr::Atom("%%".to_string()),
             BinaryOp::Access => Sexpr::Atom("access".to_string()),
         }
     }
@@ -134,6 +139,14 @@ impl ToSexpr for Value {
                 list_items.extend(items.iter().map(|item| item.to_sexpr()));
                 Sexpr::List(list_items)
             }
+            Value::Map(map) => {
+                let mut items = vec![Sexpr::Atom("map".to_string())];
+                items.extend(
+                    map.iter()
+                        .map(|(key, value)| Sexpr::List(vec![key.to_sexpr(), value.to_sexpr()])),
+                );
+                Sexpr::List(items)
+            }
             Value::Block(block) => {
                 Sexpr::List(vec![Sexpr::Atom("block".to_string()), block.to_sexpr()])
             }
@@ -196,6 +209,15 @@ impl ToSexpr for Expr {
                 list_items.extend(items.iter().map(|item| item.to_sexpr()));
                 Sexpr::List(list_items)
             }
+            Expr::Map(entries) => {
+                let mut items = vec![Sexpr::Atom("map".to_string())];
+                items.extend(
+                    entries
+                        .iter()
+                        .map(|(key, value)| Sexpr::List(vec![key.to_sexpr(), value.to_sexpr()])),
+                );
+                Sexpr::List(items)
+            }
             Expr::Value(value) => value.to_sexpr(),
             Expr::Variable(name) => Sexpr::Atom(name.clone()),
             Expr::Binary(binary) => {
@@ -205,6 +227,9 @@ impl ToSexpr for Expr {
 
                 Sexpr::List(vec![op, left, right])
             }
+            Expr::Unary(unary) => {
+                Sexpr::List(vec![unary.operator().to_sexpr(), unary.expr().to_sexpr()])
+            }
             Expr::Call(call) => {
                 let mut items = vec![Sexpr::Atom("call".to_string()), call.target().to_sexpr()];
 
@@ -212,6 +237,13 @@ impl ToSexpr for Expr {
                     items.push(arg.to_sexpr());
                 }
 
+                if let Some(timeout) = call.reply_timeout() {
+                    items.push(Sexpr::List(vec![
+                        Sexpr::Atom("timeout".to_string()),
+                        Sexpr::Atom(format!("{:?}", timeout)),
+                    ]));
+                }
+
                 Sexpr::List(items)
             }
             Expr::Block(block) => block.to_sexpr(),
@@ -253,6 +285,20 @@ impl ToSexpr for Statement {
                 Sexpr::List(items)
             }
             Statement::Handler(handler) => handler.to_sexpr(),
+            Statement::Try(body, name, handler) => Sexpr::List(vec![
+                Sexpr::Atom("try".to_string()),
+                body.to_sexpr(),
+                Sexpr::List(vec![
+                    Sexpr::Atom("catch".to_string()),
+                    Sexpr::Atom(name.clone()),
+                    handler.to_sexpr(),
+                ]),
+            ]),
+            Statement::While(condition, body) => Sexpr::List(vec![
+                Sexpr::Atom("while".to_string()),
+                condition.to_sexpr(),
+                body.to_sexpr(),
+            ]),
         }
     }
 }
@@ -361,6 +407,7 @@ impl ToSexpr for ValueType {
             ValueType::String => Sexpr::Atom("String".to_string()),
             ValueType::Number => Sexpr::Atom("Number".to_string()),
             ValueType::List => Sexpr::Atom("List".to_string()),
+            ValueType::Map => Sexpr::Atom("Map".to_string()),
             ValueType::Block => Sexpr::Atom("Block".to_string()),
             ValueType::Bytes => Sexpr::Atom("Bytes".to_string()),
             ValueType::EmbeddedBlock => Sexpr::Atom("EmbeddedBlock".to_string()),
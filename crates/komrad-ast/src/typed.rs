@@ -37,10 +37,13 @@ impl Typed for ValueType {
             (ValueType::String, ValueType::String) => true,
             (ValueType::Number, ValueType::Number) => true,
             (ValueType::List, ValueType::List) => true,
+            (ValueType::Map, ValueType::Map) => true,
             (ValueType::Block, ValueType::Block) => true,
             (ValueType::Bytes, ValueType::Bytes) => true,
             (ValueType::EmbeddedBlock, ValueType::EmbeddedBlock) => true,
             (ValueType::User(u1), ValueType::User(u2)) => u1 == u2,
+            // Agents spawned from a definition are still channels
+            (ValueType::User(_), ValueType::Channel) => true,
             _ => false,
         }
     }
@@ -1,8 +1,11 @@
 use crate::ast::Block;
 use crate::channel::Channel;
 use crate::error::RuntimeError;
+use crate::list::List;
+use crate::map::Map;
 use crate::number::Number;
 use crate::prelude::{literal, EmbeddedBlock, TypeExpr};
+use crate::typed::Typed;
 use crate::value_type::ValueType;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -19,7 +22,8 @@ pub enum Value {
     Word(String),
     String(String),
     Number(Number),
-    List(Vec<Value>),
+    List(List),
+    Map(Map),
     Block(Box<Block>),
     Bytes(Vec<u8>),
     Embedded(EmbeddedBlock),
@@ -61,12 +65,34 @@ impl From<Number> for Value {
     }
 }
 
+/// A checked arithmetic result: the number, or the error that stopped it.
+impl From<Result<Number, RuntimeError>> for Value {
+    fn from(value: Result<Number, RuntimeError>) -> Self {
+        match value {
+            Ok(n) => Value::Number(n),
+            Err(e) => Value::Error(e),
+        }
+    }
+}
+
 impl From<Vec<Value>> for Value {
     fn from(value: Vec<Value>) -> Self {
+        Value::List(value.into())
+    }
+}
+
+impl From<List> for Value {
+    fn from(value: List) -> Self {
         Value::List(value)
     }
 }
 
+impl From<Map> for Value {
+    fn from(value: Map) -> Self {
+        Value::Map(value)
+    }
+}
+
 impl From<literal::Int> for Value {
     fn from(value: literal::Int) -> Self {
         Value::Number(Number::Int(value))
@@ -136,28 +162,46 @@ impl Value {
         match self {
             Value::Empty => ValueType::Empty,
             Value::Error(_) => ValueType::Error,
-            Value::Channel(_) => ValueType::Channel,
+            Value::Channel(channel) => match channel.agent_type() {
+                Some(name) => ValueType::User(name.to_string()),
+                None => ValueType::Channel,
+            },
             Value::Boolean(_) => ValueType::Boolean,
             Value::Word(_) => ValueType::Word,
             Value::String(_) => ValueType::String,
             Value::Number(_) => ValueType::Number,
             Value::List(_) => ValueType::List,
+            Value::Map(_) => ValueType::Map,
             Value::Block(_) => ValueType::Block,
             Value::Bytes(_) => ValueType::Bytes,
             Value::Embedded(_) => ValueType::EmbeddedBlock,
         }
     }
 
+    /// Whether the value belongs to `typ`. Besides subtyping, a channel
+    /// belongs to every protocol its agent implements.
+    pub fn has_type(&self, typ: &ValueType) -> bool {
+        if self.get_type().is_subtype_of(typ) {
+            return true;
+        }
+        match (self, typ) {
+            (Value::Channel(channel), ValueType::User(name)) => channel.implements(name),
+            (Value::Channel(channel), ValueType::List) => channel.implements("List"),
+            _ => false,
+        }
+    }
+
     pub fn get_type_expr(&self) -> TypeExpr {
         match self {
             Value::Empty => TypeExpr::new_empty(),
             Value::Error(_) => TypeExpr::HasType(ValueType::Error),
-            Value::Channel(_) => TypeExpr::HasType(ValueType::Channel),
+            Value::Channel(_) => TypeExpr::HasType(self.get_type()),
             Value::Boolean(_) => TypeExpr::HasType(ValueType::Boolean),
             Value::Word(_) => TypeExpr::HasType(ValueType::Word),
             Value::String(_) => TypeExpr::HasType(ValueType::String),
             Value::Number(_) => TypeExpr::HasType(ValueType::Number),
             Value::List(_) => TypeExpr::HasType(ValueType::List),
+            Value::Map(_) => TypeExpr::HasType(ValueType::Map),
             Value::Block(_) => TypeExpr::HasType(ValueType::Block),
             Value::Bytes(_) => TypeExpr::HasType(ValueType::Bytes),
             Value::Embedded(_) => TypeExpr::HasType(ValueType::EmbeddedBlock),
@@ -176,6 +220,7 @@ impl PartialEq for Value {
             (Value::String(s1), Value::String(s2)) => s1 == s2,
             (Value::Number(n1), Value::Number(n2)) => n1 == n2,
             (Value::List(l1), Value::List(l2)) => l1 == l2,
+            (Value::Map(m1), Value::Map(m2)) => m1 == m2,
             (Value::Block(b1), Value::Block(b2)) => b1 == b2,
             (Value::Bytes(b1), Value::Bytes(b2)) => b1 == b2,
             (Value::Embedded(b1), Value::Embedded(b2)) => b1 == b2,
@@ -187,12 +232,7 @@ impl PartialEq for Value {
 impl PartialOrd for Value {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
         match (self, other) {
-            (Value::Number(n1), Value::Number(n2)) => match (n1, n2) {
-                (Number::Int(i1), Number::Int(i2)) => i1.partial_cmp(i2),
-                (Number::UInt(u1), Number::UInt(u2)) => u1.partial_cmp(u2),
-                (Number::Float(f1), Number::Float(f2)) => f1.partial_cmp(f2),
-                _ => None,
-            },
+            (Value::Number(n1), Value::Number(n2)) => n1.partial_cmp(n2),
             (Value::String(s1), Value::String(s2)) => s1.partial_cmp(s2),
             (Value::Boolean(b1), Value::Boolean(b2)) => b1.partial_cmp(b2),
             _ => {
@@ -208,12 +248,7 @@ impl Rem for &Value {
 
     fn rem(self, rhs: Self) -> Self::Output {
         match (self, rhs) {
-            (Value::Number(Number::Int(i1)), Value::Number(Number::Int(i2))) => {
-                Value::Number(Number::Int(i1 % i2))
-            }
-            (Value::Number(Number::UInt(u1)), Value::Number(Number::UInt(u2))) => {
-                Value::Number(Number::UInt(u1 % u2))
-            }
+            (Value::Number(n1), Value::Number(n2)) => (n1.clone() % n2.clone()).into(),
             (_, _) => Value::Error(RuntimeError::TypeMismatch(String::new())),
         }
     }
@@ -230,6 +265,7 @@ impl Display for Value {
             Value::String(s) => write!(f, "{}", s),
             Value::Number(n) => write!(f, "{}", n),
             Value::List(l) => write!(f, "List: {:?}", l),
+            Value::Map(m) => write!(f, "Map: {:?}", m),
             Value::Block(b) => write!(f, "Block: {:?}", b),
             Value::Bytes(b) => write!(f, "Bytes: {:?}", b),
             Value::Embedded(b) => write!(f, "EmbeddedBlock: {:?}", b),
@@ -248,6 +284,7 @@ impl Hash for Value {
             Value::String(s) => s.hash(state),
             Value::Number(n) => n.hash(state),
             Value::List(l) => l.hash(state),
+            Value::Map(m) => m.hash(state),
             Value::Block(b) => b.hash(state),
             Value::Bytes(b) => b.hash(state),
             Value::Embedded(b) => b.hash(state),
@@ -12,6 +12,7 @@ pub enum ValueType {
     String,
     Number,
     List,
+    Map,
     Block,
     Bytes,
     EmbeddedBlock,
@@ -29,6 +30,7 @@ impl Display for ValueType {
             ValueType::String => write!(f, "String"),
             ValueType::Number => write!(f, "Number"),
             ValueType::List => write!(f, "List"),
+            ValueType::Map => write!(f, "Map"),
             ValueType::Block => write!(f, "Block"),
             ValueType::Bytes => write!(f, "Bytes"),
             ValueType::EmbeddedBlock => write!(f, "EmbeddedBlock"),
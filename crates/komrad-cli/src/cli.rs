@@ -1,10 +1,16 @@
 use crate::banner::banner;
+use crate::repl::repl;
 use clap::{Parser, Subcommand};
 use komrad_ast::prelude::{Message, Value};
 use komrad_ast::sexpr::ToSexpr;
+use komrad_parser::check::{check_module, check_types};
+use komrad_parser::cst::SyntaxTree;
+use komrad_parser::module_loader::ModuleLoader;
+use miette::Report;
 use notify::Watcher;
 use owo_colors::OwoColorize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
 #[derive(Clone, Debug, Parser)]
@@ -27,6 +33,16 @@ struct Args {
     /// Wait for 100 ms before exiting
     #[arg(long, global = true, default_value_t = false)]
     wait_100: bool,
+
+    /// Fail calls that wait longer than this many milliseconds for a reply
+    #[arg(long, global = true, value_name = "MS")]
+    reply_timeout: Option<u64>,
+}
+
+impl Args {
+    fn reply_timeout(&self) -> Option<Duration> {
+        self.reply_timeout.map(Duration::from_millis)
+    }
 }
 
 #[derive(Clone, Debug, Subcommand)]
@@ -43,6 +59,26 @@ enum Subcommands {
         #[clap(long, default_value_t = false)]
         watch: bool,
     },
+    /// Rewrite files in canonical Komrad formatting
+    Fmt {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// List files that would change instead of rewriting them
+        #[clap(long, default_value_t = false)]
+        check: bool,
+    },
+    /// Warn about handlers that can never run
+    Check {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+
+        /// Also check field defaults, type annotations and operators
+        #[clap(long, default_value_t = false)]
+        types: bool,
+    },
+    /// Start an interactive session
+    Repl,
 }
 
 #[derive(Clone, Debug, clap::ValueEnum, Default)]
@@ -73,11 +109,14 @@ pub async fn main() {
         Some(Subcommands::Parse { file, fmt }) => handle_parse(file, fmt),
         Some(Subcommands::Run { file, watch }) => {
             if watch {
-                handle_run_watch(file).await;
+                handle_run_watch(file, args.reply_timeout()).await;
             } else {
                 handle_run(file, &args).await;
             }
         }
+        Some(Subcommands::Fmt { files, check }) => handle_fmt(files, check),
+        Some(Subcommands::Check { files, types }) => handle_check(files, types),
+        Some(Subcommands::Repl) => repl(args.reply_timeout()).await,
         None => {
             println!("Use `komrad --help` for more information.");
         }
@@ -86,52 +125,127 @@ pub async fn main() {
 
 fn handle_parse(file: PathBuf, fmt: Option<KomradOutputFormat>) {
     info!("Parsing file: {}", file.display());
-    let source = std::fs::read_to_string(&file).expect("Failed to read file");
-    match komrad_parser::parse_verbose(&source) {
+    match komrad_parser::parse_file(&file) {
         Ok(module_builder) => {
             debug!("Parsed module: {:?}", module_builder);
-            let sexpr = module_builder.to_sexpr();
             match fmt {
-                None | Some(KomradOutputFormat::Komrad) => println!("{}", sexpr),
-                Some(KomradOutputFormat::Sexpr) => println!("{}", sexpr),
+                // Printed from the syntax tree, as `komrad fmt` writes it
+                Some(KomradOutputFormat::Komrad) => match std::fs::read_to_string(&file) {
+                    Ok(source) => print!("{}", SyntaxTree::parse(&source).format()),
+                    Err(err) => error!("Failed to read {}: {}", file.display(), err),
+                },
+                None | Some(KomradOutputFormat::Sexpr) => println!("{}", module_builder.to_sexpr()),
             }
         }
         Err(err) => {
-            error!("Failed to parse file: {}", err);
+            error!("Failed to parse file: {:?}", err);
+        }
+    }
+}
+
+/// Rewrites each file in canonical form. With `check`, lists the files that
+/// would change instead, and exits with an error if there are any.
+fn handle_fmt(files: Vec<PathBuf>, check: bool) {
+    let mut failed = false;
+    for file in files {
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(err) => {
+                error!("Failed to read {}: {}", file.display(), err);
+                failed = true;
+                continue;
+            }
+        };
+        let name = file.display().to_string();
+        let tree = SyntaxTree::parse(&source);
+        let module_builder = match tree.to_module(&name) {
+            Ok(module_builder) => module_builder,
+            Err(err) => {
+                error!("Failed to parse file: {:?}", Report::new(err));
+                failed = true;
+                continue;
+            }
+        };
+
+        let formatted = tree.format();
+        if formatted == source {
+            continue;
+        }
+        // Formatting only moves whitespace, which must not change the program
+        let unchanged = komrad_parser::parse_source(&name, &formatted)
+            .is_ok_and(|formatted| formatted.build_block() == module_builder.build_block());
+        if !unchanged {
+            error!("Not formatting {}: it would change the program", name);
+            failed = true;
+            continue;
+        }
+        if check {
+            println!("{}", file.display());
+            failed = true;
+        } else if let Err(err) = std::fs::write(&file, formatted) {
+            error!("Failed to write {}: {}", file.display(), err);
+            failed = true;
+        } else {
+            info!("Formatted {}", file.display());
         }
     }
+
+    if failed {
+        std::process::exit(1);
+    }
 }
 
-/// Runs the file once by reading, parsing, building the block, creating the system/agent,
-/// and sending the "main" message. Returns the system instance so that it can be shut down later.
-async fn run_file_once(file: &PathBuf) -> Option<komrad_vm::System> {
+/// Reports parse errors and handler warnings for each file, and type
+/// warnings with `types`. Exits with an error if there are any.
+fn handle_check(files: Vec<PathBuf>, types: bool) {
+    let mut failed = false;
+    for file in files {
+        let module_builder = match komrad_parser::parse_file(&file) {
+            Ok(module_builder) => module_builder,
+            Err(err) => {
+                eprintln!("{:?}", err);
+                failed = true;
+                continue;
+            }
+        };
+        let mut warnings = check_module(&module_builder);
+        if types {
+            warnings.extend(check_types(&module_builder));
+        }
+        for warning in warnings {
+            eprintln!("{:?}", Report::new(warning));
+            failed = true;
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Runs the file once by reading, parsing, resolving imports, building the block,
+/// creating the system/agent, and sending the "main" message. Returns the system
+/// instance so that it can be shut down later.
+async fn run_file_once(file: &Path, reply_timeout: Option<Duration>) -> Option<komrad_vm::System> {
     info!("Running file: {}", file.display());
-    match tokio::fs::read_to_string(file).await {
-        Ok(source) => {
-            debug!("Read source: {}", source);
-            match komrad_parser::parse_verbose(&source) {
-                Ok(module_builder) => {
-                    let block = module_builder.build_block();
-                    let system = komrad_vm::System::new();
-                    let agent = system.create_agent("main", &block).await;
-
-                    match agent
-                        .send(Message::new(vec![Value::Word("main".into())], None))
-                        .await
-                    {
-                        Ok(_) => info!("Main sent to agent"),
-                        Err(err) => info!("Failed to send main message: {}", err),
-                    }
-                    Some(system)
-                }
-                Err(err) => {
-                    error!("Failed to parse file: {}", err);
-                    None
-                }
+    match ModuleLoader::new().load(file) {
+        Ok(module_builder) => {
+            let block = module_builder.build_block();
+            let mut system = komrad_vm::System::new();
+            system.set_reply_timeout(reply_timeout);
+            let agent = system.create_agent("main", &block).await;
+
+            match agent
+                .send(Message::new(vec![Value::Word("main".into())], None))
+                .await
+            {
+                Ok(_) => info!("Main sent to agent"),
+                Err(err) => info!("Failed to send main message: {}", err),
             }
+            Some(system)
         }
         Err(err) => {
-            info!("Failed to read file: {}", err);
+            error!("Failed to load file: {:?}", err);
             None
         }
     }
@@ -139,7 +253,7 @@ async fn run_file_once(file: &PathBuf) -> Option<komrad_vm::System> {
 
 /// Non‑watch mode: execute the file once then optionally wait.
 async fn handle_run(file: PathBuf, args: &Args) {
-    let system = run_file_once(&file).await;
+    let system = run_file_once(&file, args.reply_timeout()).await;
     tokio::time::sleep(tokio::time::Duration::from_millis(0)).await;
 
     if args.wait_1 {
@@ -163,13 +277,13 @@ async fn handle_run(file: PathBuf, args: &Args) {
 
 /// Watch mode: set up a file watcher using `notify` v8 and hot-reload on file changes.
 /// Before running the file again, the previous system instance is gracefully shut down.
-async fn handle_run_watch(file: PathBuf) {
+async fn handle_run_watch(file: PathBuf, reply_timeout: Option<Duration>) {
     use notify::{Config, RecommendedWatcher, RecursiveMode};
     use std::sync::{mpsc, Arc, Mutex};
 
     info!("Running file in watch mode: {}", file.display());
     // Initial run
-    let mut active_system = run_file_once(&file).await;
+    let mut active_system = run_file_once(&file, reply_timeout).await;
 
     // Setup file watcher
     let (tx, rx) = mpsc::channel();
@@ -204,7 +318,7 @@ async fn handle_run_watch(file: PathBuf) {
                             system.shutdown().await;
                         }
                         // Re-run the file and store the new system instance
-                        active_system = run_file_once(&file).await;
+                        active_system = run_file_once(&file, reply_timeout).await;
                     },
                     Ok(Err(e)) => {
                         info!("Watcher error: {}", e);
@@ -1,4 +1,5 @@
 mod banner;
 mod cli;
+mod repl;
 
 pub use cli::main;
@@ -0,0 +1,177 @@
+use komrad_ast::prelude::{Block, Value};
+use komrad_ast::sexpr::ToSexpr;
+use komrad_parser::cst::{TokenKind, tokenize};
+use komrad_parser::module_builder::ModuleBuilder;
+use komrad_parser::module_loader::ModuleLoader;
+use miette::Report;
+use owo_colors::OwoColorize;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+
+const HELP: &str = r#"Enter statements, agents, and handlers as you would in a .kom file.
+
+  :load <file.kom>   load and evaluate a file in the REPL scope
+  :scope             list the bindings in scope
+  :sexpr             toggle printing the s-expression of each input
+  :help              show this help
+  :quit              exit the REPL"#;
+
+/// An interactive session backed by a single persistent agent.
+///
+/// Every input is evaluated in the same scope, so assignments, agent
+/// definitions and handlers accumulate across inputs. Handlers become
+/// handlers of the REPL agent itself, reachable through `me`.
+pub async fn repl(reply_timeout: Option<Duration>) {
+    let mut system = komrad_vm::System::new();
+    system.set_reply_timeout(reply_timeout);
+    system.create_agent("repl", &Block::new(vec![])).await;
+    let agent = system.agent("repl").expect("REPL agent was just created");
+
+    println!("{}", "Komrad REPL. Type :help for help.".bright_cyan());
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut show_sexpr = false;
+    let mut input = String::new();
+
+    loop {
+        prompt(input.is_empty());
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(err) => {
+                eprintln!("{}", err.red());
+                break;
+            }
+        };
+
+        // Commands work inside an unfinished block too, without ending it
+        if let Some(command) = repl_command(&line) {
+            let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+            match name {
+                "q" | "quit" | "exit" => break,
+                "h" | "help" => println!("{}", HELP),
+                "sexpr" => {
+                    show_sexpr = !show_sexpr;
+                    println!("s-expressions {}", if show_sexpr { "on" } else { "off" });
+                }
+                "scope" => {
+                    for (name, value) in agent.bindings().await {
+                        println!("{} = {}", name.bright_yellow(), value);
+                    }
+                }
+                "load" if !arg.trim().is_empty() => {
+                    match ModuleLoader::new().load(&PathBuf::from(arg.trim())) {
+                        Ok(module) => {
+                            print_value(&system.run(agent.eval(&module.build_block())).await);
+                        }
+                        Err(err) => eprintln!("{:?}", err),
+                    }
+                }
+                _ => eprintln!("Unknown command :{}. Type :help for help.", command),
+            }
+            continue;
+        }
+
+        input.push_str(&line);
+        input.push('\n');
+        if nesting_depth(&input) > 0 {
+            continue;
+        }
+
+        let source = std::mem::take(&mut input);
+        if source.trim().is_empty() {
+            continue;
+        }
+        match parse(&source) {
+            Ok(module) => {
+                if show_sexpr {
+                    println!("{}", module.to_sexpr().format(0).bright_black());
+                }
+                print_value(&system.run(agent.eval(&module.build_block())).await);
+            }
+            Err(err) => eprintln!("{:?}", err),
+        }
+    }
+
+    system.shutdown().await;
+}
+
+fn parse(source: &str) -> Result<ModuleBuilder, Report> {
+    komrad_parser::parse_verbose(source)?.resolve_imports()
+}
+
+fn prompt(fresh: bool) {
+    let prompt = if fresh { "komrad> " } else { "   ...> " };
+    print!("{}", prompt.bright_magenta());
+    let _ = std::io::stdout().flush();
+}
+
+/// The command on a line such as `:load file.kom`, without the colon. A
+/// colon that isn't followed by a letter, as in `:}`, is Komrad.
+fn repl_command(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix(':')
+        .filter(|command| command.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+fn print_value(value: &Value) {
+    match value {
+        Value::Empty => {}
+        Value::Error(err) => eprintln!("{}", err.red()),
+        value => println!("{}", value),
+    }
+}
+
+/// How many brackets are still open at the end of the input, going by
+/// the same tokens the parser reads, so that brackets in strings, comments
+/// and embedded blocks don't count. An unfinished embedded block or
+/// triple-quoted string counts as open too. A positive depth means the
+/// REPL should keep reading lines before parsing.
+fn nesting_depth(input: &str) -> i32 {
+    let mut depth = 0;
+    for token in tokenize(input) {
+        match token.kind {
+            TokenKind::Punct => match token.text.as_str() {
+                "{" | "[" | "(" | "{:" => depth += 1,
+                "}" | "]" | ")" | ":}" => depth -= 1,
+                _ => {}
+            },
+            TokenKind::Error
+                if token.text.starts_with("```") || token.text.starts_with("\"\"\"") =>
+            {
+                depth += 1
+            }
+            _ => {}
+        }
+    }
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nesting_depth() {
+        assert_eq!(nesting_depth("x = 1\n"), 0);
+        assert_eq!(nesting_depth("agent Alice {\n"), 1);
+        assert_eq!(nesting_depth("agent Alice {\n  [foo] {\n"), 2);
+        assert_eq!(nesting_depth("agent Alice {\n  [foo] {}\n}\n"), 0);
+        assert_eq!(nesting_depth("Io println \"{\"\n"), 0);
+        assert_eq!(nesting_depth("x = ```html\n<p>{</p>\n"), 1);
+        assert_eq!(nesting_depth("x = ```html\n<p>{</p>\n```\n"), 0);
+        assert_eq!(nesting_depth("x = 1 // {\n"), 0);
+        assert_eq!(nesting_depth("Io println '{'\n"), 0);
+        assert_eq!(nesting_depth("person = {:\n"), 1);
+        assert_eq!(nesting_depth("x = \"\"\"\n{\n"), 1);
+    }
+
+    #[test]
+    fn test_repl_command() {
+        assert_eq!(repl_command("  :load lib.kom"), Some("load lib.kom"));
+        assert_eq!(repl_command(":}"), None);
+        assert_eq!(repl_command("x = 1"), None);
+    }
+}
@@ -0,0 +1,417 @@
+use komrad_ast::prelude::{
+    Block, CallExpr, Expr, Handler, ParserError, Pattern, Statement, TypeExpr, Value,
+};
+use komrad_parser::parse_source;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Agent,
+    Handler,
+}
+
+/// An agent or handler defined in a document.
+#[derive(Debug, Clone)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Byte range of an agent's name, or of a handler's `[...]` pattern.
+    pub range: Range<usize>,
+    pub pattern: Option<Pattern>,
+    pub children: Vec<Symbol>,
+}
+
+impl Symbol {
+    /// The word a handler is called with, e.g. `divide` for `[divide _a _b]`.
+    pub fn command(&self) -> Option<&str> {
+        match self.pattern.as_ref()?.terms().first()? {
+            TypeExpr::Word(word) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn walk<'a>(&'a self, symbols: &mut Vec<&'a Symbol>) {
+        symbols.push(self);
+        for child in &self.children {
+            child.walk(symbols);
+        }
+    }
+}
+
+/// What the editor needs to know about a document: its symbols, and why
+/// it failed to parse. Statements that do parse still provide symbols.
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub symbols: Vec<Symbol>,
+    pub errors: Vec<ParserError>,
+}
+
+impl Analysis {
+    pub fn new(name: &str, text: &str) -> Self {
+        let (module, errors) = match parse_source(name, text) {
+            Ok(module) => (module, Vec::new()),
+            Err(err) => (err.module, err.errors),
+        };
+        Analysis {
+            symbols: Locator::new(text).statements(module.statements()),
+            errors,
+        }
+    }
+
+    /// Every symbol, nested ones included, in source order.
+    pub fn all_symbols(&self) -> Vec<&Symbol> {
+        let mut symbols = Vec::new();
+        for symbol in &self.symbols {
+            symbol.walk(&mut symbols);
+        }
+        symbols
+    }
+
+    /// Agents named `word`, and handlers whose pattern starts with `word`.
+    pub fn definitions(&self, word: &str) -> Vec<&Symbol> {
+        self.all_symbols()
+            .into_iter()
+            .filter(|symbol| match symbol.kind {
+                SymbolKind::Agent => symbol.name == word,
+                SymbolKind::Handler => symbol.command() == Some(word),
+            })
+            .collect()
+    }
+
+    /// Markdown describing the handler pattern under `offset`, or the
+    /// agents and handlers that `word` refers to.
+    pub fn hover(&self, offset: usize, word: &str) -> Option<String> {
+        let symbols = self.all_symbols();
+        if let Some(handler) = symbols
+            .iter()
+            .rev()
+            .find(|s| s.kind == SymbolKind::Handler && s.range.contains(&offset))
+        {
+            return Some(describe(handler));
+        }
+
+        let descriptions: Vec<String> = self.definitions(word).into_iter().map(describe).collect();
+        if descriptions.is_empty() {
+            None
+        } else {
+            Some(descriptions.join("\n\n---\n\n"))
+        }
+    }
+}
+
+fn describe(symbol: &Symbol) -> String {
+    match (&symbol.kind, &symbol.pattern) {
+        (SymbolKind::Handler, Some(pattern)) => {
+            let mut text = format!("```komrad\n{}\n```", pattern_source(pattern));
+            let holes: Vec<String> = pattern.terms().iter().filter_map(describe_hole).collect();
+            if !holes.is_empty() {
+                text.push_str("\n\n");
+                text.push_str(&holes.join("\n"));
+            }
+            text
+        }
+        _ => {
+            let mut text = format!("```komrad\nagent {}\n```", symbol.name);
+            for child in &symbol.children {
+                text.push_str(&format!("\n- `{}`", child.name));
+            }
+            text
+        }
+    }
+}
+
+fn describe_hole(term: &TypeExpr) -> Option<String> {
+    match term {
+        TypeExpr::Hole(name) => Some(format!("- `{}`: any", name)),
+        TypeExpr::BlockHole(name) => Some(format!("- `{}`: Block", name)),
+        TypeExpr::TypeHole(name, value_type) => Some(format!("- `{}`: {}", name, value_type)),
+        TypeExpr::Binary(name, op, value) => Some(format!(
+            "- `{}`: where `{} {} {}`",
+            name,
+            name,
+            op,
+            value_source(value)
+        )),
+        _ => None,
+    }
+}
+
+/// Renders a pattern the way it is written, e.g. `[divide _a _(b:Number)]`.
+pub fn pattern_source(pattern: &Pattern) -> String {
+    let terms: Vec<String> = pattern
+        .terms()
+        .iter()
+        .map(|term| match term {
+            TypeExpr::Empty => "_".to_string(),
+            TypeExpr::Value(value) => value_source(value),
+            TypeExpr::HasType(value_type) => value_type.to_string(),
+            TypeExpr::Word(word) => word.clone(),
+            TypeExpr::Hole(name) => format!("_{}", name),
+            TypeExpr::TypeHole(name, value_type) => format!("_({}:{})", name, value_type),
+            TypeExpr::BlockHole(name) => format!("_{{{}}}", name),
+            TypeExpr::Binary(name, op, value) => {
+                format!("_({} {} {})", name, op, value_source(value))
+            }
+        })
+        .collect();
+    format!("[{}]", terms.join(" "))
+}
+
+fn value_source(value: &Value) -> String {
+    match value {
+        Value::String(s) => format!("{:?}", s),
+        other => other.to_string(),
+    }
+}
+
+/// `agent Name { ... }`, or `agent Name implements Protocol... { ... }`.
+fn agent_definition(call: &CallExpr) -> Option<(&str, &Block)> {
+    if call.target() != &Expr::Variable("agent".to_string()) {
+        return None;
+    }
+    match call.args().as_slice() {
+        [name, .., block] => match (name.as_ref(), block.as_ref()) {
+            (Expr::Variable(name), Expr::Block(block)) => Some((name, block)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Finds where parsed agents and handlers appear in the source. The AST
+/// doesn't carry spans, so this searches forward through the text in the
+/// same order the statements were parsed.
+struct Locator<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Locator<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text, pos: 0 }
+    }
+
+    fn statements(&mut self, statements: &[Statement]) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        for statement in statements {
+            match statement {
+                Statement::Handler(handler) => symbols.extend(self.handler(handler)),
+                Statement::Expr(Expr::Call(call)) => {
+                    if let Some((name, block)) = agent_definition(call) {
+                        symbols.extend(self.agent(name, block));
+                    }
+                }
+                Statement::Try(body, _, catch) => {
+                    symbols.extend(self.statements(body.statements()));
+                    symbols.extend(self.statements(catch.statements()));
+                }
+                Statement::While(_, body) => symbols.extend(self.statements(body.statements())),
+                _ => {}
+            }
+        }
+        symbols
+    }
+
+    fn agent(&mut self, name: &str, block: &Block) -> Option<Symbol> {
+        let range = self.find_agent_name(name)?;
+        self.pos = range.end;
+        Some(Symbol {
+            name: name.to_string(),
+            kind: SymbolKind::Agent,
+            range,
+            pattern: None,
+            children: self.statements(block.statements()),
+        })
+    }
+
+    fn handler(&mut self, handler: &Handler) -> Option<Symbol> {
+        let range = self.find_handler_pattern(handler.pattern())?;
+        self.pos = range.end;
+        Some(Symbol {
+            name: pattern_source(handler.pattern()),
+            kind: SymbolKind::Handler,
+            range,
+            pattern: Some(handler.pattern().clone()),
+            children: self.statements(handler.block().statements()),
+        })
+    }
+
+    /// The range of `Name` in the next `agent Name`.
+    fn find_agent_name(&self, name: &str) -> Option<Range<usize>> {
+        let mut from = self.pos;
+        while let Some(found) = self.text[from..].find("agent") {
+            let start = from + found;
+            let end = start + "agent".len();
+            from = end;
+            if self.text[..start]
+                .chars()
+                .next_back()
+                .is_some_and(is_ident_char)
+            {
+                continue;
+            }
+            let rest = &self.text[end..];
+            let trimmed = rest.trim_start();
+            if trimmed.len() == rest.len() || !trimmed.starts_with(name) {
+                continue;
+            }
+            let name_start = end + (rest.len() - trimmed.len());
+            let name_end = name_start + name.len();
+            if self.text[name_end..]
+                .chars()
+                .next()
+                .is_some_and(is_ident_char)
+            {
+                continue;
+            }
+            return Some(name_start..name_end);
+        }
+        None
+    }
+
+    /// The range of the next `[...]` that is followed by a block and starts
+    /// with the pattern's first term.
+    fn find_handler_pattern(&self, pattern: &Pattern) -> Option<Range<usize>> {
+        let first = pattern_source(&Pattern::new(
+            pattern.terms().iter().take(1).cloned().collect(),
+        ));
+        let first = first.trim_start_matches('[').trim_end_matches(']');
+
+        let mut from = self.pos;
+        while let Some(found) = self.text[from..].find('[') {
+            let open = from + found;
+            from = open + 1;
+            let Some(close) = self.matching_bracket(open) else {
+                continue;
+            };
+            let inner = self.text[open + 1..close].trim_start();
+            let followed_by_block = self.text[close + 1..].trim_start().starts_with('{');
+            if followed_by_block && inner.starts_with(first) {
+                return Some(open..close + 1);
+            }
+        }
+        None
+    }
+
+    fn matching_bracket(&self, open: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (i, c) in self.text[open..].char_indices() {
+            match c {
+                '[' => depth += 1,
+                ']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(open + i);
+                    }
+                }
+                '\n' => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"agent Calculator {
+	[divide _a _(b:Number)] {
+		a / b
+	}
+
+	[check _(n > 3) _{then}] {
+		then
+	}
+}
+
+[main] {
+	calc = spawn Calculator
+	result = calc divide 10 2
+}
+"#;
+
+    #[test]
+    fn test_symbols() {
+        let analysis = Analysis::new("test.kom", SOURCE);
+        assert!(analysis.errors.is_empty());
+
+        let names: Vec<&str> = analysis
+            .all_symbols()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Calculator",
+                "[divide _a _(b:Number)]",
+                "[check _(n > 3) _{then}]",
+                "[main]"
+            ]
+        );
+
+        let agent = &analysis.symbols[0];
+        assert_eq!(&SOURCE[agent.range.clone()], "Calculator");
+        assert_eq!(
+            &SOURCE[agent.children[0].range.clone()],
+            "[divide _a _(b:Number)]"
+        );
+    }
+
+    #[test]
+    fn test_definitions() {
+        let analysis = Analysis::new("test.kom", SOURCE);
+
+        let agents = analysis.definitions("Calculator");
+        assert_eq!(agents.len(), 1);
+        assert_eq!(agents[0].kind, SymbolKind::Agent);
+
+        let handlers = analysis.definitions("divide");
+        assert_eq!(handlers.len(), 1);
+        assert_eq!(handlers[0].command(), Some("divide"));
+
+        assert!(analysis.definitions("multiply").is_empty());
+    }
+
+    #[test]
+    fn test_hover() {
+        let analysis = Analysis::new("test.kom", SOURCE);
+
+        let offset = SOURCE.find("check").unwrap();
+        let hover = analysis.hover(offset, "check").unwrap();
+        assert!(hover.contains("[check _(n > 3) _{then}]"));
+        assert!(hover.contains("- `n`: where `n > 3`"));
+        assert!(hover.contains("- `then`: Block"));
+
+        let offset = SOURCE.rfind("divide").unwrap();
+        let hover = analysis.hover(offset, "divide").unwrap();
+        assert!(hover.contains("- `a`: any"));
+        assert!(hover.contains("- `b`: Number"));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let analysis = Analysis::new("test.kom", "agent Broken {\n\t[oops _x] {\n");
+        assert!(analysis.symbols.is_empty());
+        assert!(!analysis.errors.is_empty());
+    }
+
+    #[test]
+    fn test_symbols_despite_errors() {
+        let source = "agent Fine {\n\t[ok] {\n\t\tx = = 1\n\t}\n}\n\n[main] {\n\ty = 2 )\n}\n";
+        let analysis = Analysis::new("test.kom", source);
+        assert_eq!(analysis.errors.len(), 2);
+        let names: Vec<&str> = analysis
+            .all_symbols()
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Fine", "[ok]", "[main]"]);
+    }
+}
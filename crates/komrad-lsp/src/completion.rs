@@ -0,0 +1,95 @@
+use crate::analysis::{Analysis, SymbolKind};
+use tower_lsp::lsp_types::{CompletionItem, CompletionItemKind};
+
+/// The agents every Komrad agent starts with, and the commands they handle.
+pub const DEFAULT_AGENTS: &[(&str, &[&str])] = &[
+    ("Io", &["println", "print", "shutdown"]),
+    ("Fs", &["read-all", "read-all-binary", "list-dir"]),
+    ("json", &["encode", "decode"]),
+    ("Registry", &["define", "spawn", "lookup"]),
+    ("agent", &[]),
+    ("protocol", &[]),
+    ("spawn", &[]),
+    ("assert", &[]),
+    ("dict", &[]),
+    ("monitor", &[]),
+    ("link", &[]),
+    ("stop", &[]),
+];
+
+/// Completions for the word being typed at the end of `line_prefix`.
+///
+/// After a default agent (`Io `) these are its commands; otherwise they are
+/// the default agents and the agents defined in the document.
+pub fn completions(analysis: &Analysis, line_prefix: &str) -> Vec<CompletionItem> {
+    let mut words: Vec<&str> = line_prefix.split_whitespace().collect();
+    if !line_prefix.ends_with(char::is_whitespace) {
+        // Drop the partial word; the editor filters by it.
+        words.pop();
+    }
+
+    let receiver = words.last().and_then(|receiver| {
+        DEFAULT_AGENTS
+            .iter()
+            .find(|(agent, commands)| agent == receiver && !commands.is_empty())
+    });
+    if let Some((agent, commands)) = receiver {
+        return commands
+            .iter()
+            .map(|command| CompletionItem {
+                label: command.to_string(),
+                kind: Some(CompletionItemKind::METHOD),
+                detail: Some(format!("{} {}", agent, command)),
+                ..Default::default()
+            })
+            .collect();
+    }
+
+    let defaults = DEFAULT_AGENTS.iter().map(|(agent, _)| CompletionItem {
+        label: agent.to_string(),
+        kind: Some(CompletionItemKind::MODULE),
+        detail: Some("default agent".to_string()),
+        ..Default::default()
+    });
+    let defined = analysis
+        .all_symbols()
+        .into_iter()
+        .filter(|symbol| symbol.kind == SymbolKind::Agent)
+        .map(|symbol| CompletionItem {
+            label: symbol.name.clone(),
+            kind: Some(CompletionItemKind::CLASS),
+            detail: Some(format!("agent {}", symbol.name)),
+            ..Default::default()
+        });
+    defaults.chain(defined).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(items: Vec<CompletionItem>) -> Vec<String> {
+        items.into_iter().map(|item| item.label).collect()
+    }
+
+    #[test]
+    fn test_default_agent_commands() {
+        let analysis = Analysis::default();
+        assert_eq!(
+            labels(completions(&analysis, "\tIo ")),
+            vec!["println", "print", "shutdown"]
+        );
+        assert_eq!(
+            labels(completions(&analysis, "text = Fs read")),
+            vec!["read-all", "read-all-binary", "list-dir"]
+        );
+    }
+
+    #[test]
+    fn test_agent_names() {
+        let analysis = Analysis::new("test.kom", "agent Alice {}\n");
+        let items = labels(completions(&analysis, "a = spawn A"));
+        assert!(items.contains(&"Alice".to_string()));
+        assert!(items.contains(&"Io".to_string()));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::analysis::Analysis;
+use std::ops::Range;
+use tower_lsp::lsp_types::{self, Position};
+
+/// An open document and its analysis.
+///
+/// The analysis works in byte offsets while LSP positions count UTF-16
+/// code units, so the document also keeps the offset of each line.
+#[derive(Debug)]
+pub struct Document {
+    text: String,
+    line_starts: Vec<usize>,
+    analysis: Analysis,
+}
+
+impl Document {
+    pub fn new(name: &str, text: String) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        let analysis = Analysis::new(name, &text);
+        Self {
+            text,
+            line_starts,
+            analysis,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn analysis(&self) -> &Analysis {
+        &self.analysis
+    }
+
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.text[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let mut units = 0;
+        for (i, c) in self.text[start..].char_indices() {
+            if units >= position.character as usize || c == '\n' {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        self.text.len()
+    }
+
+    pub fn range(&self, range: Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(range.start), self.position(range.end))
+    }
+
+    /// The identifier under `offset`, e.g. `read-all` or `Calculator`.
+    pub fn word_at(&self, offset: usize) -> Option<&str> {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+        let offset = offset.min(self.text.len());
+        let start = self.text[..offset]
+            .char_indices()
+            .rev()
+            .take_while(|(_, c)| is_word(*c))
+            .last()
+            .map_or(offset, |(i, _)| i);
+        let end = self.text[offset..]
+            .char_indices()
+            .find(|(_, c)| !is_word(*c))
+            .map_or(self.text.len(), |(i, _)| offset + i);
+        let word = self.text[start..end].trim_start_matches('_');
+        (!word.is_empty()).then_some(word)
+    }
+
+    /// The text of the line before `position`, for completions.
+    pub fn line_prefix(&self, position: Position) -> &str {
+        let end = self.offset(position);
+        let start = self.line_starts[(position.line as usize).min(self.line_starts.len() - 1)];
+        &self.text[start.min(end)..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let doc = Document::new("test.kom", "x = \"é\"\ny = x\n".to_string());
+
+        let offset = doc.text().find('y').unwrap();
+        assert_eq!(doc.position(offset), Position::new(1, 0));
+        assert_eq!(doc.offset(Position::new(1, 0)), offset);
+
+        // `é` is two bytes but one UTF-16 unit
+        let quote = doc.text().rfind('"').unwrap();
+        assert_eq!(doc.position(quote), Position::new(0, 6));
+        assert_eq!(doc.offset(Position::new(0, 6)), quote);
+    }
+
+    #[test]
+    fn test_word_at() {
+        let doc = Document::new("test.kom", "Fs read-all path\n".to_string());
+        assert_eq!(doc.word_at(5), Some("read-all"));
+        assert_eq!(doc.word_at(0), Some("Fs"));
+        assert_eq!(doc.line_prefix(Position::new(0, 3)), "Fs ");
+    }
+}
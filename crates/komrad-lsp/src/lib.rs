@@ -0,0 +1,6 @@
+pub mod analysis;
+pub mod completion;
+pub mod document;
+mod server;
+
+pub use server::{Backend, serve_stdio};
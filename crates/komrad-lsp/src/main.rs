@@ -0,0 +1,13 @@
+use tracing_subscriber::EnvFilter;
+
+#[tokio::main]
+async fn main() {
+    // stdout carries the protocol, so logs go to stderr
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    komrad_lsp::serve_stdio().await;
+}
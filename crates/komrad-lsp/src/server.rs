@@ -0,0 +1,204 @@
+use crate::analysis::{Symbol, SymbolKind};
+use crate::completion::completions;
+use crate::document::Document;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use tower_lsp::jsonrpc::Result;
+use tower_lsp::lsp_types::*;
+use tower_lsp::{Client, LanguageServer, LspService, Server};
+use tracing::debug;
+
+pub struct Backend {
+    client: Client,
+    documents: RwLock<HashMap<Url, Document>>,
+}
+
+impl Backend {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            documents: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Re-parses a document and publishes its diagnostics.
+    async fn update(&self, uri: Url, text: String, version: Option<i32>) {
+        let document = Document::new(uri.path(), text);
+        let diagnostics = document
+            .analysis()
+            .errors
+            .iter()
+            .map(|error| {
+                let start = error.span.offset();
+                Diagnostic {
+                    range: document.range(start..start + error.span.len()),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("komrad".to_string()),
+                    message: error.kind.to_string(),
+                    ..Default::default()
+                }
+            })
+            .collect();
+
+        self.documents.write().await.insert(uri.clone(), document);
+        self.client
+            .publish_diagnostics(uri, diagnostics, version)
+            .await;
+    }
+}
+
+#[allow(deprecated)]
+fn document_symbol(document: &Document, symbol: &Symbol) -> DocumentSymbol {
+    let range = document.range(symbol.range.clone());
+    DocumentSymbol {
+        name: symbol.name.clone(),
+        detail: None,
+        kind: match symbol.kind {
+            SymbolKind::Agent => tower_lsp::lsp_types::SymbolKind::CLASS,
+            SymbolKind::Handler => tower_lsp::lsp_types::SymbolKind::METHOD,
+        },
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: Some(
+            symbol
+                .children
+                .iter()
+                .map(|child| document_symbol(document, child))
+                .collect(),
+        ),
+    }
+}
+
+#[tower_lsp::async_trait]
+impl LanguageServer for Backend {
+    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+        Ok(InitializeResult {
+            server_info: Some(ServerInfo {
+                name: "komrad-lsp".to_string(),
+                version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            }),
+            capabilities: ServerCapabilities {
+                text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                    TextDocumentSyncKind::FULL,
+                )),
+                hover_provider: Some(HoverProviderCapability::Simple(true)),
+                definition_provider: Some(OneOf::Left(true)),
+                document_symbol_provider: Some(OneOf::Left(true)),
+                completion_provider: Some(CompletionOptions {
+                    trigger_characters: Some(vec![" ".to_string()]),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn initialized(&self, _: InitializedParams) {
+        debug!("komrad-lsp initialized");
+    }
+
+    async fn shutdown(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn did_open(&self, params: DidOpenTextDocumentParams) {
+        let doc = params.text_document;
+        self.update(doc.uri, doc.text, Some(doc.version)).await;
+    }
+
+    async fn did_change(&self, params: DidChangeTextDocumentParams) {
+        // We ask for full syncs, so the last change holds the whole text.
+        if let Some(change) = params.content_changes.into_iter().last() {
+            let doc = params.text_document;
+            self.update(doc.uri, change.text, Some(doc.version)).await;
+        }
+    }
+
+    async fn did_close(&self, params: DidCloseTextDocumentParams) {
+        let uri = params.text_document.uri;
+        self.documents.write().await.remove(&uri);
+        self.client.publish_diagnostics(uri, vec![], None).await;
+    }
+
+    async fn goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> Result<Option<GotoDefinitionResponse>> {
+        let position = params.text_document_position_params;
+        let uri = position.text_document.uri;
+        let documents = self.documents.read().await;
+        let Some(document) = documents.get(&uri) else {
+            return Ok(None);
+        };
+        let Some(word) = document.word_at(document.offset(position.position)) else {
+            return Ok(None);
+        };
+
+        let locations: Vec<Location> = document
+            .analysis()
+            .definitions(word)
+            .into_iter()
+            .map(|symbol| Location::new(uri.clone(), document.range(symbol.range.clone())))
+            .collect();
+        Ok(match locations.len() {
+            0 => None,
+            1 => Some(GotoDefinitionResponse::Scalar(locations[0].clone())),
+            _ => Some(GotoDefinitionResponse::Array(locations)),
+        })
+    }
+
+    async fn hover(&self, params: HoverParams) -> Result<Option<Hover>> {
+        let position = params.text_document_position_params;
+        let documents = self.documents.read().await;
+        let Some(document) = documents.get(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let offset = document.offset(position.position);
+        let word = document.word_at(offset).unwrap_or_default();
+
+        Ok(document.analysis().hover(offset, word).map(|value| Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value,
+            }),
+            range: None,
+        }))
+    }
+
+    async fn completion(&self, params: CompletionParams) -> Result<Option<CompletionResponse>> {
+        let position = params.text_document_position;
+        let documents = self.documents.read().await;
+        let Some(document) = documents.get(&position.text_document.uri) else {
+            return Ok(None);
+        };
+        let items = completions(document.analysis(), document.line_prefix(position.position));
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    async fn document_symbol(
+        &self,
+        params: DocumentSymbolParams,
+    ) -> Result<Option<DocumentSymbolResponse>> {
+        let documents = self.documents.read().await;
+        let Some(document) = documents.get(&params.text_document.uri) else {
+            return Ok(None);
+        };
+        let symbols = document
+            .analysis()
+            .symbols
+            .iter()
+            .map(|symbol| document_symbol(document, symbol))
+            .collect();
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+}
+
+/// Serves the language server over stdin and stdout until the editor exits.
+pub async fn serve_stdio() {
+    let (service, socket) = LspService::new(Backend::new);
+    Server::new(tokio::io::stdin(), tokio::io::stdout(), socket)
+        .serve(service)
+        .await;
+}
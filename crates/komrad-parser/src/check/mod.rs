@@ -0,0 +1,421 @@
+//! Static checks over handler patterns, run by `komrad check`.
+//!
+//! An agent tries its handlers in declaration order and runs the first one
+//! whose pattern binds, so a broad pattern placed early silently takes
+//! every message meant for the ones after it. These checks find patterns
+//! like that without running anything. They only warn when they are sure:
+//! a pattern the checker can't reason about is assumed to be fine.
+//!
+//! The `types` checks are optional, and look at what the handlers do.
+
+mod types;
+
+pub use types::check_types;
+
+use crate::cst::{TokenKind, tokenize};
+use crate::module_builder::ModuleBuilder;
+use komrad_ast::prelude::{
+    Block, CallExpr, ComparisonOp, Expr, Handler, Pattern, SourceLocation, Statement, ToKomrad,
+    TypeExpr,
+};
+use miette::{Diagnostic, LabeledSpan, NamedSource};
+use std::ops::Range;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Every message the handler accepts is taken by an earlier one.
+    Shadowed,
+    /// Handlers for the same word expect one of its literal words at
+    /// different positions, so one of them is probably missing a hole.
+    ArityCollision,
+    /// A predicate hole whose comparison can't succeed for any value.
+    ImpossiblePredicate,
+    /// An agent definition that can't respond to any message.
+    NoHandlers,
+    /// A field whose default doesn't have the declared type.
+    TypeMismatch,
+    /// A type annotation that names neither a built-in type, an agent nor
+    /// a protocol.
+    UnknownType,
+    /// An operator applied to types it doesn't support.
+    UnsupportedOperation,
+    /// An agent missing a handler for a protocol it says it implements.
+    MissingHandler,
+}
+
+/// A handler or agent that is legal but almost certainly a mistake.
+#[derive(Debug, Clone, Error, Diagnostic)]
+#[error("{message}")]
+#[diagnostic(severity(Warning))]
+pub struct CheckWarning {
+    pub kind: WarningKind,
+    pub message: String,
+
+    #[source_code]
+    pub src: Option<Arc<NamedSource<String>>>,
+
+    #[label(collection)]
+    pub labels: Vec<LabeledSpan>,
+
+    #[help]
+    pub help: Option<String>,
+}
+
+impl CheckWarning {
+    /// The 1-based line of the first label, if the source is known.
+    pub fn line(&self) -> Option<usize> {
+        let src = self.src.as_ref()?.inner();
+        let offset = self.labels.first()?.offset().min(src.len());
+        Some(src[..offset].matches('\n').count() + 1)
+    }
+}
+
+/// Checks every agent in a parsed module, the module itself included.
+pub fn check_module(module: &ModuleBuilder) -> Vec<CheckWarning> {
+    let mut warnings = Vec::new();
+    check_block(&module.build_block(), &mut warnings);
+    warnings
+}
+
+/// A handler and where its pattern is in the source.
+struct Located<'a> {
+    handler: &'a Handler,
+    location: Option<&'a SourceLocation>,
+}
+
+impl Located<'_> {
+    fn pattern(&self) -> &Pattern {
+        self.handler.pattern()
+    }
+
+    fn src(&self) -> Option<Arc<NamedSource<String>>> {
+        self.location.map(|location| location.src().clone())
+    }
+
+    fn label(&self, text: impl Into<String>) -> Option<LabeledSpan> {
+        let range = pattern_range(self.location?)?;
+        Some(LabeledSpan::new(
+            Some(text.into()),
+            range.start,
+            range.end - range.start,
+        ))
+    }
+}
+
+/// Checks the handlers of one agent's body, then any agents defined in it.
+fn check_block(block: &Block, warnings: &mut Vec<CheckWarning>) {
+    let handlers: Vec<Located> = block
+        .statements()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, statement)| match statement {
+            Statement::Handler(handler) => Some(Located {
+                handler,
+                location: block.location(index),
+            }),
+            _ => None,
+        })
+        .collect();
+
+    for (index, handler) in handlers.iter().enumerate() {
+        if handler.pattern().is_unmatchable() {
+            check_predicates(handler, warnings);
+            continue;
+        }
+        check_shadowed(&handlers[..index], handler, warnings);
+        check_collisions(&handlers[..index], handler, warnings);
+    }
+
+    for (index, statement) in block.statements().iter().enumerate() {
+        match statement {
+            Statement::Handler(handler) => check_block(handler.block(), warnings),
+            Statement::Expr(Expr::Call(call)) => {
+                if let Some((name, body)) = agent_definition(call) {
+                    if !body.statements().iter().any(Statement::is_handler) {
+                        warnings.push(no_handlers(name, block.location(index)));
+                    }
+                    check_block(body, warnings);
+                }
+            }
+            Statement::Try(body, _, catch) => {
+                check_block(body, warnings);
+                check_block(catch, warnings);
+            }
+            Statement::While(_, body) => check_block(body, warnings),
+            _ => {}
+        }
+    }
+}
+
+fn check_predicates(handler: &Located, warnings: &mut Vec<CheckWarning>) {
+    for term in handler.pattern().terms() {
+        if let TypeExpr::Binary(name, op, value) = term
+            && term.is_unmatchable()
+        {
+            let help = match op {
+                ComparisonOp::Divisible => "`%%` needs a non-zero number",
+                _ => "only numbers, strings and booleans can be ordered",
+            };
+            warnings.push(CheckWarning {
+                kind: WarningKind::ImpossiblePredicate,
+                message: format!(
+                    "`{}` never matches: `{} {} {}` is never true",
+                    handler.pattern().to_komrad(),
+                    name,
+                    op,
+                    value.to_komrad()
+                ),
+                src: handler.src(),
+                labels: handler
+                    .label("this handler never runs")
+                    .into_iter()
+                    .collect(),
+                help: Some(help.to_string()),
+            });
+        }
+    }
+}
+
+fn check_shadowed(earlier: &[Located], handler: &Located, warnings: &mut Vec<CheckWarning>) {
+    let Some(shadow) = earlier.iter().find(|shadow| {
+        !shadow.pattern().is_unmatchable() && shadow.pattern().covers(handler.pattern())
+    }) else {
+        return;
+    };
+    warnings.push(CheckWarning {
+        kind: WarningKind::Shadowed,
+        message: format!(
+            "`{}` is unreachable: `{}` comes first and matches everything it does",
+            handler.pattern().to_komrad(),
+            shadow.pattern().to_komrad()
+        ),
+        src: handler.src(),
+        labels: handler
+            .label("this handler never runs")
+            .into_iter()
+            .chain(shadow.label("because this one always matches first"))
+            .collect(),
+        help: Some("handlers are tried in order; put the more specific one first".to_string()),
+    });
+}
+
+/// Warns when the same literal word is at a different position than in an
+/// earlier handler for the same command, e.g. `[http _response GET]` and
+/// `[http GET "about"]`. A message can't fit both shapes.
+fn check_collisions(earlier: &[Located], handler: &Located, warnings: &mut Vec<CheckWarning>) {
+    let Some(name) = command(handler.pattern()) else {
+        return;
+    };
+    for (position, word) in literal_words(handler.pattern()) {
+        let collision = earlier
+            .iter()
+            .filter(|other| command(other.pattern()) == Some(name))
+            .find_map(|other| {
+                literal_words(other.pattern())
+                    .find(|(_, other_word)| *other_word == word)
+                    .filter(|(other_position, _)| *other_position != position)
+                    .map(|(other_position, _)| (other, other_position))
+            });
+        let Some((other, other_position)) = collision else {
+            continue;
+        };
+        warnings.push(CheckWarning {
+            kind: WarningKind::ArityCollision,
+            message: format!(
+                "`{}` has `{}` as term {}, but `{}` has it as term {}",
+                handler.pattern().to_komrad(),
+                word,
+                position + 1,
+                other.pattern().to_komrad(),
+                other_position + 1
+            ),
+            src: handler.src(),
+            labels: handler
+                .label(format!("`{}` is term {} here", word, position + 1))
+                .into_iter()
+                .chain(other.label(format!("but term {} here", other_position + 1)))
+                .collect(),
+            help: Some(format!(
+                "is a hole missing from one of the `{}` handlers?",
+                name
+            )),
+        });
+        return;
+    }
+}
+
+fn no_handlers(name: &str, location: Option<&SourceLocation>) -> CheckWarning {
+    let label = location
+        .and_then(|location| agent_name_range(location, name))
+        .map(|range| LabeledSpan::new(Some("defined here".into()), range.start, range.len()));
+    CheckWarning {
+        kind: WarningKind::NoHandlers,
+        message: format!("agent `{}` has no handlers", name),
+        src: location.map(|location| location.src().clone()),
+        labels: label.into_iter().collect(),
+        help: Some(format!(
+            "every message sent to a `{}` will be ignored",
+            name
+        )),
+    }
+}
+
+/// `agent Name { ... }`, or `agent Name implements Protocol... { ... }`.
+fn agent_definition(call: &CallExpr) -> Option<(&str, &Block)> {
+    if call.target() != &Expr::Variable("agent".to_string()) {
+        return None;
+    }
+    match call.args().as_slice() {
+        [name, .., block] => match (name.as_ref(), block.as_ref()) {
+            (Expr::Variable(name), Expr::Block(block)) => Some((name, block)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// The literal word a handler is called with, e.g. `divide`.
+fn command(pattern: &Pattern) -> Option<&str> {
+    match pattern.terms().first()? {
+        TypeExpr::Word(word) => Some(word),
+        _ => None,
+    }
+}
+
+/// The literal words after the command, with their positions.
+fn literal_words(pattern: &Pattern) -> impl Iterator<Item = (usize, &str)> {
+    pattern
+        .terms()
+        .iter()
+        .enumerate()
+        .skip(1)
+        .filter_map(|(position, term)| match term {
+            TypeExpr::Word(word) => Some((position, word.as_str())),
+            _ => None,
+        })
+}
+
+/// The `[...]` at the start of a handler statement.
+fn pattern_range(location: &SourceLocation) -> Option<Range<usize>> {
+    let start = location.span().offset();
+    let text = location
+        .src()
+        .inner()
+        .get(start..start + location.span().len())?;
+    let tokens = tokenize(text);
+    let open = tokens.first()?.offset;
+    let mut depth = 0;
+    for token in tokens {
+        if token.is_punct("[") {
+            depth += 1;
+        } else if token.is_punct("]") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(start + open..start + token.range().end);
+            }
+        } else if depth == 0 {
+            return None;
+        }
+    }
+    None
+}
+
+/// The name in an `agent Name { ... }` statement.
+fn agent_name_range(location: &SourceLocation, name: &str) -> Option<Range<usize>> {
+    let start = location.span().offset();
+    let text = location
+        .src()
+        .inner()
+        .get(start..start + location.span().len())?;
+    let tokens = tokenize(text);
+    let name = tokens.windows(2).find(|pair| {
+        pair[0].text == "agent" && pair[1].kind == TokenKind::Ident && pair[1].text == name
+    })?;
+    let range = name[1].range();
+    Some(start + range.start..start + range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_source;
+
+    fn check(source: &str) -> Vec<(WarningKind, Option<usize>)> {
+        let module = parse_source("test.kom", source).unwrap();
+        check_module(&module)
+            .iter()
+            .map(|warning| (warning.kind, warning.line()))
+            .collect()
+    }
+
+    #[test]
+    fn test_fizzbuzz_in_order() {
+        let source = std::fs::read_to_string(
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples/fizzbuzz.kom"),
+        )
+        .unwrap();
+        assert_eq!(check(&source), vec![]);
+    }
+
+    #[test]
+    fn test_shadowed_handlers() {
+        let source = "[fizzbuzz _(x %% 1)] {\n\tIo println x\n}\n\n[fizzbuzz _(x %% 15)] {\n\tIo println \"FizzBuzz\"\n}\n\n[greet _name] {}\n[greet \"Bob\"] {}\n[greet _(n:Number)] {}\n[big _(n > 10)] {}\n[big _(n >= 11)] {}\n[big _(n > 9)] {}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::Shadowed, Some(5)),
+                (WarningKind::Shadowed, Some(10)),
+                (WarningKind::Shadowed, Some(11)),
+                (WarningKind::Shadowed, Some(13)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shadowed_labels() {
+        let source = "agent Calc {\n\t[add _a _b] {}\n\t[add _(a:Number) _(b:Number)] {}\n}\n";
+        let module = parse_source("test.kom", source).unwrap();
+        let warnings = check_module(&module);
+        assert_eq!(warnings.len(), 1);
+        let spans: Vec<&str> = warnings[0]
+            .labels
+            .iter()
+            .map(|label| &source[label.offset()..label.offset() + label.len()])
+            .collect();
+        assert_eq!(spans, vec!["[add _(a:Number) _(b:Number)]", "[add _a _b]"]);
+    }
+
+    #[test]
+    fn test_arity_collisions() {
+        let source = "agent Server {\n\t[http _response GET] {}\n\t[http _response POST] {}\n\t[http GET \"about\"] {}\n}\n";
+        assert_eq!(check(source), vec![(WarningKind::ArityCollision, Some(4))]);
+    }
+
+    #[test]
+    fn test_impossible_predicates() {
+        let source =
+            "[a _(x %% 0)] {}\n[b _(x %% \"two\")] {}\n[c _(x < foo)] {}\n[d _(x < 3)] {}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::ImpossiblePredicate, Some(1)),
+                (WarningKind::ImpossiblePredicate, Some(2)),
+                (WarningKind::ImpossiblePredicate, Some(3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_agents_without_handlers() {
+        let source = "agent Empty {\n\tx = 1\n}\n\n[main] {\n\tagent Inner {}\n}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::NoHandlers, Some(1)),
+                (WarningKind::NoHandlers, Some(6)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,557 @@
+//! An optional static type checker, run by `komrad check --types`.
+//!
+//! Types are only enforced at runtime, when a field is initialised or a
+//! hole binds. This infers what it can from literals, annotations and the
+//! operators, and reports the mistakes that would fail at runtime. Anything
+//! it can't infer, such as the reply to a message, is left unchecked.
+
+use super::{CheckWarning, WarningKind};
+use crate::module_builder::ModuleBuilder;
+use komrad_ast::prelude::{
+    BinaryExpr, BinaryOp, Block, CallExpr, ComparisonOp, Expr, Handler, Pattern, SourceLocation,
+    Statement, ToKomrad, TypeExpr, Typed, UnaryOp, ValueType,
+};
+use miette::LabeledSpan;
+use std::collections::HashMap;
+
+/// Checks field defaults, type annotations and operators in a parsed module.
+pub fn check_types(module: &ModuleBuilder) -> Vec<CheckWarning> {
+    let block = module.build_block();
+    let mut checker = TypeChecker {
+        agents: HashMap::new(),
+        // Built in, for the list expander `...`
+        protocols: HashMap::from([("List".to_string(), Pattern::list_protocol())]),
+        warnings: Vec::new(),
+    };
+    checker.declarations(&block);
+    checker.block(&block, &mut HashMap::new());
+    checker.warnings
+}
+
+/// The protocols named by `agent Name implements Protocol... { ... }`.
+fn implemented_protocols(call: &CallExpr) -> Vec<String> {
+    match call.args().as_slice() {
+        [_, keyword, names @ .., _]
+            if keyword.as_ref() == &Expr::Variable("implements".to_string()) =>
+        {
+            names
+                .iter()
+                .filter_map(|name| match name.as_ref() {
+                    Expr::Variable(name) => Some(name.clone()),
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// `protocol Name { ... }`, with the patterns of its handlers.
+fn protocol_definition(call: &CallExpr) -> Option<(&str, Vec<Pattern>)> {
+    if call.target() != &Expr::Variable("protocol".to_string()) {
+        return None;
+    }
+    match call.args().as_slice() {
+        [name, block] => match (name.as_ref(), block.as_ref()) {
+            (Expr::Variable(name), Expr::Block(block)) => {
+                Some((name, handlers(block).map(|h| h.pattern().clone()).collect()))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn handlers(block: &Block) -> impl Iterator<Item = &Handler> {
+    block
+        .statements()
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Handler(handler) => Some(handler.as_ref()),
+            _ => None,
+        })
+}
+
+/// The types of the variables in scope, where they are known.
+type Env = HashMap<String, ValueType>;
+
+struct TypeChecker {
+    /// Every agent defined in the module, with the protocols it implements.
+    agents: HashMap<String, Vec<String>>,
+    protocols: HashMap<String, Vec<Pattern>>,
+    warnings: Vec<CheckWarning>,
+}
+
+impl TypeChecker {
+    /// Collects every agent and protocol defined in the block, nested ones
+    /// included.
+    fn declarations(&mut self, block: &Block) {
+        for statement in block.statements() {
+            match statement {
+                Statement::Expr(Expr::Call(call)) => {
+                    if let Some((name, body)) = super::agent_definition(call) {
+                        self.agents
+                            .insert(name.to_string(), implemented_protocols(call));
+                        self.declarations(body);
+                    } else if let Some((name, patterns)) = protocol_definition(call) {
+                        self.protocols.insert(name.to_string(), patterns);
+                    }
+                }
+                Statement::Handler(handler) => self.declarations(handler.block()),
+                Statement::Try(body, _, catch) => {
+                    self.declarations(body);
+                    self.declarations(catch);
+                }
+                Statement::While(_, body) => self.declarations(body),
+                _ => {}
+            }
+        }
+    }
+
+    /// Whether a value of type `actual` is accepted where `declared` is
+    /// expected, counting the protocols an agent implements.
+    fn conforms(&self, actual: &ValueType, declared: &ValueType) -> bool {
+        if actual.is_subtype_of(declared) {
+            return true;
+        }
+        let protocol = match declared {
+            ValueType::User(name) => name.as_str(),
+            ValueType::List => "List",
+            _ => return false,
+        };
+        match actual {
+            ValueType::User(agent) => self
+                .agents
+                .get(agent)
+                .is_some_and(|protocols| protocols.iter().any(|p| p == protocol)),
+            _ => false,
+        }
+    }
+
+    /// Checks that an agent handles every pattern of the protocols it
+    /// declares, as the registry will when it is defined.
+    fn implements(
+        &mut self,
+        name: &str,
+        call: &CallExpr,
+        body: &Block,
+        location: Option<&SourceLocation>,
+    ) {
+        for protocol in implemented_protocols(call) {
+            let Some(patterns) = self.protocols.get(&protocol) else {
+                self.warn(
+                    WarningKind::UnknownType,
+                    format!("unknown protocol `{}`", protocol),
+                    location,
+                    format!("no protocol named `{}` is defined", protocol),
+                );
+                continue;
+            };
+            let missing: Vec<String> = patterns
+                .iter()
+                .filter(|pattern| !pattern.is_handled_by(handlers(body)))
+                .map(|pattern| format!("`{}`", pattern.to_komrad()))
+                .collect();
+            if !missing.is_empty() {
+                self.warn(
+                    WarningKind::MissingHandler,
+                    format!("agent `{}` doesn't implement `{}`", name, protocol),
+                    location,
+                    format!("no handler for {}", missing.join(", ")),
+                );
+            }
+        }
+    }
+
+    fn block(&mut self, block: &Block, env: &mut Env) {
+        for (index, statement) in block.statements().iter().enumerate() {
+            self.statement(statement, block.location(index), env);
+        }
+    }
+
+    fn statement(
+        &mut self,
+        statement: &Statement,
+        location: Option<&SourceLocation>,
+        env: &mut Env,
+    ) {
+        match statement {
+            Statement::Expr(expr) => {
+                if let Expr::Call(call) = expr
+                    && let Some((name, body)) = super::agent_definition(call)
+                {
+                    self.implements(name, call, body, location);
+                    // An agent's body runs in a scope of its own
+                    self.block(body, &mut Env::new());
+                } else {
+                    self.expr(expr, location, env);
+                }
+            }
+            Statement::Assignment(name, expr) => match self.expr(expr, location, env) {
+                Some(typ) => {
+                    env.insert(name.clone(), typ);
+                }
+                None => {
+                    env.remove(name);
+                }
+            },
+            Statement::Field(name, type_expr, default) => {
+                let declared = self.type_expr(type_expr, location);
+                let actual = default
+                    .as_ref()
+                    .and_then(|default| self.expr(default, location, env));
+                if let (Some(declared), Some(actual)) = (&declared, actual)
+                    && !self.conforms(&actual, declared)
+                {
+                    self.warn(
+                        WarningKind::TypeMismatch,
+                        format!(
+                            "field `{}` is declared as {} but its default is {}",
+                            name, declared, actual
+                        ),
+                        location,
+                        format!("this is {}", actual),
+                    );
+                }
+                match declared {
+                    Some(declared) => env.insert(name.clone(), declared),
+                    None => env.remove(name),
+                };
+            }
+            Statement::Handler(handler) => {
+                let mut env = env.clone();
+                for term in handler.pattern().terms() {
+                    if let Some((name, typ)) = self.hole(term, location) {
+                        match typ {
+                            Some(typ) => env.insert(name.to_string(), typ),
+                            None => env.remove(name),
+                        };
+                    }
+                }
+                self.block(handler.block(), &mut env);
+            }
+            Statement::Try(body, name, catch) => {
+                self.block(body, &mut env.clone());
+                let mut env = env.clone();
+                env.insert(name.clone(), ValueType::Error);
+                self.block(catch, &mut env);
+            }
+            Statement::While(condition, body) => {
+                // The body's assignments are seen by the next condition
+                let mut env = env.clone();
+                self.block(condition, &mut env);
+                self.block(body, &mut env);
+            }
+            Statement::NoOp | Statement::Comment(_) | Statement::Expander(_) => {}
+        }
+    }
+
+    /// The name a pattern term binds and, if known, the type it binds.
+    fn hole<'a>(
+        &mut self,
+        term: &'a TypeExpr,
+        location: Option<&SourceLocation>,
+    ) -> Option<(&'a str, Option<ValueType>)> {
+        match term {
+            TypeExpr::Hole(name) => Some((name, None)),
+            TypeExpr::BlockHole(name) => Some((name, Some(ValueType::Block))),
+            TypeExpr::TypeHole(name, _) => Some((name, self.type_expr(term, location))),
+            TypeExpr::Binary(name, op, value) => {
+                let typ = match op {
+                    ComparisonOp::Ne => None,
+                    ComparisonOp::Divisible => Some(ValueType::Number),
+                    _ => Some(value.get_type()),
+                };
+                Some((name, typ))
+            }
+            TypeExpr::HasType(_) => {
+                self.type_expr(term, location);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// The declared type, after checking that user types name an agent or
+    /// a protocol.
+    fn type_expr(
+        &mut self,
+        type_expr: &TypeExpr,
+        location: Option<&SourceLocation>,
+    ) -> Option<ValueType> {
+        let (TypeExpr::HasType(typ) | TypeExpr::TypeHole(_, typ)) = type_expr else {
+            return None;
+        };
+        if let ValueType::User(name) = typ
+            && !self.agents.contains_key(name)
+            && !self.protocols.contains_key(name)
+        {
+            self.warn(
+                WarningKind::UnknownType,
+                format!("unknown type `{}`", name),
+                location,
+                format!("no agent or protocol named `{}` is defined", name),
+            );
+            return None;
+        }
+        Some(typ.clone())
+    }
+
+    /// Infers the type of an expression, reporting operators that would
+    /// fail on the way.
+    fn expr(
+        &mut self,
+        expr: &Expr,
+        location: Option<&SourceLocation>,
+        env: &Env,
+    ) -> Option<ValueType> {
+        match expr {
+            Expr::Value(value) => Some(value.get_type()),
+            Expr::List(items) => {
+                for item in items {
+                    self.expr(item, location, env);
+                }
+                Some(ValueType::List)
+            }
+            Expr::Map(entries) => {
+                for (key, value) in entries {
+                    self.expr(key, location, env);
+                    self.expr(value, location, env);
+                }
+                Some(ValueType::Map)
+            }
+            Expr::Variable(name) => env.get(name).cloned(),
+            Expr::Binary(binary) => self.binary(binary, location, env),
+            Expr::Unary(unary) => {
+                let operand = self.expr(unary.expr(), location, env)?;
+                let result = match (unary.operator(), &operand) {
+                    (UnaryOp::Not, ValueType::Boolean) => ValueType::Boolean,
+                    (UnaryOp::Neg | UnaryOp::Inc | UnaryOp::Dec, ValueType::Number) => {
+                        ValueType::Number
+                    }
+                    (_, ValueType::Error) => return None,
+                    (op, _) => {
+                        self.warn(
+                            WarningKind::UnsupportedOperation,
+                            format!("`{}` can't be applied to {}", op, operand),
+                            location,
+                            format!("`{}` is {}", unary.expr().to_komrad(), operand),
+                        );
+                        return None;
+                    }
+                };
+                Some(result)
+            }
+            Expr::Call(call) => {
+                self.expr(call.target(), location, env);
+                for arg in call.args() {
+                    self.expr(arg, location, env);
+                }
+                // `spawn Bob` is a `Bob`
+                match (call.target(), call.args().first().map(AsRef::as_ref)) {
+                    (Expr::Variable(target), Some(Expr::Variable(name)))
+                        if target == "spawn" && self.agents.contains_key(name) =>
+                    {
+                        Some(ValueType::User(name.clone()))
+                    }
+                    _ => None,
+                }
+            }
+            Expr::Block(block) => {
+                self.block(block, &mut env.clone());
+                Some(ValueType::Block)
+            }
+        }
+    }
+
+    fn binary(
+        &mut self,
+        binary: &BinaryExpr,
+        location: Option<&SourceLocation>,
+        env: &Env,
+    ) -> Option<ValueType> {
+        let op = binary.operator();
+        if *op == BinaryOp::Access {
+            // The right side is a member name, not a variable
+            self.expr(binary.left(), location, env);
+            return None;
+        }
+        let left = self.expr(binary.left(), location, env);
+        let right = self.expr(binary.right(), location, env);
+        if matches!(op, BinaryOp::Eq | BinaryOp::Ne) {
+            return Some(ValueType::Boolean);
+        }
+        let (left, right) = (left?, right?);
+        match binary_result(op, &left, &right) {
+            Some(result) => Some(result),
+            None => {
+                self.warn(
+                    WarningKind::UnsupportedOperation,
+                    format!("`{} {} {}` is not supported", left, op, right),
+                    location,
+                    format!(
+                        "`{}` fails at runtime",
+                        Expr::Binary(binary.clone()).to_komrad()
+                    ),
+                );
+                None
+            }
+        }
+    }
+
+    fn warn(
+        &mut self,
+        kind: WarningKind,
+        message: String,
+        location: Option<&SourceLocation>,
+        label: String,
+    ) {
+        self.warnings.push(CheckWarning {
+            kind,
+            message,
+            src: location.map(|location| location.src().clone()),
+            labels: location
+                .and_then(|location| statement_label(location, label))
+                .into_iter()
+                .collect(),
+            help: None,
+        });
+    }
+}
+
+/// The type `left op right` evaluates to, mirroring `BinaryExpr::execute`,
+/// or `None` if it would fail.
+fn binary_result(op: &BinaryOp, left: &ValueType, right: &ValueType) -> Option<ValueType> {
+    use ValueType::*;
+    let result = match (op, left, right) {
+        (BinaryOp::Add, Number, Number) => Number,
+        (BinaryOp::Add, String, String | Channel | Empty | Number) => String,
+        (BinaryOp::Add, EmbeddedBlock, String) | (BinaryOp::Add, String, EmbeddedBlock) => {
+            EmbeddedBlock
+        }
+        (BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod, Number, Number) => Number,
+        (BinaryOp::And | BinaryOp::Or, Boolean, Boolean) => Boolean,
+        (BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge, left, right)
+            if left == right && matches!(left, Number | String | Boolean) =>
+        {
+            Boolean
+        }
+        (BinaryOp::Divisible, Number, Number) => Boolean,
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// The first line of a statement, where a problem inside it is reported.
+fn statement_label(location: &SourceLocation, label: String) -> Option<LabeledSpan> {
+    let start = location.span().offset();
+    let text = location
+        .src()
+        .inner()
+        .get(start..start + location.span().len())?;
+    let line = text.lines().next()?;
+    let indent = line.len() - line.trim_start().len();
+    Some(LabeledSpan::new(
+        Some(label),
+        start + indent,
+        line.trim().len(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_source;
+
+    fn check(source: &str) -> Vec<(WarningKind, Option<usize>)> {
+        let module = parse_source("test.kom", source).unwrap();
+        check_types(&module)
+            .iter()
+            .map(|warning| (warning.kind, warning.line()))
+            .collect()
+    }
+
+    #[test]
+    fn test_field_defaults() {
+        let source = "agent User {\n\tname: String = \"Unknown\"\n\tage: Number = \"old\"\n\tfriend: User\n\tboss: Manager\n}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::TypeMismatch, Some(3)),
+                (WarningKind::UnknownType, Some(5)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spawned_agents() {
+        let source = "agent Bob {}\nagent Alice {\n\tpeer: Bob = spawn Bob\n\tname: String = spawn Bob\n\tchannel: Channel = spawn Bob\n\tother: Bob = spawn Alice\n}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::TypeMismatch, Some(4)),
+                (WarningKind::TypeMismatch, Some(6)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_protocols() {
+        let source = "protocol Stack {\n\t[push _x] {}\n\t[pop] {}\n}\nagent Pile implements Stack {\n\t[push _x] {}\n\t[pop] {}\n}\nagent Heap {}\nagent Owner {\n\tstack: Stack = spawn Pile\n\tother: Stack = spawn Heap\n\titems: List = [1 2]\n\tqueue: Queue\n}\nagent Half implements Stack {\n\t[push _(x:Number)] {}\n\t[pop] {}\n}\nagent Line implements Queue {}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::TypeMismatch, Some(12)),
+                (WarningKind::UnknownType, Some(14)),
+                (WarningKind::MissingHandler, Some(16)),
+                (WarningKind::UnknownType, Some(20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_binary_operations() {
+        let source = "[main] {\n\tgreeting = \"hi \" + 3\n\tflag = true\n\tIo println greeting + flag\n\tcount = 3 * 2\n\tcount - \"one\"\n\tdone = !count\n}\n";
+        let module = parse_source("test.kom", source).unwrap();
+        let warnings = check_types(&module);
+        let kinds: Vec<(WarningKind, Option<usize>)> = warnings
+            .iter()
+            .map(|warning| (warning.kind, warning.line()))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (WarningKind::UnsupportedOperation, Some(4)),
+                (WarningKind::UnsupportedOperation, Some(6)),
+                (WarningKind::UnsupportedOperation, Some(7)),
+            ]
+        );
+        assert_eq!(warnings[0].message, "`String + Boolean` is not supported");
+    }
+
+    #[test]
+    fn test_holes_bind_types() {
+        let source = "[add _(a:Number) _(b:String) _c] {\n\ta + b\n\tb + a\n\tc + a\n}\n\n[check _(n %% 2) _{then}] {\n\tthen + n\n}\n\n[find _(p:Person)] {}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::UnsupportedOperation, Some(2)),
+                (WarningKind::UnsupportedOperation, Some(8)),
+                (WarningKind::UnknownType, Some(11)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scopes() {
+        // Assignments in a block or another handler don't leak out
+        let source = "x = \"text\"\n[main] {\n\tx = 1\n\tx + 1\n\ttry {\n\t\tx = true\n\t} catch _err {\n\t\terr + 1\n\t}\n\tx + 2\n}\n[other] {\n\tx - 1\n}\n";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningKind::UnsupportedOperation, Some(8)),
+                (WarningKind::UnsupportedOperation, Some(13)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use super::{SyntaxTree, Token, TokenKind, Trivia, TriviaKind, tokenize};
+
+/// Operators that always have a space on each side.
+const SPACED: &[&str] = &["=", "==", "!=", "<", "<=", ">", ">=", "&&", "||", "%%"];
+
+const OPENERS: &[&str] = &["{", "[", "(", "{:"];
+const CLOSERS: &[&str] = &["}", "]", ")", ":}"];
+
+impl SyntaxTree {
+    /// The source laid out the way `komrad fmt` writes it.
+    ///
+    /// Only whitespace changes, so every comment and literal is kept as
+    /// written. Lines are indented with a tab per open bracket, and at most
+    /// one blank line separates statements. Tokens written together stay
+    /// together and tokens written apart get one space, since `f -x` and
+    /// `f - x` are different calls; only the spacing around `,`, `:`,
+    /// brackets and comparison operators is normalized.
+    pub fn format(&self) -> String {
+        let mut printer = Printer::default();
+        for token in self.root.tokens() {
+            printer.token(token);
+        }
+        if printer.line_open {
+            printer.out.push('\n');
+        }
+        printer.out
+    }
+}
+
+fn is_punct(token: &Token, puncts: &[&str]) -> bool {
+    token.kind == TokenKind::Punct && puncts.contains(&token.text.as_str())
+}
+
+#[derive(Default)]
+struct Printer<'a> {
+    out: String,
+    /// The brackets open at this point, innermost last.
+    open: Vec<&'a str>,
+    /// The last token on the current line.
+    previous: Option<&'a Token>,
+    line_open: bool,
+    /// Whether the source had whitespace since the last token.
+    gap: bool,
+    /// Whether the source had a blank line since the last line.
+    blank: bool,
+    /// Whether nothing has been written since an opening bracket.
+    after_opener: bool,
+}
+
+impl<'a> Printer<'a> {
+    fn token(&mut self, token: &'a Token) {
+        for trivia in &token.leading {
+            self.trivia(trivia);
+        }
+        if token.kind != TokenKind::Eof {
+            let closes = is_punct(token, CLOSERS);
+            if closes {
+                self.open.pop();
+            }
+            if !self.line_open {
+                self.start_line(closes);
+            } else if self
+                .previous
+                .is_some_and(|previous| self.space(previous, token))
+            {
+                self.out.push(' ');
+            }
+            self.out.push_str(&token.text);
+            self.previous = Some(token);
+            self.gap = false;
+            self.after_opener = is_punct(token, OPENERS);
+            if self.after_opener {
+                self.open.push(&token.text);
+            }
+        }
+        for trivia in &token.trailing {
+            self.trivia(trivia);
+        }
+    }
+
+    fn trivia(&mut self, trivia: &Trivia) {
+        match trivia.kind {
+            TriviaKind::Whitespace => self.gap = true,
+            TriviaKind::Newline if self.line_open => {
+                self.out.push('\n');
+                self.line_open = false;
+                self.previous = None;
+            }
+            // Leading blank lines are dropped, and the rest become one
+            TriviaKind::Newline => self.blank = !self.out.is_empty(),
+            TriviaKind::Comment => {
+                if self.line_open {
+                    self.out.push(' ');
+                } else {
+                    self.start_line(false);
+                }
+                self.out.push_str(trivia.text.trim_end());
+                self.after_opener = false;
+            }
+        }
+    }
+
+    /// Indents a new line, after a blank line if the source had one
+    /// between statements.
+    fn start_line(&mut self, closes: bool) {
+        if self.blank && !self.after_opener && !closes {
+            self.out.push('\n');
+        }
+        self.blank = false;
+        self.out.extend(std::iter::repeat_n('\t', self.open.len()));
+        self.line_open = true;
+    }
+
+    /// Whether to put a space between two tokens on the same line.
+    fn space(&self, previous: &Token, token: &Token) -> bool {
+        let in_group = self.open.last() == Some(&"(");
+        let space = if is_punct(token, &[",", ":"]) {
+            false
+        } else if is_punct(previous, &[","]) {
+            true
+        } else if is_punct(previous, &[":"]) {
+            // `name: String`, but `_(name:String)`
+            !in_group
+        } else if is_punct(previous, SPACED) || is_punct(token, SPACED) {
+            true
+        } else if is_punct(previous, &["[", "("])
+            || is_punct(token, &["]", ")"])
+            || (is_punct(previous, &["{"]) && is_punct(token, &["}"]))
+        {
+            false
+        } else {
+            self.gap
+        };
+        // Never join two tokens into one, as `{` and `:}` would be
+        space || (self.gap && tokenize(&format!("{}{}", previous.text, token.text)).len() != 3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::SyntaxTree;
+
+    fn format(source: &str) -> String {
+        SyntaxTree::parse(source).format()
+    }
+
+    #[test]
+    fn test_layout() {
+        let source = "\n\nagent  Alice {\n\n  name:String=\"Alice\"\n\n\n  [greet   _(n : Number) ] {\n    Io println 'hi ' + name\n\n  }\n}\n\n";
+        assert_eq!(
+            format(source),
+            "agent Alice {\n\tname: String = \"Alice\"\n\n\t[greet _(n:Number)] {\n\t\tIo println 'hi ' + name\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_comments() {
+        let source = "// A greeter\nagent Greeter {   // the agent\n[greet // who to greet\n_name] {\n  Io println -x   // say it\n\n      // done\n  }\n}";
+        assert_eq!(
+            format(source),
+            "// A greeter\nagent Greeter { // the agent\n\t[greet // who to greet\n\t\t_name] {\n\t\tIo println -x // say it\n\n\t\t// done\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_tokens_stay_apart_or_together() {
+        assert_eq!(format("f  a -b  a-b { }"), "f a -b a-b {}\n");
+        assert_eq!(format("[when _{then}]"), "[when _{then}]\n");
+        assert_eq!(
+            format("x = \"\"\"two\n  lines\"\"\""),
+            "x = \"\"\"two\n  lines\"\"\"\n"
+        );
+    }
+}
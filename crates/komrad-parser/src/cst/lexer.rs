@@ -0,0 +1,310 @@
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// `foo`, `read-all`, `implements?`, `_name` or a bare `_`.
+    Ident,
+    /// `42`, `4.2`, `1e9`. Signs are separate `Punct` tokens.
+    Number,
+    /// A single, double or triple quoted string, quotes included.
+    String,
+    /// A fenced ```` ``` ```` block, fences included.
+    Embedded,
+    /// Brackets and operators, e.g. `{`, `==`, `.`.
+    Punct,
+    /// A character the language has no use for, or an unterminated string.
+    Error,
+    /// Carries the trivia at the end of the source.
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    /// A `//` comment, without its newline.
+    Comment,
+}
+
+/// Text that doesn't affect the meaning of the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub offset: usize,
+    pub text: String,
+}
+
+impl Trivia {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.text.len()
+    }
+}
+
+/// A token and the trivia around it.
+///
+/// Trailing trivia runs up to and including the end of the token's line;
+/// everything after that leads the next token. So a comment after code
+/// trails the code, while a comment on a line of its own leads whatever
+/// follows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub offset: usize,
+    pub text: String,
+    pub leading: Vec<Trivia>,
+    pub trailing: Vec<Trivia>,
+}
+
+impl Token {
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.text.len()
+    }
+
+    pub fn is_punct(&self, punct: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == punct
+    }
+
+    /// Whether the token is the last one on its line.
+    pub fn ends_line(&self) -> bool {
+        self.trailing.iter().any(|t| t.kind == TriviaKind::Newline)
+    }
+
+    /// The token's text with its trivia, exactly as in the source.
+    pub fn full_text(&self) -> String {
+        let mut text = String::new();
+        for trivia in &self.leading {
+            text.push_str(&trivia.text);
+        }
+        text.push_str(&self.text);
+        for trivia in &self.trailing {
+            text.push_str(&trivia.text);
+        }
+        text
+    }
+}
+
+/// Operators, longest first so that `==` isn't read as `=` `=`.
+const PUNCTS: &[&str] = &[
+    "++", "--", "==", "!=", "<=", ">=", "&&", "||", "%%", "{:", ":}", "{", "}", "[", "]", "(", ")",
+    "+", "-", "*", "/", "%", "<", ">", "=", "!", ".", ":", ",", "@",
+];
+
+/// Splits source into tokens. This never fails: anything unexpected
+/// becomes an `Error` token and is left for the parser to report.
+pub fn tokenize(source: &str) -> Vec<Token> {
+    let mut lexer = Lexer { source, pos: 0 };
+    let mut tokens = Vec::new();
+    let mut leading = lexer.trivia(false);
+    loop {
+        let offset = lexer.pos;
+        let Some(kind) = lexer.token() else {
+            tokens.push(Token {
+                kind: TokenKind::Eof,
+                offset,
+                text: String::new(),
+                leading,
+                trailing: Vec::new(),
+            });
+            return tokens;
+        };
+        let text = source[offset..lexer.pos].to_string();
+        let trailing = lexer.trivia(true);
+        tokens.push(Token {
+            kind,
+            offset,
+            text,
+            leading,
+            trailing,
+        });
+        leading = lexer.trivia(false);
+    }
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+}
+
+impl Lexer<'_> {
+    fn rest(&self) -> &str {
+        &self.source[self.pos..]
+    }
+
+    /// Reads trivia. Trailing trivia stops after the first newline.
+    fn trivia(&mut self, trailing: bool) -> Vec<Trivia> {
+        let mut trivia = Vec::new();
+        loop {
+            let rest = self.rest();
+            let (kind, len) = if rest.starts_with("//") {
+                (TriviaKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+            } else if rest.starts_with("\r\n") {
+                (TriviaKind::Newline, 2)
+            } else if rest.starts_with('\n') {
+                (TriviaKind::Newline, 1)
+            } else {
+                let len = whitespace_len(rest);
+                if len == 0 {
+                    return trivia;
+                }
+                (TriviaKind::Whitespace, len)
+            };
+            trivia.push(Trivia {
+                kind,
+                offset: self.pos,
+                text: rest[..len].to_string(),
+            });
+            self.pos += len;
+            if trailing && kind == TriviaKind::Newline {
+                return trivia;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Option<TokenKind> {
+        let rest = self.rest();
+        let c = rest.chars().next()?;
+        let (kind, len) = if rest.starts_with("```") {
+            delimited(rest, "```", "```", TokenKind::Embedded)
+        } else if rest.starts_with("\"\"\"") {
+            delimited(rest, "\"\"\"", "\"\"\"", TokenKind::String)
+        } else if c == '"' || c == '\'' {
+            let quote = &rest[..1];
+            delimited(rest, quote, quote, TokenKind::String)
+        } else if c.is_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+                .unwrap_or(rest.len());
+            let question = usize::from(rest[len..].starts_with('?'));
+            (TokenKind::Ident, len + question)
+        } else if c.is_ascii_digit() {
+            (TokenKind::Number, number_len(rest))
+        } else if let Some(punct) = PUNCTS.iter().find(|p| rest.starts_with(**p)) {
+            (TokenKind::Punct, punct.len())
+        } else {
+            (TokenKind::Error, c.len_utf8())
+        };
+        self.pos += len;
+        Some(kind)
+    }
+}
+
+/// Spaces and tabs, and any `\r` that isn't part of a `\r\n`.
+fn whitespace_len(rest: &str) -> usize {
+    let mut len = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            ' ' | '\t' => len = i + 1,
+            '\r' if !rest[i..].starts_with("\r\n") => len = i + 1,
+            _ => break,
+        }
+    }
+    len
+}
+
+/// A quoted token, honouring backslash escapes. Unterminated ones run to
+/// the end of the source as an `Error`.
+fn delimited(rest: &str, open: &str, close: &str, kind: TokenKind) -> (TokenKind, usize) {
+    let mut chars = rest[open.len()..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if rest[open.len() + i..].starts_with(close) {
+            return (kind, open.len() + i + close.len());
+        }
+    }
+    (TokenKind::Error, rest.len())
+}
+
+fn number_len(rest: &str) -> usize {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let mut len = digits(rest);
+    if rest[len..].starts_with('.') && rest[len + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+        len += 1 + digits(&rest[len + 1..]);
+    }
+    if rest[len..].starts_with(['e', 'E']) {
+        let sign = usize::from(rest[len + 1..].starts_with(['+', '-']));
+        let exponent = digits(&rest[len + 1 + sign..]);
+        if exponent > 0 {
+            len += 1 + sign + exponent;
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str) -> Vec<(TokenKind, String)> {
+        tokenize(source)
+            .into_iter()
+            .map(|token| (token.kind, token.text))
+            .collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(
+            kinds("x = a.b >= 4.5e3 \"s\""),
+            vec![
+                (TokenKind::Ident, "x".to_string()),
+                (TokenKind::Punct, "=".to_string()),
+                (TokenKind::Ident, "a".to_string()),
+                (TokenKind::Punct, ".".to_string()),
+                (TokenKind::Ident, "b".to_string()),
+                (TokenKind::Punct, ">=".to_string()),
+                (TokenKind::Number, "4.5e3".to_string()),
+                (TokenKind::String, "\"s\"".to_string()),
+                (TokenKind::Eof, String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments_in_strings_and_embedded_blocks() {
+        let source = "Io println \"http://x\" // real\n```css\n// not a comment\n```\n";
+        let comments: Vec<String> = tokenize(source)
+            .iter()
+            .flat_map(|token| token.leading.iter().chain(&token.trailing))
+            .filter(|trivia| trivia.kind == TriviaKind::Comment)
+            .map(|trivia| trivia.text.clone())
+            .collect();
+        assert_eq!(comments, vec!["// real"]);
+    }
+
+    #[test]
+    fn test_trivia_attachment() {
+        let tokens = tokenize("x // trailing\n\n// leading\ny");
+        assert_eq!(tokens[0].text, "x");
+        assert_eq!(
+            tokens[0]
+                .trailing
+                .iter()
+                .map(|t| t.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                TriviaKind::Whitespace,
+                TriviaKind::Comment,
+                TriviaKind::Newline
+            ]
+        );
+        assert_eq!(tokens[1].text, "y");
+        assert_eq!(
+            tokens[1].leading.iter().map(|t| t.kind).collect::<Vec<_>>(),
+            vec![
+                TriviaKind::Newline,
+                TriviaKind::Comment,
+                TriviaKind::Newline
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lossless() {
+        let source = "[a _(n:Number)] {\r\n\t'it\\'s' $ \"\"\"multi\nline\"\"\" \"open";
+        let tokens = tokenize(source);
+        let text: String = tokens.iter().map(Token::full_text).collect();
+        assert_eq!(text, source);
+        assert_eq!(tokens[tokens.len() - 2].kind, TokenKind::Error);
+    }
+}
@@ -0,0 +1,497 @@
+//! A lossless concrete syntax tree.
+//!
+//! Every byte of the source, comments and whitespace included, belongs to
+//! exactly one token or piece of trivia, so the tree prints back to the
+//! source it came from. The tree only knows about lines and brackets; the
+//! AST is derived from it by blanking out the comments the grammar doesn't
+//! allow and handing the result to the nom parser. `komrad fmt` prints from
+//! the tree rather than the AST, so it keeps every comment.
+//!
+//! Going through the nom parser is a stopgap: the AST isn't built from the
+//! tree's nodes yet, so the lexer here and the nom grammar still have to
+//! agree on where tokens, statements and brackets begin and end.
+
+mod format;
+mod lexer;
+
+pub use lexer::*;
+
+use crate::module_builder::ModuleBuilder;
+use crate::parse::statements::parse_statement;
+use crate::parser::{ParseErrors, parse_module};
+use crate::span::Span;
+use komrad_ast::prelude::ParserError;
+use miette::NamedSource;
+use nom::combinator::all_consuming;
+use nom::{Finish, Input, Parser};
+use std::iter::Peekable;
+use std::ops::Range;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Module,
+    /// One line of a block, or several if it contains blocks or brackets.
+    Statement,
+    /// `{ ... }`
+    Block,
+    /// `[ ... ]`, a handler pattern or a list.
+    List,
+    /// `( ... )`, a hole such as `_(n > 3)`.
+    Group,
+    /// `{: ... :}`, a map literal.
+    Map,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxNode {
+    kind: NodeKind,
+    children: Vec<SyntaxElement>,
+}
+
+impl SyntaxNode {
+    pub fn kind(&self) -> NodeKind {
+        self.kind
+    }
+
+    pub fn children(&self) -> &[SyntaxElement] {
+        &self.children
+    }
+
+    /// The child nodes, skipping tokens.
+    pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode> {
+        self.children.iter().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// Every token under this node, in source order.
+    pub fn tokens(&self) -> Vec<&Token> {
+        let mut tokens = Vec::new();
+        self.collect_tokens(&mut tokens);
+        tokens
+    }
+
+    fn collect_tokens<'a>(&'a self, tokens: &mut Vec<&'a Token>) {
+        for child in &self.children {
+            match child {
+                SyntaxElement::Node(node) => node.collect_tokens(tokens),
+                SyntaxElement::Token(token) => tokens.push(token),
+            }
+        }
+    }
+
+    pub fn first_token(&self) -> Option<&Token> {
+        self.children.first().and_then(|child| match child {
+            SyntaxElement::Node(node) => node.first_token(),
+            SyntaxElement::Token(token) => Some(token),
+        })
+    }
+
+    pub fn last_token(&self) -> Option<&Token> {
+        self.children.last().and_then(|child| match child {
+            SyntaxElement::Node(node) => node.last_token(),
+            SyntaxElement::Token(token) => Some(token),
+        })
+    }
+
+    /// The byte range from the first token to the last, without trivia.
+    pub fn range(&self) -> Range<usize> {
+        let start = self.first_token().map_or(0, |token| token.offset);
+        let end = self.last_token().map_or(start, |token| token.range().end);
+        start..end
+    }
+
+    /// The node's source text, trivia included.
+    pub fn text(&self) -> String {
+        self.tokens().into_iter().map(Token::full_text).collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxTree {
+    root: SyntaxNode,
+}
+
+impl SyntaxTree {
+    pub fn parse(source: &str) -> Self {
+        let mut builder = Builder {
+            tokens: tokenize(source).into_iter().peekable(),
+        };
+        let mut children = Vec::new();
+        builder.statements(&mut children);
+        while let Some(token) = builder.tokens.next() {
+            if token.kind == TokenKind::Eof {
+                children.push(SyntaxElement::Token(token));
+                break;
+            }
+            // A stray `}` at the top level is a statement of its own
+            children.push(SyntaxElement::Node(SyntaxNode {
+                kind: NodeKind::Statement,
+                children: vec![SyntaxElement::Token(token)],
+            }));
+            builder.statements(&mut children);
+        }
+        SyntaxTree {
+            root: SyntaxNode {
+                kind: NodeKind::Module,
+                children,
+            },
+        }
+    }
+
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// The source the tree was parsed from.
+    pub fn text(&self) -> String {
+        self.root.text()
+    }
+
+    /// Every comment, in source order.
+    pub fn comments(&self) -> Vec<&Trivia> {
+        self.root
+            .tokens()
+            .into_iter()
+            .flat_map(|token| token.leading.iter().chain(&token.trailing))
+            .filter(|trivia| trivia.kind == TriviaKind::Comment)
+            .collect()
+    }
+
+    /// The token at a byte offset, if the offset isn't in trivia.
+    pub fn token_at(&self, offset: usize) -> Option<&Token> {
+        self.root
+            .tokens()
+            .into_iter()
+            .find(|token| token.range().contains(&offset))
+    }
+
+    /// The source as the AST parser sees it: the same length, with the
+    /// comments it can't parse blanked out. Only comments on lines of their
+    /// own between statements are kept, as `Statement::Comment`. Inside
+    /// `[...]` and `(...)`, line breaks are blanked too, so patterns and
+    /// lists can span lines.
+    pub fn code(&self) -> String {
+        let mut code = String::new();
+        blank(&self.root, Context::Statements, false, &mut code);
+        code
+    }
+
+    /// Derives the module's AST. Errors point into the original source.
+    ///
+    /// The AST comes from running the nom parser over `code()`, not from
+    /// the tree's nodes; see the module docs.
+    ///
+    /// If the source doesn't parse, each statement is parsed on its own,
+    /// descending into the blocks of those that fail, so that every broken
+    /// statement is reported. Broken statements are then blanked out and
+    /// what remains is returned as a partial module alongside the errors.
+    pub fn to_module(&self, name: &str) -> Result<ModuleBuilder, ParseErrors> {
+        let src = Arc::new(NamedSource::new(name, self.text()));
+        let mut code = self.code();
+        let first_error = match parse_code(&code, &src) {
+            Ok(module) => return Ok(module),
+            Err(err) => err,
+        };
+
+        let mut errors = Vec::new();
+        recover(&self.root, &src, &mut code, &mut errors);
+        let module = parse_code(&code, &src).unwrap_or_else(|err| {
+            errors.push(err);
+            ModuleBuilder::new()
+        });
+        if errors.is_empty() {
+            errors.push(first_error);
+        }
+        errors.sort_by_key(|err| err.span.offset());
+        errors.dedup();
+        Err(ParseErrors { errors, module })
+    }
+}
+
+fn parse_code(code: &str, src: &Arc<NamedSource<String>>) -> Result<ModuleBuilder, ParserError> {
+    all_consuming(parse_module)
+        .parse(Span::new_extra(code, src.clone()))
+        .finish()
+        .map(|(_remaining, module)| module)
+}
+
+/// Parses the statement at `range` of `code` on its own.
+fn parse_statement_at(
+    code: &str,
+    src: &Arc<NamedSource<String>>,
+    range: Range<usize>,
+) -> Result<(), ParserError> {
+    let input = Span::new_extra(code, src.clone()).take_from(range.start);
+    let (remaining, _) = parse_statement(input).finish()?;
+    let end = remaining.location_offset();
+    if end < range.end && !code[end..range.end].trim().is_empty() {
+        let rest = remaining.take_from(code[end..].len() - code[end..].trim_start().len());
+        return Err(ParserError::unexpected(rest));
+    }
+    Ok(())
+}
+
+/// Reports the statements of a module or block that don't parse, and
+/// blanks them out of `code`.
+fn recover(
+    node: &SyntaxNode,
+    src: &Arc<NamedSource<String>>,
+    code: &mut String,
+    errors: &mut Vec<ParserError>,
+) {
+    for statement in node.nodes() {
+        let range = statement.range();
+        let Err(err) = parse_statement_at(code, src, range.clone()) else {
+            continue;
+        };
+
+        // Errors inside a statement's blocks are more precise than the
+        // statement's own, and once blanked the statement may parse.
+        let before = errors.len();
+        for block in blocks(statement) {
+            recover(block, src, code, errors);
+        }
+        let err = if errors.len() > before {
+            parse_statement_at(code, src, range.clone()).err()
+        } else {
+            Some(err)
+        };
+        if let Some(err) = err {
+            errors.push(err);
+            blank_out(code, range);
+        }
+    }
+}
+
+/// The blocks directly in a statement, including those inside brackets.
+fn blocks(node: &SyntaxNode) -> Vec<&SyntaxNode> {
+    let mut blocks = Vec::new();
+    for child in node.nodes() {
+        match child.kind {
+            NodeKind::Block => blocks.push(child),
+            _ => blocks.extend(self::blocks(child)),
+        }
+    }
+    blocks
+}
+
+fn blank_out(code: &mut String, range: Range<usize>) {
+    let blanked: String = code[range.clone()]
+        .chars()
+        .map(|c| match c {
+            '\n' => "\n".to_string(),
+            c => " ".repeat(c.len_utf8()),
+        })
+        .collect();
+    code.replace_range(range, &blanked);
+}
+
+struct Builder<I: Iterator<Item = Token>> {
+    tokens: Peekable<I>,
+}
+
+impl<I: Iterator<Item = Token>> Builder<I> {
+    fn peek_is(&mut self, f: impl FnOnce(&Token) -> bool) -> bool {
+        self.tokens.peek().is_some_and(f)
+    }
+
+    /// Statements up to a closing `}` or the end of the source.
+    fn statements(&mut self, children: &mut Vec<SyntaxElement>) {
+        while !self.peek_is(|t| t.kind == TokenKind::Eof || t.is_punct("}"))
+            && self.tokens.peek().is_some()
+        {
+            let statement = self.statement();
+            children.push(SyntaxElement::Node(statement));
+        }
+    }
+
+    fn statement(&mut self) -> SyntaxNode {
+        let mut children: Vec<SyntaxElement> = Vec::new();
+        loop {
+            let element = self.element();
+            let ends_line = match &element {
+                SyntaxElement::Node(node) => node.last_token().is_some_and(Token::ends_line),
+                SyntaxElement::Token(token) => token.ends_line(),
+            };
+            let after_block =
+                matches!(&element, SyntaxElement::Node(node) if node.kind == NodeKind::Block);
+            children.push(element);
+
+            if self.peek_is(|t| t.kind == TokenKind::Eof || t.is_punct("}")) {
+                break;
+            }
+            // `try { ... }` may have its `catch` on the next line
+            let catch = self.peek_is(|t| t.kind == TokenKind::Ident && t.text == "catch");
+            if ends_line && !(after_block && catch) {
+                break;
+            }
+        }
+        SyntaxNode {
+            kind: NodeKind::Statement,
+            children,
+        }
+    }
+
+    fn element(&mut self) -> SyntaxElement {
+        let token = self.tokens.next().expect("a token before the end");
+        let (kind, close) = match token.text.as_str() {
+            "{" if token.kind == TokenKind::Punct => (NodeKind::Block, "}"),
+            "[" if token.kind == TokenKind::Punct => (NodeKind::List, "]"),
+            "(" if token.kind == TokenKind::Punct => (NodeKind::Group, ")"),
+            "{:" if token.kind == TokenKind::Punct => (NodeKind::Map, ":}"),
+            _ => return SyntaxElement::Token(token),
+        };
+
+        let mut children = vec![SyntaxElement::Token(token)];
+        if kind == NodeKind::Block {
+            self.statements(&mut children);
+        } else {
+            // An unclosed bracket stops at the end of the enclosing block
+            while !self
+                .peek_is(|t| t.kind == TokenKind::Eof || t.is_punct("}") || t.is_punct(close))
+                && self.tokens.peek().is_some()
+            {
+                children.push(self.element());
+            }
+        }
+        if self.peek_is(|t| t.is_punct(close)) {
+            children.push(SyntaxElement::Token(self.tokens.next().unwrap()));
+        }
+        SyntaxElement::Node(SyntaxNode { kind, children })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Context {
+    /// Line breaks separate statements, and comments on their own line are
+    /// statements.
+    Statements,
+    /// Inside brackets, where line breaks and comments are only space.
+    Brackets,
+}
+
+/// Writes the node's text, blanking what the AST parser can't read.
+/// `first` is set when the node starts a statement.
+fn blank(node: &SyntaxNode, outer: Context, first: bool, code: &mut String) {
+    let close = match node.kind {
+        NodeKind::Block => Some("}"),
+        NodeKind::List => Some("]"),
+        NodeKind::Group => Some(")"),
+        NodeKind::Map => Some(":}"),
+        NodeKind::Module | NodeKind::Statement => None,
+    };
+    let inner = match node.kind {
+        NodeKind::List | NodeKind::Group | NodeKind::Map => Context::Brackets,
+        NodeKind::Module | NodeKind::Block => Context::Statements,
+        NodeKind::Statement => outer,
+    };
+
+    for (i, child) in node.children.iter().enumerate() {
+        let starts_statement = i == 0 && (first || node.kind == NodeKind::Statement);
+        let token = match child {
+            SyntaxElement::Node(child) => {
+                blank(child, inner, starts_statement, code);
+                continue;
+            }
+            SyntaxElement::Token(token) => token,
+        };
+        // The brackets themselves sit in the outer context
+        let opens = close.is_some() && i == 0;
+        let closes = i > 0 && close.is_some_and(|close| token.is_punct(close));
+        let before = if opens { outer } else { inner };
+        let after = if closes { outer } else { inner };
+
+        // Comments leading a statement, or the end of a block or module,
+        // sit on their own line between statements.
+        let keep_comments = before == Context::Statements
+            && (starts_statement || closes || token.kind == TokenKind::Eof);
+        for trivia in &token.leading {
+            push_trivia(trivia, before, keep_comments, code);
+        }
+        code.push_str(&token.text);
+        for trivia in &token.trailing {
+            push_trivia(trivia, after, false, code);
+        }
+    }
+}
+
+fn push_trivia(trivia: &Trivia, context: Context, keep_comments: bool, code: &mut String) {
+    let blank = match trivia.kind {
+        TriviaKind::Whitespace => false,
+        TriviaKind::Newline => context == Context::Brackets,
+        TriviaKind::Comment => !keep_comments,
+    };
+    if blank {
+        code.extend(std::iter::repeat_n(' ', trivia.text.len()));
+    } else {
+        code.push_str(&trivia.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "// A greeter\nagent Greeter { // the agent\n\t[greet // who to greet\n\t\t_name] {\n\t\tIo println \"hi \" + name // say it\n\t}\n}\n";
+
+    #[test]
+    fn test_lossless() {
+        let tree = SyntaxTree::parse(SOURCE);
+        assert_eq!(tree.text(), SOURCE);
+
+        let broken = "agent Broken {\n\t[oops _x) {\n";
+        assert_eq!(SyntaxTree::parse(broken).text(), broken);
+    }
+
+    #[test]
+    fn test_structure() {
+        let tree = SyntaxTree::parse(SOURCE);
+        let statements: Vec<&SyntaxNode> = tree.root().nodes().collect();
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].first_token().unwrap().text, "agent");
+
+        let block = statements[0].nodes().next().unwrap();
+        assert_eq!(block.kind(), NodeKind::Block);
+        let handler = block.nodes().next().unwrap();
+        let pattern = handler.nodes().next().unwrap();
+        assert_eq!(pattern.kind(), NodeKind::List);
+        assert_eq!(
+            &SOURCE[pattern.range()],
+            "[greet // who to greet\n\t\t_name]"
+        );
+
+        let comments: Vec<&str> = tree.comments().iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(
+            comments,
+            vec![
+                "// A greeter",
+                "// the agent",
+                "// who to greet",
+                "// say it"
+            ]
+        );
+        let offset = SOURCE.find("println").unwrap();
+        assert_eq!(tree.token_at(offset).unwrap().text, "println");
+    }
+
+    #[test]
+    fn test_code() {
+        let blanked = |text: &str| " ".repeat(text.len());
+        // The leading comment stays, as a statement. The rest are blanked,
+        // along with the line break inside the pattern.
+        let expected = SOURCE
+            .replace("// the agent", &blanked("// the agent"))
+            .replace("// who to greet\n", &blanked("// who to greet\n"))
+            .replace("// say it", &blanked("// say it"));
+        assert_eq!(SyntaxTree::parse(SOURCE).code(), expected);
+    }
+}
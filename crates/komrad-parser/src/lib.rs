@@ -16,7 +16,10 @@
 
 extern crate core;
 
-mod module_builder;
+pub mod check;
+pub mod cst;
+pub mod module_builder;
+pub mod module_loader;
 pub mod parse;
 pub mod parser;
 pub mod span;
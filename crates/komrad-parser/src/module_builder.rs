@@ -1,5 +1,8 @@
-use komrad_ast::prelude::{Block, Statement};
+use crate::module_loader::ModuleLoader;
+use komrad_ast::format::{ToKomrad, format_statements};
+use komrad_ast::prelude::{Block, SourceLocation, Statement};
 use komrad_ast::sexpr::{Sexpr, ToSexpr};
+use miette::Report;
 use std::path::PathBuf;
 
 #[derive(Debug, Clone)]
@@ -7,6 +10,7 @@ pub struct ModuleBuilder {
     name: String,
     source_file: Option<PathBuf>,
     statements: Vec<Statement>,
+    locations: Vec<Option<SourceLocation>>,
 }
 
 impl ModuleBuilder {
@@ -15,6 +19,7 @@ impl ModuleBuilder {
             name: String::new(),
             source_file: None,
             statements: Vec::new(),
+            locations: Vec::new(),
         }
     }
 
@@ -26,16 +31,54 @@ impl ModuleBuilder {
         self.source_file = Some(path);
     }
 
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source_file(&self) -> Option<&PathBuf> {
+        self.source_file.as_ref()
+    }
+
+    /// A copy of this module's name and source file, without any statements.
+    pub fn without_statements(&self) -> Self {
+        ModuleBuilder {
+            name: self.name.clone(),
+            source_file: self.source_file.clone(),
+            statements: Vec::new(),
+            locations: Vec::new(),
+        }
+    }
+
+    /// Replaces `import` statements with the modules they refer to.
+    /// Relative paths are resolved from this module's source file.
+    pub fn resolve_imports(self) -> Result<Self, Report> {
+        ModuleLoader::new().resolve(self)
+    }
+
     pub fn add_statement(&mut self, statement: Statement) {
+        self.add_located_statement(statement, None);
+    }
+
+    pub fn add_located_statement(
+        &mut self,
+        statement: Statement,
+        location: Option<SourceLocation>,
+    ) {
         self.statements.push(statement);
+        self.locations.push(location);
     }
 
     pub fn statements(&self) -> &[Statement] {
         &self.statements
     }
 
+    /// Where the statement at `index` came from, if it was parsed.
+    pub fn location(&self, index: usize) -> Option<&SourceLocation> {
+        self.locations.get(index).and_then(Option::as_ref)
+    }
+
     pub fn build_block(&self) -> Block {
-        Block::new(self.statements.clone())
+        Block::with_locations(self.statements.clone(), self.locations.clone())
     }
 }
 
@@ -64,3 +107,9 @@ impl ToSexpr for ModuleBuilder {
         Sexpr::List(sexpr)
     }
 }
+
+impl ToKomrad for ModuleBuilder {
+    fn to_komrad(&self) -> String {
+        format_statements(&self.build_block())
+    }
+}
@@ -0,0 +1,415 @@
+use crate::module_builder::ModuleBuilder;
+use crate::parser::parse_file;
+use komrad_ast::prelude::{Block, CallExpr, Expr, Statement, ToSexpr, Value};
+use miette::{Diagnostic, Report};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::debug;
+
+/// Errors raised while resolving `import` statements.
+#[derive(Debug, Error, Diagnostic)]
+pub enum ImportError {
+    #[error("Could not read imported module {path}")]
+    #[diagnostic(help("import paths are resolved relative to the importing file"))]
+    NotFound {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    #[error("Import cycle detected: {chain}")]
+    Cycle { chain: String },
+
+    #[error("Invalid import: {0}")]
+    #[diagnostic(help(
+        r#"expected `import "path/to/file.kom"` or `import "path/to/file.kom" as Name`"#
+    ))]
+    InvalidImport(String),
+}
+
+/// Resolves `import "path/to/file.kom" [as Name]` statements.
+///
+/// Each imported file becomes an agent named after its canonical path,
+/// whose block is the imported module, and the import binds that agent to
+/// the namespace (the `as` name, or the file stem):
+///
+/// ```komrad
+/// import "lib/math.kom" as Math
+/// ```
+///
+/// becomes, with `/src/lib/math.kom` standing for the canonical path,
+///
+/// ```komrad
+/// /src/lib/math.kom = agent /src/lib/math.kom { ...statements of lib/math.kom... }
+/// /src/lib/math.kom = spawn /src/lib/math.kom
+/// Math = /src/lib/math.kom
+/// ```
+///
+/// Top-level handlers of the imported file are then reachable as
+/// `Math square 4`, and any `agent` definitions it contains are
+/// registered with the shared registry when the namespace agent starts.
+/// Since no Komrad name looks like a path, files with the same name in
+/// different directories don't collide, and neither do they with the
+/// importer's own names.
+///
+/// A file is loaded and spawned once, however many modules import it. The
+/// definitions of every imported module go to the top of the importing
+/// program, and a module's own imports are handed to its agent when it's
+/// spawned, e.g. `... = spawn /src/lib/math.kom { Log = /src/lib/log.kom }`.
+/// Importing a file again under another name binds that name to the same
+/// agent.
+#[derive(Default)]
+pub struct ModuleLoader {
+    stack: Vec<PathBuf>,
+    /// The imported files, by their canonical paths.
+    loaded: HashSet<PathBuf>,
+}
+
+impl ModuleLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the file at `path` and recursively resolves its imports.
+    pub fn load(&mut self, path: &Path) -> Result<ModuleBuilder, Report> {
+        let canonical = self.enter(path)?;
+        debug!("Loading module {}", canonical.display());
+        let module = parse_file(&canonical)?;
+
+        self.stack.push(canonical);
+        let result = self.resolve(module);
+        self.stack.pop();
+        result
+    }
+
+    /// Replaces the import statements of an already parsed module.
+    pub fn resolve(&mut self, module: ModuleBuilder) -> Result<ModuleBuilder, Report> {
+        let mut resolved = module.without_statements();
+        for (index, statement) in module.statements().iter().enumerate() {
+            match parse_import(statement)? {
+                Some((path, namespace)) => {
+                    let mut definitions = Vec::new();
+                    let (spawned, namespace) =
+                        self.import(&module, &path, namespace, &mut definitions)?;
+                    for statement in definitions {
+                        resolved.add_statement(statement);
+                    }
+                    resolved
+                        .add_statement(Statement::Assignment(namespace, Expr::Variable(spawned)));
+                }
+                None => resolved
+                    .add_located_statement(statement.clone(), module.location(index).cloned()),
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Loads a module imported by `importer`, unless it was loaded already,
+    /// adding the statements that define and spawn it to `definitions` after
+    /// those of the modules it imports in turn. Returns the name it was
+    /// spawned as and the name it's imported as.
+    fn import(
+        &mut self,
+        importer: &ModuleBuilder,
+        path: &str,
+        namespace: Option<String>,
+        definitions: &mut Vec<Statement>,
+    ) -> Result<(String, String), Report> {
+        let base_dir = importer
+            .source_file()
+            .and_then(|file| file.parent().map(Path::to_path_buf))
+            .unwrap_or_else(|| PathBuf::from("."));
+        let namespace = match namespace {
+            Some(namespace) => namespace,
+            None => Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .ok_or_else(|| ImportError::InvalidImport(path.to_string()))?,
+        };
+
+        let canonical = self.enter(&base_dir.join(path))?;
+        let spawned = canonical.display().to_string();
+        if self.loaded.contains(&canonical) {
+            return Ok((spawned, namespace));
+        }
+
+        debug!("Loading module {}", canonical.display());
+        let module = parse_file(&canonical)?;
+        self.stack.push(canonical.clone());
+        let result = self.split_imports(module, definitions);
+        self.stack.pop();
+        let (block, imports) = result?;
+
+        definitions.extend(namespace_statements(&spawned, block, imports));
+        self.loaded.insert(canonical);
+        Ok((spawned, namespace))
+    }
+
+    /// Separates a module's statements from its imports, which become
+    /// assignments run when its agent is spawned.
+    fn split_imports(
+        &mut self,
+        module: ModuleBuilder,
+        definitions: &mut Vec<Statement>,
+    ) -> Result<(Block, Vec<Statement>), Report> {
+        let mut statements = Vec::new();
+        let mut imports = Vec::new();
+        for statement in module.statements() {
+            match parse_import(statement)? {
+                Some((path, namespace)) => {
+                    let (spawned, namespace) =
+                        self.import(&module, &path, namespace, definitions)?;
+                    imports.push(Statement::Assignment(namespace, Expr::Variable(spawned)));
+                }
+                None => statements.push(statement.clone()),
+            }
+        }
+        Ok((Block::new(statements), imports))
+    }
+
+    /// The canonical path of a module about to be loaded, unless loading it
+    /// would complete an import cycle.
+    fn enter(&self, path: &Path) -> Result<PathBuf, Report> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|source| ImportError::NotFound {
+                path: path.to_path_buf(),
+                source,
+            })?;
+
+        if let Some(start) = self.stack.iter().position(|p| p == &canonical) {
+            let chain = self.stack[start..]
+                .iter()
+                .chain(std::iter::once(&canonical))
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(ImportError::Cycle { chain }.into());
+        }
+        Ok(canonical)
+    }
+}
+
+/// Recognizes `import "path"` and `import "path" as Name`.
+fn parse_import(statement: &Statement) -> Result<Option<(String, Option<String>)>, ImportError> {
+    let Statement::Expr(Expr::Call(call)) = statement else {
+        return Ok(None);
+    };
+    if call.target() != &Expr::Variable("import".to_string()) {
+        return Ok(None);
+    }
+
+    let args: Vec<&Expr> = call.args().iter().map(|arg| arg.as_ref()).collect();
+    match args.as_slice() {
+        [Expr::Value(Value::String(path))] => Ok(Some((path.clone(), None))),
+        [
+            Expr::Value(Value::String(path)),
+            Expr::Variable(keyword),
+            Expr::Variable(namespace),
+        ] if keyword == "as" => Ok(Some((path.clone(), Some(namespace.clone())))),
+        _ => Err(ImportError::InvalidImport(statement.to_sexpr().format(0))),
+    }
+}
+
+/// Defines and spawns the agent for an imported module under `name`.
+fn namespace_statements(name: &str, block: Block, imports: Vec<Statement>) -> Vec<Statement> {
+    let mut spawn_args = vec![Expr::Variable(name.to_string()).into()];
+    if !imports.is_empty() {
+        spawn_args.push(Expr::Block(Box::new(Block::new(imports))).into());
+    }
+    vec![
+        Statement::Assignment(
+            name.to_string(),
+            Expr::Call(CallExpr::new(
+                Expr::Variable("agent".to_string()),
+                vec![
+                    Expr::Variable(name.to_string()).into(),
+                    Expr::Block(Box::new(block)).into(),
+                ],
+            )),
+        ),
+        Statement::Assignment(
+            name.to_string(),
+            Expr::Call(CallExpr::new(
+                Expr::Variable("spawn".to_string()),
+                spawn_args,
+            )),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("komrad-imports-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        dir
+    }
+
+    /// The name the agent for the file at `path` is defined under.
+    fn internal_name(path: &Path) -> String {
+        path.canonicalize().unwrap().display().to_string()
+    }
+
+    #[test]
+    fn test_import_is_namespaced() {
+        let dir = temp_dir("namespaced");
+        fs::write(dir.join("lib/math.kom"), "[square _x] {\n    x * x\n}\n").unwrap();
+        fs::write(
+            dir.join("main.kom"),
+            "import \"lib/math.kom\"\nimport \"lib/math.kom\" as Math\n",
+        )
+        .unwrap();
+
+        let module = ModuleLoader::new().load(&dir.join("main.kom")).unwrap();
+        let statements = module.statements();
+        let math = internal_name(&dir.join("lib/math.kom"));
+        assert_eq!(statements.len(), 4);
+        assert!(matches!(&statements[0], Statement::Assignment(name, _) if name == &math));
+        assert!(matches!(&statements[1], Statement::Assignment(name, _) if name == &math));
+        // Both imports name the same agent
+        assert_eq!(
+            statements[2..],
+            [
+                Statement::Assignment("math".to_string(), Expr::Variable(math.clone())),
+                Statement::Assignment("Math".to_string(), Expr::Variable(math)),
+            ]
+        );
+        match &statements[0] {
+            Statement::Assignment(_, Expr::Call(call)) => {
+                assert_eq!(call.target(), &Expr::Variable("agent".to_string()));
+                match call.args()[1].as_ref() {
+                    Expr::Block(block) => {
+                        assert!(matches!(block.statements()[0], Statement::Handler(_)))
+                    }
+                    other => panic!("Expected a block, got {:?}", other),
+                }
+            }
+            other => panic!("Expected an agent definition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_same_file_names_do_not_collide() {
+        let dir = temp_dir("same-names");
+        for lib in ["a", "b"] {
+            fs::create_dir_all(dir.join(lib)).unwrap();
+            fs::write(dir.join(lib).join("util.kom"), "answer = 42\n").unwrap();
+        }
+        fs::write(
+            dir.join("main.kom"),
+            "import \"a/util.kom\"\nimport \"b/util.kom\" as Other\n",
+        )
+        .unwrap();
+
+        let module = ModuleLoader::new().load(&dir.join("main.kom")).unwrap();
+        let a = internal_name(&dir.join("a/util.kom"));
+        let b = internal_name(&dir.join("b/util.kom"));
+        assert_ne!(a, b);
+        // Each file is defined under its own name, and only the aliases are
+        // the importer's
+        let assigned: Vec<&str> = module
+            .statements()
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Assignment(name, _) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(assigned, vec![&a, &a, "util", &b, &b, "Other"]);
+    }
+
+    #[test]
+    fn test_import_resolves_relative_to_importer() {
+        let dir = temp_dir("relative");
+        fs::write(dir.join("lib/inner.kom"), "answer = 42\n").unwrap();
+        fs::write(dir.join("lib/outer.kom"), "import \"inner.kom\"\n").unwrap();
+        fs::write(dir.join("main.kom"), "import \"lib/outer.kom\"\n").unwrap();
+
+        let module = ModuleLoader::new().load(&dir.join("main.kom"));
+        assert!(module.is_ok(), "{:?}", module.err());
+    }
+
+    #[test]
+    fn test_diamond_import_is_loaded_once() {
+        let dir = temp_dir("diamond");
+        fs::write(dir.join("lib/log.kom"), "[log _x] {\n    Io println x\n}\n").unwrap();
+        fs::write(dir.join("lib/left.kom"), "import \"log.kom\"\n").unwrap();
+        fs::write(dir.join("lib/right.kom"), "import \"log.kom\" as Log\n").unwrap();
+        fs::write(
+            dir.join("main.kom"),
+            "import \"lib/left.kom\"\nimport \"lib/right.kom\"\n",
+        )
+        .unwrap();
+
+        let module = ModuleLoader::new().load(&dir.join("main.kom")).unwrap();
+        // The spawns, with what each is handed
+        let spawns: Vec<(String, Vec<Statement>)> = module
+            .statements()
+            .iter()
+            .filter_map(|statement| match statement {
+                Statement::Assignment(name, Expr::Call(call))
+                    if call.target() == &Expr::Variable("spawn".to_string()) =>
+                {
+                    let init = match call.args().get(1).map(|arg| arg.as_ref()) {
+                        Some(Expr::Block(block)) => block.statements().to_vec(),
+                        _ => vec![],
+                    };
+                    Some((name.clone(), init))
+                }
+                _ => None,
+            })
+            .collect();
+        let log = internal_name(&dir.join("lib/log.kom"));
+        let bind_log =
+            |name: &str| Statement::Assignment(name.to_string(), Expr::Variable(log.clone()));
+        assert_eq!(
+            spawns,
+            vec![
+                (log.clone(), vec![]),
+                (
+                    internal_name(&dir.join("lib/left.kom")),
+                    vec![bind_log("log")]
+                ),
+                (
+                    internal_name(&dir.join("lib/right.kom")),
+                    vec![bind_log("Log")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_cycle() {
+        let dir = temp_dir("cycle");
+        fs::write(dir.join("a.kom"), "import \"b.kom\"\n").unwrap();
+        fs::write(dir.join("b.kom"), "import \"a.kom\"\n").unwrap();
+
+        let err = ModuleLoader::new().load(&dir.join("a.kom")).unwrap_err();
+        assert!(
+            err.to_string().starts_with("Import cycle detected"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_import_not_found() {
+        let dir = temp_dir("missing");
+        fs::write(dir.join("main.kom"), "import \"nope.kom\"\n").unwrap();
+
+        let err = ModuleLoader::new().load(&dir.join("main.kom")).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Could not read imported module"),
+            "{}",
+            err
+        );
+    }
+}
@@ -1,11 +1,11 @@
 use crate::parse::lines::{parse_blank_line, parse_comment};
 use crate::parse::statements;
-use crate::span::{KResult, Span};
-use komrad_ast::prelude::{Block, Expr, Statement};
+use crate::span::{KResult, Span, located};
+use komrad_ast::prelude::{Block, Expr, SourceLocation, Statement};
 use nom::Parser;
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{line_ending, multispace0};
+use nom::character::complete::{line_ending, multispace0, space0};
 use nom::multi::{many1, separated_list0};
 use nom::sequence::{delimited, preceded};
 
@@ -18,7 +18,11 @@ pub fn parse_block(input: Span) -> KResult<Block> {
     )
     .parse(input)?;
 
-    Ok((remaining, Block::new(block)))
+    let (statements, locations) = block
+        .into_iter()
+        .map(|(statement, location)| (statement, Some(location)))
+        .unzip();
+    Ok((remaining, Block::with_locations(statements, locations)))
 }
 
 /// Parse a block expression, i.e. `{ ...statements... }`
@@ -28,10 +32,16 @@ pub fn parse_block_expression(input: Span) -> KResult<Expr> {
         .parse(input)
 }
 
-pub fn parse_block_statements(input: Span) -> KResult<Vec<Statement>> {
+/// Parse the statements of a block, each with the source it came from.
+pub fn parse_block_statements(input: Span) -> KResult<Vec<(Statement, SourceLocation)>> {
     separated_list0(
-        many1(line_ending),
-        alt((statements::parse_statement, parse_blank_line, parse_comment)),
+        // Trailing spaces are allowed, e.g. where a comment was blanked out
+        many1((space0, line_ending)),
+        located(alt((
+            statements::parse_statement,
+            parse_blank_line,
+            parse_comment,
+        ))),
     )
     .parse(input)
 }
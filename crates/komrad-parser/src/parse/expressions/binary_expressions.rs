@@ -1,29 +1,31 @@
 use crate::parse::expressions;
-use crate::parse::{block, embedded_block, identifier};
+use crate::parse::{block, embedded_block, identifier, primitives};
 use crate::span::KResult;
-use komrad_ast::prelude::{BinaryExpr, BinaryOp, Expr, Span};
+use komrad_ast::prelude::{BinaryExpr, BinaryOp, Expr, Span, UnaryExpr, UnaryOp};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::space0;
+use nom::character::complete::{space0, space1};
 use nom::combinator::{map, opt};
-use nom::sequence::delimited;
+use nom::sequence::{delimited, pair};
 use nom::Parser;
 
 /// Returns the precedence value of a given binary operator.
 /// Higher numbers bind more tightly.
 fn precedence(op: &BinaryOp) -> u8 {
     match op {
-        BinaryOp::Eq | BinaryOp::Ne => 0,
-        BinaryOp::Or => 1,
-        BinaryOp::And => 2,
-        BinaryOp::Add | BinaryOp::Sub => 3,
-        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 4,
-        BinaryOp::Access => 5,
+        BinaryOp::Or => 0,
+        BinaryOp::And => 1,
+        BinaryOp::Eq | BinaryOp::Ne => 2,
+        BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge | BinaryOp::Divisible => 3,
+        BinaryOp::Add | BinaryOp::Sub => 4,
+        BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 5,
+        BinaryOp::Access => 6,
     }
 }
 
 /// Parses a binary operator from the input.
-/// This now supports both arithmetic and logical operators.
+/// This supports arithmetic, logical and relational operators.
+/// Longer operators must be tried before their prefixes (`<=` before `<`, `%%` before `%`).
 fn parse_binary_operator(input: Span) -> KResult<BinaryOp> {
     alt((
         map(tag("||"), |_| BinaryOp::Or),
@@ -32,34 +34,75 @@ fn parse_binary_operator(input: Span) -> KResult<BinaryOp> {
         map(tag("-"), |_| BinaryOp::Sub),
         map(tag("*"), |_| BinaryOp::Mul),
         map(tag("/"), |_| BinaryOp::Div),
+        map(tag("%%"), |_| BinaryOp::Divisible),
         map(tag("%"), |_| BinaryOp::Mod),
         map(tag("=="), |_| BinaryOp::Eq),
         map(tag("!="), |_| BinaryOp::Ne),
+        map(tag("<="), |_| BinaryOp::Le),
+        map(tag(">="), |_| BinaryOp::Ge),
+        map(tag("<"), |_| BinaryOp::Lt),
+        map(tag(">"), |_| BinaryOp::Gt),
         map(tag("."), |_| BinaryOp::Access),
     ))
     .parse(input)
 }
 
+/// Parses a prefix operator. `++` and `--` must be tried before `-`.
+fn parse_unary_operator(input: Span) -> KResult<UnaryOp> {
+    alt((
+        map(tag("++"), |_| UnaryOp::Inc),
+        map(tag("--"), |_| UnaryOp::Dec),
+        map(tag("-"), |_| UnaryOp::Neg),
+        map(tag("!"), |_| UnaryOp::Not),
+    ))
+    .parse(input)
+}
+
+/// Parses a prefix operator applied to an operand, e.g. `-x`, `!done`, `++count`.
+///
+/// The operand binds as tightly as `.`, so `-a.b` is `-(a.b)` and `-x * y` is `(-x) * y`.
+/// Negative number literals such as `-1` are parsed as numbers, not negations.
+fn parse_unary_expression(input: Span) -> KResult<Expr> {
+    pair(parse_unary_operator, |input| {
+        parse_binary_expr_prec(input, precedence(&BinaryOp::Access), false)
+    })
+    .map(|(op, expr)| Expr::Unary(UnaryExpr::new(op, expr)))
+    .parse(input)
+}
+
 /// Parses a primary (non-binary) expression.
-/// This combines call expressions, blocks, numbers, strings, embedded values, and identifiers.
+/// This combines call expressions, blocks, numbers, strings, embedded values, booleans and identifiers.
 fn parse_primary(input: Span) -> KResult<Expr> {
     alt((
         //expressions::parse_call_expression,
         block::parse_block_expression,
         expressions::parse_expression::parse_number_expression,
+        parse_unary_expression,
         expressions::parse_expression::parse_string_expression,
         map(embedded_block::parse_embedded_block_value, Expr::Value),
+        map(primitives::parse_boolean, Expr::Value),
         map(identifier::parse_identifier, Expr::Variable),
     ))
     .parse(input)
 }
 
+/// Returns true if `input` is a space followed by a prefix operator stuck to its operand,
+/// as in the ` -x` of `Io println -x`.
+fn is_prefixed_argument(input: Span) -> bool {
+    match pair(space1, parse_unary_operator).parse(input) {
+        Ok((rest, _)) => rest.fragment().starts_with(|c: char| !c.is_whitespace()),
+        Err(_) => false,
+    }
+}
+
 /// Parses binary expressions using a precedence climbing algorithm.
 ///
 /// This function first parses a primary expression and then, as long as the next operator
 /// has a precedence higher than or equal to `min_prec`, it consumes the operator and recursively
 /// parses the right-hand side expression with a higher minimum precedence (to enforce left associativity).
-fn parse_binary_expr_prec(input: Span, min_prec: u8) -> KResult<Expr> {
+///
+/// In call arguments, `a -b` is two arguments, `a` and `-b`, while `a - b` and `a-b` are still subtraction.
+fn parse_binary_expr_prec(input: Span, min_prec: u8, in_arguments: bool) -> KResult<Expr> {
     let (mut input, mut lhs) = parse_primary(input)?;
 
     loop {
@@ -71,9 +114,15 @@ fn parse_binary_expr_prec(input: Span, min_prec: u8) -> KResult<Expr> {
                 if op_prec < min_prec {
                     break;
                 }
+                if in_arguments
+                    && matches!(op, BinaryOp::Add | BinaryOp::Sub)
+                    && is_prefixed_argument(input.clone())
+                {
+                    break;
+                }
                 input = next_input;
                 let next_min_prec = op_prec + 1;
-                let (after_rhs, rhs) = parse_binary_expr_prec(input, next_min_prec)?;
+                let (after_rhs, rhs) = parse_binary_expr_prec(input, next_min_prec, in_arguments)?;
                 lhs = Expr::Binary(BinaryExpr::new(lhs, op, rhs));
                 input = after_rhs;
             }
@@ -87,14 +136,19 @@ fn parse_binary_expr_prec(input: Span, min_prec: u8) -> KResult<Expr> {
 ///
 /// This simply calls the recursive function with a minimum precedence of 0.
 pub fn parse_binary_expression(input: Span) -> KResult<Expr> {
-    parse_binary_expr_prec(input, 0)
+    parse_binary_expr_prec(input, 0, false)
+}
+
+/// Parses a binary expression in call argument position, where `-x` and `!x` are prefix operators.
+pub fn parse_argument_expression(input: Span) -> KResult<Expr> {
+    parse_binary_expr_prec(input, 0, true)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::span::new_span;
-    use komrad_ast::prelude::{BinaryExpr, BinaryOp, Expr, Number, Value};
+    use komrad_ast::prelude::{BinaryExpr, BinaryOp, Expr, Number, UnaryExpr, UnaryOp, Value};
 
     #[test]
     fn test_simple_addition() {
@@ -208,4 +262,90 @@ mod tests {
         });
         assert_eq!(expr, expected);
     }
+
+    #[test]
+    fn test_relational_operators() {
+        let cases = [
+            ("a<b", BinaryOp::Lt),
+            ("a <= b", BinaryOp::Le),
+            ("a>b", BinaryOp::Gt),
+            ("a >= b", BinaryOp::Ge),
+            ("a %% b", BinaryOp::Divisible),
+        ];
+        for (source, op) in cases {
+            let (remaining, expr) = parse_binary_expression(new_span(source)).expect(source);
+            assert!(remaining.fragment().is_empty());
+            let expected = Expr::Binary(BinaryExpr::new(
+                Expr::Variable("a".into()),
+                op,
+                Expr::Variable("b".into()),
+            ));
+            assert_eq!(expr, expected);
+        }
+    }
+
+    #[test]
+    fn test_precedence_relational_and_logical() {
+        let input = new_span("a + n < b && c == d");
+        let (remaining, expr) = parse_binary_expression(input).expect("parse failed");
+        assert!(remaining.fragment().is_empty());
+
+        // Expected AST: ((a + n) < b) && (c == d)
+        let expected = Expr::Binary(BinaryExpr::new(
+            Expr::Binary(BinaryExpr::new(
+                Expr::Binary(BinaryExpr::new(
+                    Expr::Variable("a".into()),
+                    BinaryOp::Add,
+                    Expr::Variable("n".into()),
+                )),
+                BinaryOp::Lt,
+                Expr::Variable("b".into()),
+            )),
+            BinaryOp::And,
+            Expr::Binary(BinaryExpr::new(
+                Expr::Variable("c".into()),
+                BinaryOp::Eq,
+                Expr::Variable("d".into()),
+            )),
+        ));
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_unary_operators() {
+        let cases = [
+            ("-x", UnaryOp::Neg),
+            ("!done", UnaryOp::Not),
+            ("++x", UnaryOp::Inc),
+            ("--x", UnaryOp::Dec),
+        ];
+        for (source, op) in cases {
+            let (remaining, expr) = parse_binary_expression(new_span(source)).expect(source);
+            assert!(remaining.fragment().is_empty());
+            let name = source.trim_start_matches(['-', '!', '+']).to_string();
+            assert_eq!(expr, Expr::Unary(UnaryExpr::new(op, Expr::Variable(name))));
+        }
+    }
+
+    #[test]
+    fn test_unary_binds_tighter_than_binary() {
+        let input = new_span("-x * !y.z");
+        let (remaining, expr) = parse_binary_expression(input).expect("parse failed");
+        assert!(remaining.fragment().is_empty());
+
+        // Expected AST: (-x) * !(y.z)
+        let expected = Expr::Binary(BinaryExpr::new(
+            Expr::Unary(UnaryExpr::new(UnaryOp::Neg, Expr::Variable("x".into()))),
+            BinaryOp::Mul,
+            Expr::Unary(UnaryExpr::new(
+                UnaryOp::Not,
+                Expr::Binary(BinaryExpr::new(
+                    Expr::Variable("y".into()),
+                    BinaryOp::Access,
+                    Expr::Variable("z".into()),
+                )),
+            )),
+        ));
+        assert_eq!(expr, expected);
+    }
 }
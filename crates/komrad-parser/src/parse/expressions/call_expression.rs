@@ -2,28 +2,53 @@ use crate::parse::expressions::parse_expression;
 use crate::parse::identifier;
 use crate::span::{KResult, Span};
 use komrad_ast::prelude::{CallExpr, Expr};
-use nom::character::complete::space1;
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, space1};
+use nom::combinator::{map_res, opt};
 use nom::multi::separated_list1;
 use nom::sequence::{pair, preceded};
 use nom::Parser;
+use std::time::Duration;
 
 /// Parse a call expression like `foo bar { ... } baz`.
 ///
 /// The first identifier is the target (`foo`).
 /// Then we parse zero or more arguments, each preceded by *multispace1* so newlines are allowed.
+/// An optional trailing `@500ms` overrides how long to wait for a reply.
 pub fn parse_call_expression(input: Span) -> KResult<Expr> {
-    pair(
+    (
         identifier::parse_identifier.map(|name| Expr::Variable(name)),
         preceded(
             space1,
-            separated_list1(space1, parse_expression::parse_value_expression),
+            separated_list1(space1, parse_expression::parse_argument_expression),
+        ),
+        opt(preceded(space1, parse_reply_timeout)),
+    )
+        .map(|(target, args, timeout)| {
+            Expr::Call(CallExpr::new(target, args).with_reply_timeout(timeout))
+        })
+        .parse(input)
+}
+
+/// Parse a reply timeout like `@250ms`, `@5s` or `@1m`.
+pub fn parse_reply_timeout(input: Span) -> KResult<Duration> {
+    preceded(
+        char('@'),
+        map_res(
+            pair(digit1, alt((tag("ms"), tag("s"), tag("m")))),
+            |(amount, unit): (Span, Span)| {
+                let amount = amount.fragment().parse::<u64>().ok();
+                match *unit.fragment() {
+                    "ms" => amount.map(Duration::from_millis),
+                    "s" => amount.map(Duration::from_secs),
+                    _ => amount
+                        .and_then(|minutes| minutes.checked_mul(60))
+                        .map(Duration::from_secs),
+                }
+                .ok_or("reply timeout is too long")
+            },
         ),
     )
-    .map(|(target, args)| {
-        Expr::Call(CallExpr::new(
-            target,
-            args.into_iter().map(|arg| arg.into()).collect(),
-        ))
-    })
     .parse(input)
 }
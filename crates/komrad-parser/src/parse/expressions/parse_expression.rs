@@ -13,6 +13,7 @@ pub fn parse_value_expression(input: Span) -> KResult<Box<Expr>> {
     map(
         alt((
             parse_binary_expression,
+            map(primitives::parse_map, Expr::Map),
             block::parse_block_expression,
             parse_number_expression,
             parse_string_expression,
@@ -23,10 +24,20 @@ pub fn parse_value_expression(input: Span) -> KResult<Box<Expr>> {
     .parse(input)
 }
 
+/// Parse a call argument. Unlike [`parse_value_expression`], `f -x` passes `-x` to `f`.
+pub fn parse_argument_expression(input: Span) -> KResult<Box<Expr>> {
+    alt((
+        map(binary_expressions::parse_argument_expression, Box::new),
+        parse_value_expression,
+    ))
+    .parse(input)
+}
+
 /// Parse an expression (calls, block, number, string, variable).
 pub fn parse_expression(input: Span) -> KResult<Expr> {
     alt((
         map(primitives::parse_list, |list| Expr::List(list)),
+        map(primitives::parse_map, Expr::Map),
         call_expression::parse_call_expression,
         binary_expressions::parse_binary_expression,
         parse_number_expression,
@@ -57,7 +68,7 @@ mod test_parse_expression {
     use crate::parse::strings::test_parse_string::full_span;
     use komrad_ast::prelude::{
         BinaryExpr, BinaryOp, Block, CallExpr, Expr, Handler, Number, Pattern, Statement, TypeExpr,
-        Value,
+        UnaryExpr, UnaryOp, Value,
     };
 
     #[test]
@@ -320,4 +331,65 @@ mod test_parse_expression {
 
         assert_eq!(stmt, expected);
     }
+
+    #[test]
+    fn test_parse_call_with_reply_timeout() {
+        let input = full_span("x = server compute y @500ms");
+
+        let (remaining, stmt) = parse_statement(input).unwrap();
+        assert_eq!(*remaining.fragment(), "");
+
+        let expected = Statement::Assignment(
+            "x".to_string(),
+            Expr::Call(
+                CallExpr::new(
+                    Expr::Variable("server".into()),
+                    vec![
+                        Expr::Variable("compute".into()).into(),
+                        Expr::Variable("y".into()).into(),
+                    ],
+                )
+                .with_reply_timeout(Some(std::time::Duration::from_millis(500))),
+            ),
+        );
+
+        assert_eq!(stmt, expected);
+    }
+
+    #[test]
+    fn test_parse_call_with_unary_arguments() {
+        let input = full_span("Io println -x !done ++n a - b");
+
+        let (remaining, stmt) = parse_statement(input).unwrap();
+        assert_eq!(*remaining.fragment(), "");
+
+        let unary = |op, name: &str| Expr::Unary(UnaryExpr::new(op, Expr::Variable(name.into())));
+        let expected = Statement::Expr(Expr::Call(CallExpr::new(
+            Expr::Variable("Io".into()),
+            vec![
+                Expr::Variable("println".into()).into(),
+                unary(UnaryOp::Neg, "x").into(),
+                unary(UnaryOp::Not, "done").into(),
+                unary(UnaryOp::Inc, "n").into(),
+                Expr::Binary(BinaryExpr::new(
+                    Expr::Variable("a".into()),
+                    BinaryOp::Sub,
+                    Expr::Variable("b".into()),
+                ))
+                .into(),
+            ],
+        )));
+
+        assert_eq!(stmt, expected);
+    }
+
+    #[test]
+    fn test_reply_timeout_too_long() {
+        use crate::parse::expressions::call_expression::parse_reply_timeout;
+
+        let minutes = parse_reply_timeout(full_span("@2m")).unwrap().1;
+        assert_eq!(minutes, std::time::Duration::from_secs(120));
+        assert!(parse_reply_timeout(full_span("@999999999999999999m")).is_err());
+        assert!(parse_reply_timeout(full_span("@99999999999999999999s")).is_err());
+    }
 }
@@ -2,18 +2,20 @@ use crate::span::{KResult, Span};
 use komrad_ast::prelude::{ErrorKind, ParserError};
 use miette::SourceSpan;
 use nom::bytes::complete::{take_while, take_while1};
-use nom::combinator::recognize;
-use nom::sequence::pair;
+use nom::character::complete::char;
+use nom::combinator::{opt, recognize};
 use nom::Parser;
 
-/// Parse an identifier, e.g. `[a-zA-Z_][a-zA-Z0-9_]*`.
+/// Parse an identifier, e.g. `[a-zA-Z_][a-zA-Z0-9_]*`, optionally ending in
+/// `?` for questions such as `implements?`.
 pub(crate) fn parse_identifier(input: Span) -> KResult<String> {
     let first = |c: char| c.is_alphabetic() || c == '_';
     let rest = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
 
-    let (remaining, matched_span) = recognize(pair(
+    let (remaining, matched_span) = recognize((
         take_while1(first), // Must start with an alphabetic character or `_`
         take_while(rest),   // Then allow numbers, letters, `_`
+        opt(char('?')),     // And a trailing `?`
     ))
     .parse(input)?;
 
@@ -60,6 +62,17 @@ mod test_parse_identifier {
         let _ = parse_identifier(input).unwrap_err();
     }
 
+    #[test]
+    fn test_parse_identifier_with_question_mark() {
+        let input = Span::new_extra(
+            "implements? List",
+            Arc::new(NamedSource::new("<test>", "implements? List".to_string())),
+        );
+        let (remaining, identifier) = parse_identifier(input).unwrap();
+        assert_eq!(identifier, "implements?");
+        assert_eq!(remaining.fragment().to_string(), " List");
+    }
+
     #[test]
     fn test_parse_identifier_with_dash() {
         let input = Span::new_extra(
@@ -5,6 +5,8 @@ pub mod identifier;
 pub mod lines;
 pub mod statements;
 pub mod strings;
+pub mod try_catch;
+pub mod while_loop;
 
 pub mod block;
 pub mod embedded_block;
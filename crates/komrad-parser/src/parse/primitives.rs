@@ -1,13 +1,14 @@
+use crate::parse::expressions::parse_expression::parse_expression;
 use crate::parse::identifier::parse_identifier;
 use crate::parse::{block, strings};
 use crate::span::{KResult, Span};
 use komrad_ast::prelude::{Expr, Number, Value};
 use nom::branch::alt;
 use nom::bytes::complete::tag;
-use nom::character::complete::{char, digit1, one_of, space0};
-use nom::combinator::{opt, recognize};
+use nom::character::complete::{char, digit1, one_of, satisfy, space0};
+use nom::combinator::{not, opt, recognize};
 use nom::multi::{many1, separated_list0};
-use nom::sequence::{delimited, pair, preceded};
+use nom::sequence::{delimited, pair, preceded, terminated};
 use nom::Parser;
 
 pub fn parse_word(input: Span) -> KResult<Value> {
@@ -17,11 +18,16 @@ pub fn parse_word(input: Span) -> KResult<Value> {
         .parse(input)
 }
 
+/// Parses `true` or `false`, but not a longer identifier such as `trueBranch`.
 pub fn parse_boolean(input: Span) -> KResult<Value> {
-    tag("true")
-        .or(tag("false"))
-        .map(|boolean: Span| Value::Boolean(boolean.fragment() == &"true"))
-        .parse(input)
+    terminated(
+        tag("true").or(tag("false")),
+        not(satisfy(|c: char| {
+            c.is_alphanumeric() || c == '_' || c == '-'
+        })),
+    )
+    .map(|boolean: Span| Value::Boolean(boolean.fragment() == &"true"))
+    .parse(input)
 }
 
 pub fn parse_number(input: Span) -> KResult<Number> {
@@ -45,22 +51,19 @@ fn float(input: Span) -> KResult<Number> {
         )), // Case three: 42. and 42.42
         recognize((unsigned_decimal, char('.'), opt(unsigned_decimal))),
     ))
-    .map(|s: Span| s.fragment().to_string())
-    .map(|s| Number::Float(s.parse().unwrap()))
+    .map_res(|s: Span| s.fragment().parse::<Number>())
     .parse(input)
 }
 
 fn unsigned_decimal(input: Span) -> KResult<Number> {
     recognize(many1(one_of("0123456789")))
-        .map(|s: Span| s.to_string())
-        .map(|s| Number::Int(s.parse().unwrap()))
+        .map_res(|s: Span| s.fragment().parse::<Number>())
         .parse(input)
 }
 
 fn signed_decimal(input: Span) -> KResult<Number> {
     recognize(pair(one_of("+-"), many1(digit1)))
-        .map(|s: Span| s.to_string())
-        .map(|s| Number::Int(s.parse().unwrap()))
+        .map_res(|s: Span| s.fragment().parse::<Number>())
         .parse(input)
 }
 
@@ -71,6 +74,7 @@ pub fn parse_list_part(input: Span) -> KResult<Expr> {
         parse_number.map(|n| Expr::Value(n.into())),
         strings::parse_string.map(|s| Expr::Value(s.into())),
         parse_list.map(|list| Expr::List(list)),
+        parse_map.map(Expr::Map),
         block::parse_block_expression,
     ))
     .parse(input)
@@ -80,6 +84,29 @@ pub fn parse_list(input: Span) -> KResult<Vec<Expr>> {
     delimited(tag("["), separated_list0(space0, parse_list_part), tag("]")).parse(input)
 }
 
+/// Parses a map literal, e.g. `{: name = "x", n = 1 :}`. Identifier keys
+/// are strings, and a trailing comma is allowed.
+pub fn parse_map(input: Span) -> KResult<Vec<(Expr, Expr)>> {
+    let comma = || (space0, char(','), space0);
+    delimited(
+        (tag("{:"), space0),
+        terminated(separated_list0(comma(), parse_map_entry), opt(comma())),
+        (space0, tag(":}")),
+    )
+    .parse(input)
+}
+
+fn parse_map_entry(input: Span) -> KResult<(Expr, Expr)> {
+    let key = alt((
+        parse_identifier.map(|key| Expr::Value(Value::String(key))),
+        strings::parse_string.map(Expr::Value),
+        parse_number.map(|n| Expr::Value(n.into())),
+    ));
+    (key, delimited(space0, char('='), space0), parse_expression)
+        .map(|(key, _, value)| (key, value))
+        .parse(input)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,6 +140,12 @@ mod tests {
         assert_eq!(boolean, Value::Boolean(false));
     }
 
+    #[test]
+    fn test_parse_boolean_prefix_of_identifier() {
+        let input = full_span("trueBranch");
+        assert!(parse_boolean(input).is_err());
+    }
+
     #[test]
     fn test_parse_boolean_invalid() {
         // Example: invalid
@@ -152,4 +185,25 @@ mod tests {
         ];
         assert_eq!(list, expected);
     }
+
+    #[test]
+    fn test_parse_map() {
+        let input = full_span("{: name = \"x\", \"n\" = count + 1, 2 = [a],  :}");
+        let (remaining, map) = parse_map(input).unwrap();
+        assert_eq!(*remaining.fragment(), "");
+        assert_eq!(map.len(), 3);
+        assert_eq!(map[0].0, Expr::Value(Value::String("name".to_string())));
+        assert_eq!(map[0].1, Expr::Value(Value::String("x".to_string())));
+        assert_eq!(map[1].0, Expr::Value(Value::String("n".to_string())));
+        assert!(matches!(map[1].1, Expr::Binary(_)));
+        assert_eq!(map[2].0, Expr::Value(Value::Number(Number::UInt(2))));
+        assert_eq!(map[2].1, Expr::List(vec![Expr::Variable("a".to_string())]));
+    }
+
+    #[test]
+    fn test_parse_empty_map() {
+        let (remaining, map) = parse_map(full_span("{::}")).unwrap();
+        assert_eq!(*remaining.fragment(), "");
+        assert!(map.is_empty());
+    }
 }
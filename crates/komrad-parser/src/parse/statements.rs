@@ -2,6 +2,8 @@
 use crate::parse::expressions::parse_expression;
 use crate::parse::handlers::parse_handler_statement;
 use crate::parse::lines::{parse_blank_line, parse_comment};
+use crate::parse::try_catch::parse_try_statement;
+use crate::parse::while_loop::parse_while_statement;
 use crate::parse::{fields, identifier};
 use crate::span::{KResult, Span};
 use komrad_ast::prelude::Statement;
@@ -14,6 +16,8 @@ use nom::Parser;
 /// Parse a single statement: possible forms are:
 /// - "IDENT: Type = expression" (field)
 /// - "[pattern] { ... }" (handler)
+/// - "try { ... } catch _err { ... }" (try)
+/// - "while { ... } { ... }" (while)
 /// - "IDENT = expression" (assignment)
 /// - expression alone
 /// - blank lines
@@ -24,6 +28,8 @@ pub fn parse_statement(input: Span) -> KResult<Statement> {
 
     let (remaining, statement) = alt((
         fields::parse_field_definition,
+        parse_try_statement,
+        parse_while_statement,
         parse_assignment_statement,
         parse_handler_statement,
         parse_expander_statement,
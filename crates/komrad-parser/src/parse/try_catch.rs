@@ -0,0 +1,75 @@
+use crate::parse::block::parse_block;
+use crate::parse::identifier::parse_identifier;
+use crate::span::{KResult, Span};
+use komrad_ast::prelude::Statement;
+use nom::Parser;
+use nom::bytes::complete::tag;
+use nom::character::complete::{multispace0, space0, space1};
+use nom::sequence::preceded;
+
+/// Parse a try statement:
+///
+/// ```komrad
+/// try {
+///     x = 1 / 0
+/// } catch _err {
+///     Io println err.message
+/// }
+/// ```
+pub fn parse_try_statement(input: Span) -> KResult<Statement> {
+    (
+        preceded((tag("try"), space0), parse_block),
+        preceded(
+            (multispace0, tag("catch"), space1, tag("_")),
+            parse_identifier,
+        ),
+        preceded(space0, parse_block),
+    )
+        .map(|(body, name, handler)| Statement::Try(body, name, handler))
+        .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::statements::parse_statement;
+    use crate::parse::strings::test_parse_string::full_span;
+    use komrad_ast::prelude::{Block, CallExpr, Expr};
+
+    #[test]
+    fn test_parse_try_statement() {
+        let input = full_span(
+            r#"try {
+    me fail
+} catch _err {
+    Io println err
+}"#,
+        );
+        let (remaining, statement) = parse_statement(input).unwrap();
+        assert_eq!(*remaining.fragment(), "");
+        assert_eq!(
+            statement,
+            Statement::Try(
+                Block::new(vec![Statement::Expr(Expr::Call(CallExpr::new(
+                    Expr::Variable("me".into()),
+                    vec![Expr::Variable("fail".into()).into()],
+                )))]),
+                "err".to_string(),
+                Block::new(vec![Statement::Expr(Expr::Call(CallExpr::new(
+                    Expr::Variable("Io".into()),
+                    vec![
+                        Expr::Variable("println".into()).into(),
+                        Expr::Variable("err".into()).into(),
+                    ],
+                )))]),
+            )
+        );
+    }
+
+    #[test]
+    fn test_try_prefixed_identifier_is_not_a_try() {
+        let input = full_span("try_count = 1");
+        let (_, statement) = parse_statement(input).unwrap();
+        assert!(statement.is_assignment());
+    }
+}
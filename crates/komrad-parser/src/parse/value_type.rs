@@ -10,6 +10,7 @@ pub fn parse_value_type(input: Span) -> KResult<ValueType> {
         "Error" => ValueType::Error,
         "Word" => ValueType::Word,
         "List" => ValueType::List,
+        "Map" => ValueType::Map,
         "Channel" => ValueType::Channel,
         "Boolean" => ValueType::Boolean,
         "String" => ValueType::String,
@@ -0,0 +1,67 @@
+use crate::parse::block::parse_block;
+use crate::span::{KResult, Span};
+use komrad_ast::prelude::Statement;
+use nom::Parser;
+use nom::bytes::complete::tag;
+use nom::character::complete::space0;
+use nom::sequence::preceded;
+
+/// Parse a while statement:
+///
+/// ```komrad
+/// while { x < 16 } {
+///     me fizzbuzz x
+///     x = x + 1
+/// }
+/// ```
+pub fn parse_while_statement(input: Span) -> KResult<Statement> {
+    (
+        preceded((tag("while"), space0), parse_block),
+        preceded(space0, parse_block),
+    )
+        .map(|(condition, body)| Statement::While(condition, body))
+        .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::statements::parse_statement;
+    use crate::parse::strings::test_parse_string::full_span;
+    use komrad_ast::prelude::{BinaryExpr, BinaryOp, Block, Expr, Number, Value};
+
+    #[test]
+    fn test_parse_while_statement() {
+        let input = full_span(
+            r#"while { x < 3 } {
+    x = x + 1
+}"#,
+        );
+        let (remaining, statement) = parse_statement(input).unwrap();
+        assert_eq!(*remaining.fragment(), "");
+        let binary = |op, n| {
+            Expr::Binary(BinaryExpr::new(
+                Expr::Variable("x".into()),
+                op,
+                Expr::Value(Value::Number(Number::Int(n))),
+            ))
+        };
+        assert_eq!(
+            statement,
+            Statement::While(
+                Block::new(vec![Statement::Expr(binary(BinaryOp::Lt, 3))]),
+                Block::new(vec![Statement::Assignment(
+                    "x".into(),
+                    binary(BinaryOp::Add, 1)
+                )]),
+            )
+        );
+    }
+
+    #[test]
+    fn test_while_prefixed_identifier_is_not_a_while() {
+        let input = full_span("while_count = 1");
+        let (_, statement) = parse_statement(input).unwrap();
+        assert!(statement.is_assignment());
+    }
+}
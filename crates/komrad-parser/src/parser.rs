@@ -1,26 +1,58 @@
+use crate::cst::SyntaxTree;
 use crate::module_builder::ModuleBuilder;
+use crate::module_loader::ImportError;
 use crate::parse::block::parse_block_statements;
 use crate::span::{KResult, Span};
-use komrad_ast::prelude::Statement;
-use miette::{NamedSource, Report};
+use komrad_ast::prelude::{ParserError, Statement};
+use miette::{Diagnostic, Report};
+use nom::Parser;
 use nom::bytes::complete::tag;
 use nom::character::complete::{multispace0, space0};
 use nom::combinator::all_consuming;
 use nom::sequence::{delimited, separated_pair};
-use nom::{Finish, Parser};
-use std::sync::Arc;
+use std::path::Path;
+use thiserror::Error;
 
 pub fn parse_verbose(input: &str) -> Result<ModuleBuilder, Report> {
-    // Create a full source context for error reporting.
-    let full_src = Arc::new(NamedSource::new("repl.kom", input.to_string()));
-    let span = Span::new_extra(input, full_src);
-
-    match all_consuming(parse_module).parse(span).finish() {
-        Ok((_remaining, module)) => Ok(module),
+    parse_named("repl.kom", input)
+}
 
-        // If the parser yields an error, we already have a `ParseError`.
-        Err(e) => Err(Report::new(e)),
+/// Reads and parses a file, recording its name and path on the module
+/// so that imports can be resolved relative to it.
+pub fn parse_file(path: &Path) -> Result<ModuleBuilder, Report> {
+    let source = std::fs::read_to_string(path).map_err(|source| ImportError::NotFound {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut module = parse_named(&path.display().to_string(), &source)?;
+    if let Some(stem) = path.file_stem() {
+        module.set_name(stem.to_string_lossy().to_string());
     }
+    module.set_source_file(path.to_path_buf());
+    Ok(module)
+}
+
+fn parse_named(name: &str, input: &str) -> Result<ModuleBuilder, Report> {
+    parse_source(name, input).map_err(Report::new)
+}
+
+/// Every syntax error in a source, and the module made of the statements
+/// that did parse.
+#[derive(Debug, Error, Diagnostic)]
+#[error("Found {} syntax error{}", .errors.len(), if .errors.len() == 1 { "" } else { "s" })]
+pub struct ParseErrors {
+    #[related]
+    pub errors: Vec<ParserError>,
+    pub module: ModuleBuilder,
+}
+
+/// Parses a named source, keeping the `ParserError`s (and their spans)
+/// rather than wrapping them in a report. Editors use this for diagnostics.
+///
+/// The source goes through the lossless syntax tree first, which is what
+/// lets comments trail statements and sit inside patterns.
+pub fn parse_source(name: &str, input: &str) -> Result<ModuleBuilder, ParseErrors> {
+    SyntaxTree::parse(input).to_module(name)
 }
 
 pub fn parse_module(input: Span) -> KResult<ModuleBuilder> {
@@ -29,8 +61,8 @@ pub fn parse_module(input: Span) -> KResult<ModuleBuilder> {
     let (remaining, statements) =
         all_consuming(delimited(multispace0, parse_block_statements, multispace0)).parse(input)?;
 
-    for statement in statements {
-        builder.add_statement(statement);
+    for (statement, location) in statements {
+        builder.add_located_statement(statement, Some(location));
     }
 
     Ok((remaining, builder))
@@ -190,4 +222,83 @@ agent Alice {
             )))
         );
     }
+
+    #[test]
+    fn test_statement_locations() {
+        let source = "x = 1\n\nagent Alice {\n  [foo] {\n    Io println x\n  }\n}\n";
+        let module = parse_source("alice.kom", source).unwrap();
+        let block = module.build_block();
+
+        let location = block.location(0).unwrap();
+        assert_eq!(location.to_string(), "alice.kom:1");
+        assert_eq!(
+            &source[location.span().offset()..][..location.span().len()],
+            "x = 1"
+        );
+
+        // Nested blocks keep the locations of their own statements
+        let Statement::Expr(Expr::Call(call)) = &block.statements()[1] else {
+            panic!("expected an agent definition");
+        };
+        let Expr::Block(agent_block) = call.args()[1].as_ref() else {
+            panic!("expected the agent's block");
+        };
+        let Statement::Handler(handler) = &agent_block.statements()[0] else {
+            panic!("expected a handler");
+        };
+        let location = handler.block().location(0).unwrap();
+        assert_eq!(location.to_string(), "alice.kom:5");
+
+        // ...but locations never affect equality
+        assert_eq!(block, Block::new(block.statements().clone()));
+    }
+
+    #[test]
+    fn test_comments_anywhere() {
+        let commented = parse_source(
+            "test.kom",
+            "agent Greeter { // the agent\n\t[greet // who\n\t\t_name] {\n\t\tx = [1 // one\n\t\t\t2]\n\t\tIo println name // say it\n\t}\n}\n",
+        )
+        .unwrap();
+        let plain = parse_source(
+            "test.kom",
+            "agent Greeter {\n\t[greet _name] {\n\t\tx = [1 2]\n\t\tIo println name\n\t}\n}\n",
+        )
+        .unwrap();
+        assert_eq!(commented.build_block(), plain.build_block());
+    }
+
+    #[test]
+    fn test_recovers_from_errors() {
+        let source = "x = 1\ny = = 2\n\nagent Alice {\n\t[greet _name] {\n\t\tIo println \"hi \" +\n\t}\n\n\t[ok] {\n\t\tIo println \"ok\"\n\t}\n}\n";
+        let err = parse_source("test.kom", source).unwrap_err();
+
+        let lines: Vec<usize> = err
+            .errors
+            .iter()
+            .map(|e| source[..e.span.offset()].matches('\n').count() + 1)
+            .collect();
+        assert_eq!(lines, vec![2, 6]);
+
+        // The rest of the module survives. Only the broken line is dropped
+        // from the handler it's in.
+        let statements = err.module.statements();
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].is_assignment());
+        let Statement::Expr(Expr::Call(call)) = &statements[1] else {
+            panic!("expected the agent definition");
+        };
+        let Expr::Block(block) = call.args()[1].as_ref() else {
+            panic!("expected the agent's block");
+        };
+        let handlers: Vec<usize> = block
+            .statements()
+            .iter()
+            .map(|statement| match statement {
+                Statement::Handler(handler) => handler.block().statements().len(),
+                _ => panic!("expected a handler"),
+            })
+            .collect();
+        assert_eq!(handlers, vec![0, 1]);
+    }
 }
@@ -1,6 +1,6 @@
-use komrad_ast::prelude::ParserError;
+use komrad_ast::prelude::{ParserError, SourceLocation};
 use miette::NamedSource;
-use nom::IResult;
+use nom::{IResult, Parser};
 use nom_locate::LocatedSpan;
 use std::sync::Arc;
 
@@ -18,3 +18,16 @@ pub fn new_span(input: &str) -> Span {
 pub fn empty_span() -> Span<'static> {
     Span::new_extra("", Arc::new(NamedSource::new("source", "".to_string())))
 }
+
+/// Runs `parser` and pairs its output with the source it consumed.
+pub fn located<'a, O>(
+    mut parser: impl Parser<Span<'a>, Output = O, Error = ParserError>,
+) -> impl FnMut(Span<'a>) -> KResult<'a, (O, SourceLocation)> {
+    move |input: Span<'a>| {
+        let start = input.location_offset();
+        let src = input.extra.clone();
+        let (remaining, output) = parser.parse(input)?;
+        let len = remaining.location_offset() - start;
+        Ok((remaining, (output, SourceLocation::new(src, start, len))))
+    }
+}
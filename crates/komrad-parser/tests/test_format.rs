@@ -0,0 +1,114 @@
+use komrad_ast::format::ToKomrad;
+use komrad_parser::cst::SyntaxTree;
+use komrad_parser::{parse_file, parse_source};
+use std::path::{Path, PathBuf};
+
+fn kom_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            kom_files(&path, files);
+        } else if path.extension().is_some_and(|ext| ext == "kom") {
+            files.push(path);
+        }
+    }
+}
+
+#[test]
+fn test_examples_round_trip() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut files = Vec::new();
+    kom_files(&examples, &mut files);
+    assert!(!files.is_empty());
+
+    for path in files {
+        let module = parse_file(&path).unwrap_or_else(|err| panic!("{:?}", err));
+        let formatted = module.to_komrad();
+
+        let name = path.display().to_string();
+        let reparsed = parse_source(&name, &formatted).unwrap_or_else(|err| {
+            panic!("{} no longer parses:\n{:?}", name, miette::Report::new(err))
+        });
+        assert_eq!(
+            reparsed.build_block(),
+            module.build_block(),
+            "{} changed when formatted",
+            name
+        );
+
+        // Formatting is idempotent
+        assert_eq!(reparsed.to_komrad(), formatted, "{} is not stable", name);
+    }
+}
+
+#[test]
+fn test_examples_are_lossless() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut files = Vec::new();
+    kom_files(&examples, &mut files);
+
+    for path in files {
+        let source = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(SyntaxTree::parse(&source).text(), source);
+    }
+}
+
+#[test]
+fn test_format_is_canonical() {
+    let source = "agent  Alice {\n  name:String=\"Alice\"\n\n\n  [greet   _(n:Number)] {\n    Io println 'hi ' + name\n    // done\n  }\n}\n";
+    let module = parse_source("alice.kom", source).unwrap();
+    assert_eq!(
+        module.to_komrad(),
+        "agent Alice {\n\tname: String = \"Alice\"\n\n\t[greet _(n:Number)] {\n\t\tIo println \"hi \" + name\n\t\t// done\n\t}\n}\n"
+    );
+}
+
+#[test]
+fn test_examples_format_from_syntax_tree() {
+    let examples = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../examples");
+    let mut files = Vec::new();
+    kom_files(&examples, &mut files);
+
+    for path in files {
+        let name = path.display().to_string();
+        let source = std::fs::read_to_string(&path).unwrap();
+        let tree = SyntaxTree::parse(&source);
+        let formatted = tree.format();
+
+        let reparsed = parse_source(&name, &formatted).unwrap_or_else(|err| {
+            panic!("{} no longer parses:\n{:?}", name, miette::Report::new(err))
+        });
+        let module = tree.to_module(&name).unwrap();
+        assert_eq!(
+            reparsed.build_block(),
+            module.build_block(),
+            "{} changed when formatted",
+            name
+        );
+
+        let formatted_tree = SyntaxTree::parse(&formatted);
+        let comments = |tree: &SyntaxTree| -> Vec<String> {
+            tree.comments()
+                .iter()
+                .map(|comment| comment.text.trim_end().to_string())
+                .collect()
+        };
+        assert_eq!(
+            comments(&formatted_tree),
+            comments(&tree),
+            "{} lost comments",
+            name
+        );
+        assert_eq!(formatted_tree.format(), formatted, "{} is not stable", name);
+    }
+}
+
+#[test]
+fn test_format_keeps_comments() {
+    let source =
+        "x = 1   // one\n[main // entry\n  _(n:Number)] {\n  Io println x // show it\n} // main\n";
+    assert_eq!(
+        SyntaxTree::parse(source).format(),
+        "x = 1 // one\n[main // entry\n\t_(n:Number)] {\n\tIo println x // show it\n} // main\n"
+    );
+}
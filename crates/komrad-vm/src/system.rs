@@ -4,10 +4,12 @@ use komrad_agents::prelude::{DynamicAgent, RegistryAgent};
 use komrad_ast::prelude::{Block, Channel};
 use komrad_ast::scope::Scope;
 use std::sync::Arc;
+use std::time::Duration;
 
 // system.rs
 pub struct System {
     agents: DashMap<String, Arc<DynamicAgent>>,
+    reply_timeout: Option<Duration>,
     shutdown_token: tokio_util::sync::CancellationToken,
 }
 
@@ -15,16 +17,23 @@ impl System {
     pub fn new() -> Self {
         Self {
             agents: DashMap::new(),
+            reply_timeout: None,
             shutdown_token: tokio_util::sync::CancellationToken::new(),
         }
     }
 
     pub async fn create_agent(&self, name: &str, block: &Block) -> Channel {
-        let registry = RegistryAgent::new();
-        let registry_channel = registry.clone().spawn();
+        let (agent, chan) = self
+            .run(async {
+                let registry = RegistryAgent::new();
+                let registry_channel = registry.clone().spawn();
 
-        let agent = DynamicAgent::from_block(name, block, Scope::new(), registry_channel).await;
-        let chan = agent.clone().spawn();
+                let agent =
+                    DynamicAgent::from_block(name, block, Scope::new(), registry_channel).await;
+                let chan = agent.clone().spawn();
+                (agent, chan)
+            })
+            .await;
 
         // Make sure the agent is fully initialized
         // TODO: is there a better way to do this?
@@ -34,6 +43,25 @@ impl System {
         chan
     }
 
+    /// Sets how long calls wait for a reply before failing with
+    /// `RuntimeError::Timeout`, unless the call gives its own (`foo bar @5s`).
+    /// `None` waits forever. The setting applies to the agents this system
+    /// creates from then on, and to every agent they spawn.
+    pub fn set_reply_timeout(&mut self, timeout: Option<Duration>) {
+        self.reply_timeout = timeout;
+    }
+
+    /// Runs `task` as one of this system's agents would, with its reply
+    /// timeout.
+    pub async fn run<F: Future>(&self, task: F) -> F::Output {
+        Channel::with_reply_timeout(self.reply_timeout, task).await
+    }
+
+    /// Looks up an agent created by this system.
+    pub fn agent(&self, name: &str) -> Option<Arc<DynamicAgent>> {
+        self.agents.get(name).map(|agent| agent.value().clone())
+    }
+
     pub async fn shutdown(&self) {
         for agent in self.agents.clone().iter() {
             agent.value().stop().await;
@@ -1,127 +1,79 @@
-use komrad_ast::prelude::{BinaryExpr, BinaryOp, Expr, Number, Statement, Value};
-use komrad_vm::{ModuleCommand, System};
-use tokio::time::{Duration, sleep};
+use komrad_ast::prelude::{BinaryExpr, BinaryOp, Block, Expr, Number, Statement, Value};
+use komrad_vm::System;
 
-#[tokio::test]
-async fn test_module_assignment_and_retrieval() {
-    let system = System::spawn().await;
-    let module = system.create_module("test_module").await;
-
-    // Assignment: x = 100
-    let assign_x = Statement::Assignment(
-        "x".to_string(),
-        Expr::Value(Value::Number(Number::from(100.0))),
-    );
-
-    module
-        .send_command(ModuleCommand::ExecuteStatement(assign_x))
-        .await;
+fn assign(name: &str, expr: Expr) -> Statement {
+    Statement::Assignment(name.to_string(), expr)
+}
 
-    sleep(Duration::from_millis(1)).await;
+fn number(n: f64) -> Expr {
+    Expr::Value(Value::Number(Number::from(n)))
+}
 
-    if let Some(scope) = module.get_scope().await {
-        let x = scope.get("x").await;
-        assert_eq!(x, Some(Value::Number(Number::from(100.0))));
-    } else {
-        panic!("Scope was inaccessible");
-    }
+async fn binding(system: &System, agent: &str, name: &str) -> Option<Value> {
+    let agent = system.agent(agent).expect("agent was created");
+    agent
+        .bindings()
+        .await
+        .into_iter()
+        .find(|(binding, _)| binding == name)
+        .map(|(_, value)| value)
 }
 
 #[tokio::test]
-async fn test_module_modify_scope_directly() {
-    let system = System::spawn().await;
-    let module = system.create_module("scope_test_module").await;
-
-    module
-        .send_command(ModuleCommand::ModifyScope {
-            key: "y".to_string(),
-            value: Value::Number(Number::from(25.0)),
-        })
-        .await;
-
-    sleep(Duration::from_millis(1)).await;
+async fn test_module_assignment_and_retrieval() {
+    let system = System::new();
+    // Assignment: x = 100
+    let block = Block::new(vec![assign("x", number(100.0))]);
+    system.create_agent("test_module", &block).await;
 
-    if let Some(scope) = module.get_scope().await {
-        let y = scope.get("y").await;
-        assert_eq!(y, Some(Value::Number(Number::from(25.0))));
-    } else {
-        panic!("Scope was inaccessible");
-    }
+    assert_eq!(
+        binding(&system, "test_module", "x").await,
+        Some(Value::Number(Number::from(100.0)))
+    );
 }
 
 #[tokio::test]
 async fn test_module_binary_operation_execution() {
-    let system = System::spawn().await;
-    let module = system.create_module("binary_op_module").await;
-
-    // Set initial values
-    module
-        .send_command(ModuleCommand::ModifyScope {
-            key: "a".to_string(),
-            value: Value::Number(Number::from(10.0)),
-        })
-        .await;
-
-    module
-        .send_command(ModuleCommand::ModifyScope {
-            key: "b".to_string(),
-            value: Value::Number(Number::from(20.0)),
-        })
-        .await;
-
-    // Perform binary addition: c = a + b
-    let assign_c = Statement::Assignment(
-        "c".to_string(),
-        Expr::Binary(BinaryExpr {
-            left: Box::new(Expr::Variable("a".to_string())),
-            op: BinaryOp::Add,
-            right: Box::new(Expr::Variable("b".to_string())),
-        }),
+    let system = System::new();
+    // c = a + b
+    let block = Block::new(vec![
+        assign("a", number(10.0)),
+        assign("b", number(20.0)),
+        assign(
+            "c",
+            Expr::Binary(BinaryExpr::new(
+                Expr::Variable("a".to_string()),
+                BinaryOp::Add,
+                Expr::Variable("b".to_string()),
+            )),
+        ),
+    ]);
+    system.create_agent("binary_op_module", &block).await;
+
+    assert_eq!(
+        binding(&system, "binary_op_module", "c").await,
+        Some(Value::Number(Number::from(30.0)))
     );
-
-    module
-        .send_command(ModuleCommand::ExecuteStatement(assign_c))
-        .await;
-
-    sleep(Duration::from_millis(1)).await;
-
-    if let Some(scope) = module.get_scope().await {
-        let c = scope.get("c").await;
-        assert_eq!(c, Some(Value::Number(Number::from(30.0))));
-    } else {
-        panic!("Scope was inaccessible");
-    }
 }
 
 #[tokio::test]
 async fn test_system_module_lookup() {
-    let system = System::spawn().await;
-    let module = system.create_module("lookup_module").await;
+    let system = System::new();
+    system
+        .create_agent("lookup_module", &Block::new(vec![]))
+        .await;
 
-    let fetched_module = system.get_module_by_id(&module.id);
-    assert!(fetched_module.is_some());
-    assert_eq!(fetched_module.unwrap().name, "lookup_module");
+    assert!(system.agent("lookup_module").is_some());
+    assert!(system.agent("missing_module").is_none());
 }
 
 #[tokio::test]
 async fn test_module_stop_command() {
-    let system = System::spawn().await;
-    let module = system.create_module("stop_module").await;
-
-    module.send_command(ModuleCommand::Stop).await;
-    sleep(Duration::from_millis(1)).await;
-
-    // Since the module is stopped, commands should fail silently or log warnings
-    module
-        .send_command(ModuleCommand::ModifyScope {
-            key: "should_fail".to_string(),
-            value: Value::Number(Number::from(999.0)),
-        })
+    let system = System::new();
+    system
+        .create_agent("stop_module", &Block::new(vec![]))
         .await;
 
-    sleep(Duration::from_millis(1)).await;
-
-    if let Some(scope) = module.get_scope().await {
-        panic!("Scope should be empty after module stop: {:?}", scope);
-    }
+    system.shutdown().await;
+    assert!(system.agent("stop_module").is_none());
 }
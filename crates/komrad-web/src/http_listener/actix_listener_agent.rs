@@ -187,7 +187,8 @@ impl ActixListenerAgent {
 
         // We spawn Actix in a tokio task so we can run it “in the background.”
         // We must call `actix_web::rt::System::run_in_tokio()` or an equivalent approach.
-        tokio::spawn(async move {
+        let timeout = Channel::default_reply_timeout();
+        tokio::spawn(Channel::with_reply_timeout(timeout, async move {
             // create an actix System
             // Clone stuff to move into HttpServer
             let data_delegate = web::Data::new(delegate);
@@ -222,7 +223,7 @@ impl ActixListenerAgent {
                     error!("Server ended unexpectedly: {:?}", res);
                 }
             }
-        })
+        }))
     }
 }
 
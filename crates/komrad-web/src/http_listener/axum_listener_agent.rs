@@ -46,11 +46,7 @@ fn axum_response_from_komrad(terms: &[Value]) -> http::Response<Body<axum::body:
         Value::List(list_of_4) if list_of_4.len() == 4 => {
             // 1) Extract status code.
             let status_code = if let Value::Number(n) = &list_of_4[0] {
-                let raw = match n {
-                    Number::Int(i) => *i,
-                    Number::UInt(u) => *u as i64,
-                    Number::Float(_) => 200,
-                };
+                let raw = n.to_i64().unwrap_or(200);
                 if raw < 100 || raw > 599 {
                     200
                 } else {
@@ -290,6 +286,8 @@ impl AxumListenerAgent {
 
         info!("AxumListenerAgent is listening on {}", addr);
 
+        // Connections are served with the reply timeout of the system
+        let timeout = Channel::default_reply_timeout();
         loop {
             select! {
                 _ = shutdown_token.cancelled() => {
@@ -301,7 +299,7 @@ impl AxumListenerAgent {
                         Ok((stream, peer_addr)) => {
                             let tower_service = app.clone();
                             let hyper_service = TowerToHyperService::new(tower_service);
-                            tokio::spawn(async move {
+                            tokio::spawn(Channel::with_reply_timeout(timeout, async move {
                                 let io = TokioIo::new(stream);
                                 if let Err(err) = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
                                     .serve_connection(io, hyper_service)
@@ -309,7 +307,7 @@ impl AxumListenerAgent {
                                 {
                                     error!("Error serving connection from {}: {}", peer_addr, err);
                                 }
-                            });
+                            }));
                         }
                         Err(e) => {
                             error!("Failed to accept connection: {}", e);
@@ -353,12 +351,13 @@ impl AxumListenerAgent {
         let shutdown = self.shutdown_token.clone();
         let self_clone = self.clone();
         let cancellation_token = self.shutdown_token.clone();
-        tokio::spawn(async move {
+        let timeout = Channel::default_reply_timeout();
+        tokio::spawn(Channel::with_reply_timeout(timeout, async move {
             self_clone
                 .run_server(host, port_num, delegate_chan, cancellation_token)
                 .await;
             shutdown.cancelled().await;
-        })
+        }))
     }
 }
 
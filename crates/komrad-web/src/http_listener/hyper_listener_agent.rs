@@ -103,6 +103,8 @@ impl HyperListenerAgent {
         info!("Hyper HTTP server listening on http://{}", addr);
         let delegate_value = self.config.delegate.clone();
         let shutdown = self.shutdown_token.clone();
+        // Connections are served with the reply timeout of the system
+        let timeout = Channel::default_reply_timeout();
         loop {
             select! {
                 accept_result = listener.accept() => {
@@ -110,7 +112,7 @@ impl HyperListenerAgent {
                         Ok((stream, _)) => {
                             let io = TokioIo::new(stream);
                             let delegate_value = delegate_value.clone();
-                            tokio::spawn(async move {
+                            tokio::spawn(Channel::with_reply_timeout(timeout, async move {
                                 if let Err(err) = http1::Builder::new()
                                     .serve_connection(io, service_fn(move |req| {
                                         handle_request(req, delegate_value.clone())
@@ -118,7 +120,7 @@ impl HyperListenerAgent {
                                     .await {
                                     error!("Error serving connection: {:?}", err);
                                 }
-                            });
+                            }));
                         },
                         Err(e) => {
                             error!("Failed to accept connection: {:?}", e);
@@ -140,11 +142,12 @@ impl AgentLifecycle for HyperListenerAgent {
     async fn init(self: Arc<Self>, _scope: &mut Scope) {
         debug!("Initializing HyperListenerAgent: {}", self.name);
         let this = self.clone();
-        let handle = tokio::spawn(async move {
+        let timeout = Channel::default_reply_timeout();
+        let handle = tokio::spawn(Channel::with_reply_timeout(timeout, async move {
             if let Err(e) = this.run_server().await {
                 error!("Error in run_server: {:?}", e);
             }
-        });
+        }));
         *self.server_handle.lock().await = Some(handle);
     }
 
@@ -298,7 +301,8 @@ async fn handle_websocket_upgrade(
         .unwrap();
 
     // Spawn a task to handle the upgrade once the connection is upgraded.
-    tokio::spawn(async move {
+    let timeout = Channel::default_reply_timeout();
+    tokio::spawn(Channel::with_reply_timeout(timeout, async move {
         match upgrade_future.await {
             Ok(upgraded) => {
                 let upgraded = TokioIo::new(upgraded);
@@ -323,7 +327,10 @@ async fn handle_websocket_upgrade(
                 } else {
                     // THe onboarding message is expected to be a delegate channel for the connection
                     // to send messages to the delegate.
-                    match ephemeral_onboarding_listener.recv().await {
+                    match ephemeral_onboarding_listener
+                        .recv_timeout(Channel::default_reply_timeout())
+                        .await
+                    {
                         Ok(onboarding_msg) => {
                             let onboarding_terms = onboarding_msg.terms();
                             if onboarding_terms.len() == 1 {
@@ -359,7 +366,7 @@ async fn handle_websocket_upgrade(
                 error!("WebSocket upgrade error: {:?}", e);
             }
         }
-    });
+    }));
 
     // Convert the handshake response body to a BoxBody.
     let converted_response =
@@ -1,7 +1,7 @@
 use crate::config::parse_server_config_from_scope;
 use crate::http_response_agent::HttpResponseAgent;
 use komrad_agent::{Agent, AgentBehavior, AgentFactory, AgentLifecycle};
-use komrad_ast::prelude::{Channel, ChannelListener, Message, Number, Value};
+use komrad_ast::prelude::{Channel, ChannelListener, Message, Value};
 use komrad_ast::scope::Scope;
 use std::net::SocketAddr;
 use std::sync::Arc;
@@ -28,11 +28,7 @@ fn warp_response_from_komrad(terms: &[Value]) -> Response<hyper::Body> {
         Value::List(list_of_4) if list_of_4.len() == 4 => {
             // 1) Extract status code.
             let status_code = if let Value::Number(n) = &list_of_4[0] {
-                let raw = match n {
-                    Number::Int(i) => *i,
-                    Number::UInt(u) => *u as i64,
-                    Number::Float(_) => 200,
-                };
+                let raw = n.to_i64().unwrap_or(200);
                 if raw < 100 || raw > 599 {
                     error!("Invalid status code: {}", raw);
                     200
@@ -240,7 +236,10 @@ impl WarpListenerAgent {
                 warp_shutdown.cancelled().await;
             });
         warn!("Starting Warp HTTP server at http://{}", addr);
-        tokio::spawn(server)
+        tokio::spawn(Channel::with_reply_timeout(
+            Channel::default_reply_timeout(),
+            server,
+        ))
     }
 }
 
@@ -156,7 +156,7 @@ impl HttpRequestAgent {
                         .headers
                         .iter()
                         .map(|(k, v)| {
-                            Value::List(vec![
+                            Value::from(vec![
                                 Value::String(k.to_string()),
                                 Value::String(v.to_str().unwrap_or("").to_string()),
                             ])
@@ -178,7 +178,7 @@ impl HttpRequestAgent {
                         .params
                         .iter()
                         .map(|(k, v)| {
-                            Value::List(vec![Value::String(k.clone()), Value::String(v.clone())])
+                            Value::from(vec![Value::String(k.clone()), Value::String(v.clone())])
                         })
                         .collect();
                     Value::List(list)
@@ -197,7 +197,7 @@ impl HttpRequestAgent {
                         .cookies
                         .iter()
                         .map(|(k, v)| {
-                            Value::List(vec![Value::String(k.clone()), Value::String(v.clone())])
+                            Value::from(vec![Value::String(k.clone()), Value::String(v.clone())])
                         })
                         .collect();
                     Value::List(list)
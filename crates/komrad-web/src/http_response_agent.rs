@@ -123,22 +123,22 @@ impl HttpResponseAgent {
             let headers_val = {
                 let mut hv = vec![];
                 for (k, v) in &st.headers {
-                    hv.push(Value::List(vec![
+                    hv.push(Value::from(vec![
                         Value::String(k.clone()),
                         Value::String(v.clone()),
                     ]));
                 }
-                Value::List(hv)
+                Value::List(hv.into())
             };
             let cookies_val = {
                 let mut cv = vec![];
                 for (n, val) in &st.cookies {
-                    cv.push(Value::List(vec![
+                    cv.push(Value::from(vec![
                         Value::String(n.clone()),
                         Value::String(val.clone()),
                     ]));
                 }
-                Value::List(cv)
+                Value::List(cv.into())
             };
             let body_val = Value::Bytes(st.body.clone());
 
@@ -336,12 +336,9 @@ impl AgentBehavior for HttpResponseAgent {
             }
             "set-status" => {
                 if let Some(Value::Number(n)) = terms.get(1) {
-                    let status = match n {
-                        Number::Int(i) => *i as u16,
-                        Number::UInt(u) => *u as u16,
-                        Number::Float(f) => *f as u16,
-                    };
-                    self.set_status(status);
+                    if let Some(status) = n.to_u64() {
+                        self.set_status(status as u16);
+                    }
                 }
             }
             "set-header" => {
@@ -3,7 +3,7 @@ pub(crate) use crate::request::full;
 use bytes::Bytes;
 use http::{Response, StatusCode};
 use http_body_util::combinators::BoxBody;
-use komrad_ast::prelude::{Number, Value};
+use komrad_ast::prelude::Value;
 
 /// Converts a final 4-element Komrad response to an HTTP Response:
 /// Expected format: [status, headers, cookies, body]
@@ -17,11 +17,7 @@ pub fn build_hyper_response_from_komrad(terms: &[Value]) -> Response<BoxBody<Byt
     }
 
     let status_code = match &terms[0] {
-        Value::Number(n) => match n {
-            Number::Int(i) => *i as u16,
-            Number::UInt(i) => *i as u16,
-            Number::Float(f) => *f as u16,
-        },
+        Value::Number(n) => n.to_u64().map_or(200, |u| u as u16),
         _ => 200,
     };
 
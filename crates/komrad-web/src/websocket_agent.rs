@@ -54,9 +54,10 @@ impl AgentLifecycle for WebSocketAgent {
         // Spawn the read loop task.
         let this = self.clone();
         let cancellation_token = self.cancellation_token.clone();
-        tokio::spawn(async move {
+        let timeout = Channel::default_reply_timeout();
+        tokio::spawn(Channel::with_reply_timeout(timeout, async move {
             this.read_loop(cancellation_token).await;
-        });
+        }));
     }
 
     async fn get_scope(&self) -> Arc<Mutex<Scope>> {